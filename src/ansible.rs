@@ -0,0 +1,518 @@
+use crate::*;
+use serde_yaml::{Mapping, Value};
+
+type Vars = BTreeMap<String, String>;
+
+#[derive(Default)]
+struct Group {
+    hosts: Vec<(String, Vars)>,
+    vars: Vars,
+    children: Vec<String>,
+}
+
+// Groups in the order they appear in the file.
+#[derive(Default)]
+struct Inventory {
+    groups: Vec<(String, Group)>,
+}
+
+impl Inventory {
+    fn group(&mut self, name: &str) -> &mut Group {
+        let i = match self.groups.iter().position(|(group, _)| group == name) {
+            Some(i) => i,
+            None => {
+                self.groups.push((name.to_string(), Group::default()));
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[i].1
+    }
+
+    fn get(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|(group, _)| group == name).map(|(_, group)| group)
+    }
+}
+
+// Implicit groups every host belongs to; they do not become a group_name.
+const IMPLICIT_GROUPS: [&str; 2] = ["all", "ungrouped"];
+
+// INI inventories such as /etc/ansible/hosts often have no extension, so the content has to look like one too.
+pub struct IniInventory;
+
+impl import::Importer for IniInventory {
+    fn detect(&self, path: &Path, text: &str) -> bool {
+        path.is_file() && matches!(import::extension(path).as_str(), "" | "ini" | "cfg") && looks_like_ini(text)
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let text = fs::read_to_string(path).map_err(|text| format!("{}: {}", path.display(), text))?;
+        Ok(resolve(parse_ini(&text)))
+    }
+}
+
+pub struct YamlInventory;
+
+impl import::Importer for YamlInventory {
+    fn detect(&self, path: &Path, _text: &str) -> bool {
+        matches!(import::extension(path).as_str(), "yml" | "yaml")
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let text = fs::read_to_string(path).map_err(|text| format!("{}: {}", path.display(), text))?;
+        let value: Value = serde_yaml::from_str(&text).map_err(|text| format!("{}: {}", path.display(), text))?;
+        let Value::Mapping(groups) = value else {
+            return Err(format!("{} is not an Ansible inventory.", path.display()));
+        };
+        let mut inventory = Inventory::default();
+        for (name, group) in &groups {
+            parse_yaml_group(&mut inventory, &scalar(name).unwrap_or_default(), group, None);
+        }
+        Ok(resolve(inventory))
+    }
+}
+
+// Every line is a [section], a "key = value" variable, or a host or group name followed by key=value variables only.
+// This tells inventories apart from other files without an extension, such as ~/.ssh/config.
+fn looks_like_ini(text: &str) -> bool {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';'))
+        .peekable();
+    lines.peek().is_some()
+        && lines.all(|line| {
+            (line.starts_with('[') && line.ends_with(']'))
+                || line.split_once('=').is_some_and(|(key, _)| !key.trim().contains(char::is_whitespace))
+                || shlex::split(line).is_some_and(|words| words.iter().skip(1).all(|word| word.contains('=')))
+        })
+}
+
+// [group], [group:vars] and [group:children] sections; hosts before the first section are ungrouped.
+fn parse_ini(text: &str) -> Inventory {
+    let mut inventory = Inventory::default();
+    let mut section = ("ungrouped".to_string(), "hosts".to_string());
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = match name.split_once(':') {
+                Some((group, kind)) => (group.to_string(), kind.to_string()),
+                None => (name.to_string(), "hosts".to_string()),
+            };
+            inventory.group(&section.0);
+            continue;
+        }
+        let words = shlex::split(line).unwrap_or_else(|| line.split_whitespace().map(str::to_string).collect());
+        let Some(first) = words.first() else {
+            continue;
+        };
+        match section.1.as_str() {
+            "vars" => {
+                if let Some((key, value)) = line.split_once('=') {
+                    let value = shlex::split(value).map(|words| words.join(" ")).unwrap_or(value.trim().to_string());
+                    inventory.group(&section.0).vars.insert(key.trim().to_string(), value);
+                }
+            }
+            "children" => {
+                inventory.group(first);
+                inventory.group(&section.0).children.push(first.clone());
+            }
+            _ => {
+                let vars: Vars = words[1..]
+                    .iter()
+                    .filter_map(|word| word.split_once('='))
+                    .map(|(key, value)| (key.to_string(), value.to_string()))
+                    .collect();
+                for host in expand_range(first) {
+                    inventory.group(&section.0).hosts.push((host, vars.clone()));
+                }
+            }
+        }
+    }
+    inventory
+}
+
+fn parse_yaml_group(inventory: &mut Inventory, name: &str, value: &Value, parent: Option<&str>) {
+    if let Some(parent) = parent {
+        inventory.group(parent).children.push(name.to_string());
+    }
+    inventory.group(name);
+    let Value::Mapping(group) = value else {
+        return;
+    };
+    if let Some(Value::Mapping(hosts)) = group.get("hosts") {
+        for (host, host_vars) in hosts {
+            let vars = scalar_map(host_vars);
+            for host in expand_range(&scalar(host).unwrap_or_default()) {
+                inventory.group(name).hosts.push((host, vars.clone()));
+            }
+        }
+    }
+    if let Some(vars) = group.get("vars") {
+        inventory.group(name).vars = scalar_map(vars);
+    }
+    if let Some(Value::Mapping(children)) = group.get("children") {
+        for (child, child_group) in children {
+            parse_yaml_group(inventory, &scalar(child).unwrap_or_default(), child_group, Some(name));
+        }
+    }
+}
+
+pub fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    }
+}
+
+fn scalar_map(value: &Value) -> Vars {
+    let Value::Mapping(map) = value else {
+        return Vars::new();
+    };
+    map.iter()
+        .filter_map(|(key, value)| Some((scalar(key)?, scalar(value)?)))
+        .collect()
+}
+
+// Expands host ranges such as web[01:03].example.com or db-[a:c], with an optional step: [1:9:2].
+fn expand_range(host: &str) -> Vec<String> {
+    let Some((prefix, rest)) = host.split_once('[') else {
+        return vec![host.to_string()];
+    };
+    let Some((range, suffix)) = rest.split_once(']') else {
+        return vec![host.to_string()];
+    };
+    let parts: Vec<&str> = range.split(':').collect();
+    let (start, end) = match parts[..] {
+        [start, end] | [start, end, _] => (start, end),
+        _ => return vec![host.to_string()],
+    };
+    let step = parts.get(2).and_then(|step| step.parse::<usize>().ok()).unwrap_or(1).max(1);
+    let values: Vec<String> = match (start.parse::<u64>(), end.parse::<u64>()) {
+        (Ok(first), Ok(last)) => {
+            let width = if start.starts_with('0') { start.len() } else { 0 };
+            (first..=last).step_by(step).map(|number| format!("{:0width$}", number, width = width)).collect()
+        }
+        _ => {
+            let (mut first, mut last) = (start.chars(), end.chars());
+            match (first.next(), first.next(), last.next(), last.next()) {
+                (Some(first), None, Some(last), None) => (first..=last).step_by(step).map(String::from).collect(),
+                _ => return vec![host.to_string()],
+            }
+        }
+    };
+    values
+        .iter()
+        .flat_map(|value| expand_range(&format!("{}{}{}", prefix, value, suffix)))
+        .collect()
+}
+
+// A host listed in several groups gets the variables of all of them, applied like Ansible does: "all" first, then
+// parent groups before their children and groups of the same depth by name, and host variables last. The first group
+// the host is listed in becomes its group_name.
+fn resolve(inventory: Inventory) -> import::Imported {
+    let mut parents: HashMap<&str, &str> = HashMap::new();
+    for (name, group) in &inventory.groups {
+        for child in &group.children {
+            parents.entry(child.as_str()).or_insert(name.as_str());
+        }
+    }
+    let lineage = |group: &str| {
+        let mut groups = vec![group.to_string()];
+        while let Some(&parent) = parents.get(groups[0].as_str()) {
+            if groups.len() >= import::MAX_GROUP_DEPTH || groups.iter().any(|group| group == parent) {
+                break;
+            }
+            groups.insert(0, parent.to_string());
+        }
+        groups.retain(|group| !IMPLICIT_GROUPS.contains(&group.as_str()));
+        groups
+    };
+    // Hosts in the order they first appear, with every group they are listed in.
+    let mut hosts: Vec<(&str, Vec<&str>, Vars)> = vec![];
+    for (name, group) in &inventory.groups {
+        for (host, host_vars) in &group.hosts {
+            let i = match hosts.iter().position(|(other, ..)| other == host) {
+                Some(i) => i,
+                None => {
+                    hosts.push((host, vec![], Vars::new()));
+                    hosts.len() - 1
+                }
+            };
+            hosts[i].1.push(name);
+            hosts[i].2.extend(host_vars.clone());
+        }
+    }
+    let mut imported = import::Imported::default();
+    let mut other_connections = 0;
+    for (host, host_groups, host_vars) in hosts {
+        let host = host.to_string();
+        let mut var_groups: Vec<Vec<String>> = vec![];
+        for group in &host_groups {
+            let groups = lineage(group);
+            for depth in 1..=groups.len() {
+                if !var_groups.iter().any(|other| *other == groups[..depth]) {
+                    var_groups.push(groups[..depth].to_vec());
+                }
+            }
+        }
+        var_groups.sort_by(|a, b| a.len().cmp(&b.len()).then(a.last().cmp(&b.last())));
+        let mut vars = inventory.get("all").map(|group| group.vars.clone()).unwrap_or_default();
+        for groups in &var_groups {
+            if let Some(group) = groups.last().and_then(|name| inventory.get(name)) {
+                vars.extend(group.vars.clone());
+            }
+        }
+        vars.extend(host_vars);
+        // Jinja expressions cannot be evaluated here.
+        let mut templated: Vec<&String> = vars.iter().filter(|(_, value)| value.contains("{{")).map(|(key, _)| key).collect();
+        if !templated.is_empty() {
+            templated.sort();
+            let keys: Vec<&str> = templated.iter().map(|key| key.as_str()).collect();
+            imported.untranslated.push(format!("{}: {}", host, keys.join(", ")));
+        }
+        vars.retain(|_, value| !value.contains("{{"));
+        let var = |keys: &[&str]| keys.iter().find_map(|key| vars.get(*key)).cloned().unwrap_or_default();
+        if !matches!(var(&["ansible_connection"]).as_str(), "" | "ssh" | "smart" | "paramiko") {
+            other_connections += 1;
+            continue;
+        }
+        let mut options = vec![];
+        let key_file = var(&["ansible_ssh_private_key_file", "ansible_private_key_file"]);
+        if !key_file.is_empty() {
+            options.push(format!("-i {}", import::quote(&key_file)));
+        }
+        options.push(var(&["ansible_ssh_common_args"]));
+        options.push(var(&["ansible_ssh_extra_args"]));
+        options.retain(|option| !option.is_empty());
+        let hostname = var(&["ansible_host", "ansible_ssh_host"]);
+        let port = var(&["ansible_port", "ansible_ssh_port"]);
+        let mut connection = import::connection(
+            host.clone(),
+            var(&["ansible_user", "ansible_ssh_user"]),
+            if hostname.is_empty() { host.clone() } else { hostname },
+            if port.is_empty() { "22".to_string() } else { port },
+            options.join(" "),
+        );
+        connection.group_name = group_name(&lineage(host_groups[0]));
+        imported.connections.push(connection);
+    }
+    if other_connections > 0 {
+        imported.notes.push(format!("Skipped hosts with other connection types than SSH: {}", other_connections));
+    }
+    imported
+}
+
+// Exported child groups carry the name of their parent (prod_web under prod), which is left out again on import.
+fn group_name(lineage: &[String]) -> String {
+    let mut names = vec![];
+    for (i, group) in lineage.iter().enumerate() {
+        let short = i
+            .checked_sub(1)
+            .and_then(|parent| group.strip_prefix(&format!("{}_", lineage[parent])))
+            .filter(|short| !short.is_empty());
+        names.push(short.unwrap_or(group).to_string());
+    }
+    names.join("/")
+}
+
+// Inventory names may not contain spaces; group names are limited to letters, digits and underscores.
+fn host_name(connection: &SSHConnection) -> String {
+    connection.server_name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+// Ansible group names are global, so each level is named after the whole path: prod/web becomes prod and prod_web.
+fn group_path(connection: &SSHConnection) -> Vec<String> {
+    let segments: Vec<String> = connection
+        .group_name
+        .split('/')
+        .map(|group| {
+            group.trim().chars().map(|c| if c.is_alphanumeric() || c == '_' { c } else { '_' }).collect::<String>()
+        })
+        .filter(|group| !group.is_empty())
+        .collect();
+    (1..=segments.len()).map(|depth| segments[..depth].join("_")).collect()
+}
+
+fn host_vars(connection: &SSHConnection) -> Vec<(&'static str, String)> {
+    let mut vars = vec![("ansible_host", connection.hostname.clone())];
+    if !connection.username.is_empty() {
+        vars.push(("ansible_user", connection.username.clone()));
+    }
+    if !connection.port.is_empty() {
+        vars.push(("ansible_port", connection.port.clone()));
+    }
+    if !connection.options.is_empty() {
+        vars.push(("ansible_ssh_common_args", connection.options.clone()));
+    }
+    vars
+}
+
+// Hosts without a group come first; each group path (prod/web) becomes nested children sections.
+pub fn write_ini(connections: &[&SSHConnection]) -> String {
+    let host_line = |connection: &SSHConnection| {
+        let mut words = vec![host_name(connection)];
+        for (key, value) in host_vars(connection) {
+            words.push(format!("{}={}", key, import::quote(&value)));
+        }
+        words.join(" ")
+    };
+    let mut lines: Vec<String> = connections
+        .iter()
+        .filter(|connection| group_path(connection).is_empty())
+        .map(|connection| host_line(connection))
+        .collect();
+    let mut groups: Vec<String> = vec![];
+    let mut children: Vec<(String, String)> = vec![];
+    for connection in connections {
+        let path = group_path(connection);
+        for pair in path.windows(2) {
+            let pair = (pair[0].clone(), pair[1].clone());
+            if !children.contains(&pair) {
+                children.push(pair);
+            }
+        }
+        if let Some(group) = path.last().filter(|group| !groups.contains(group)) {
+            groups.push(group.clone());
+        }
+    }
+    for group in &groups {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("[{}]", group));
+        lines.extend(
+            connections
+                .iter()
+                .filter(|connection| group_path(connection).last() == Some(group))
+                .map(|connection| host_line(connection)),
+        );
+    }
+    let mut parents: Vec<&String> = vec![];
+    for (parent, _) in &children {
+        if !parents.contains(&parent) {
+            parents.push(parent);
+        }
+    }
+    for parent in parents {
+        lines.push(String::new());
+        lines.push(format!("[{}:children]", parent));
+        lines.extend(children.iter().filter(|(other, _)| other == parent).map(|(_, child)| child.clone()));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+pub fn write_yaml(connections: &[&SSHConnection]) -> Result<String, String> {
+    let mut all = Mapping::new();
+    for connection in connections {
+        let mut group = &mut all;
+        for name in group_path(connection) {
+            let children = group
+                .entry("children".into())
+                .or_insert(Value::Mapping(Mapping::new()))
+                .as_mapping_mut()
+                .ok_or("invalid inventory")?;
+            group = children
+                .entry(name.into())
+                .or_insert(Value::Mapping(Mapping::new()))
+                .as_mapping_mut()
+                .ok_or("invalid inventory")?;
+        }
+        let mut vars = Mapping::new();
+        for (key, value) in host_vars(connection) {
+            let value = match value.parse::<u16>() {
+                Ok(port) if key == "ansible_port" => Value::from(port),
+                _ => Value::from(value),
+            };
+            vars.insert(key.into(), value);
+        }
+        group
+            .entry("hosts".into())
+            .or_insert(Value::Mapping(Mapping::new()))
+            .as_mapping_mut()
+            .ok_or("invalid inventory")?
+            .insert(host_name(connection).into(), Value::Mapping(vars));
+    }
+    let mut inventory = Mapping::new();
+    inventory.insert("all".into(), Value::Mapping(all));
+    serde_yaml::to_string(&inventory).map_err(|text| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{Importer, fields};
+
+    fn grouped(server_name: &str, group_name: &str, hostname: &str) -> SSHConnection {
+        let mut connection =
+            import::connection(server_name.to_string(), "deploy".to_string(), hostname.to_string(), "22".to_string(), String::new());
+        connection.group_name = group_name.to_string();
+        connection
+    }
+
+    #[test]
+    fn hosts_in_several_groups_get_the_vars_of_all_of_them() {
+        let imported = resolve(parse_ini(
+            "[web]\n\
+             app1 ansible_host=10.0.0.1\n\
+             \n\
+             [db]\n\
+             app1\n\
+             db1 ansible_host=10.0.0.2\n\
+             \n\
+             [web:vars]\n\
+             ansible_user=www\n\
+             \n\
+             [db:vars]\n\
+             ansible_port=5022\n\
+             ansible_user=postgres\n\
+             \n\
+             [all:vars]\n\
+             ansible_ssh_common_args=-o ForwardAgent=yes\n",
+        ));
+        assert_eq!(imported.connections.len(), 2);
+        // db sorts before web, so the web user wins for app1.
+        assert_eq!(fields(&imported.connections[0]), ["app1", "web", "www", "10.0.0.1", "5022", "-o ForwardAgent=yes"]);
+        assert_eq!(fields(&imported.connections[1]), ["db1", "db", "postgres", "10.0.0.2", "5022", "-o ForwardAgent=yes"]);
+    }
+
+    #[test]
+    fn groups_with_the_same_last_segment_stay_apart() {
+        let prod = grouped("web1", "prod/web", "10.0.0.1");
+        let staging = grouped("web1-staging", "staging/web", "10.1.0.1");
+        let connections = [&prod, &staging];
+
+        let ini = write_ini(&connections);
+        assert!(ini.contains("[prod_web]\nweb1 "));
+        assert!(ini.contains("[staging_web]\nweb1-staging "));
+        assert!(ini.contains("[prod:children]\nprod_web\n"));
+        let imported = resolve(parse_ini(&ini));
+        assert_eq!(fields(&imported.connections[0]), ["web1", "prod/web", "deploy", "10.0.0.1", "22", ""]);
+        assert_eq!(fields(&imported.connections[1]), ["web1-staging", "staging/web", "deploy", "10.1.0.1", "22", ""]);
+
+        let yaml: Value = serde_yaml::from_str(&write_yaml(&connections).unwrap()).unwrap();
+        let mut inventory = Inventory::default();
+        for (name, group) in yaml.as_mapping().unwrap() {
+            parse_yaml_group(&mut inventory, &scalar(name).unwrap(), group, None);
+        }
+        let imported = resolve(inventory);
+        assert_eq!(fields(&imported.connections[0]), ["web1", "prod/web", "deploy", "10.0.0.1", "22", ""]);
+        assert_eq!(fields(&imported.connections[1]), ["web1-staging", "staging/web", "deploy", "10.1.0.1", "22", ""]);
+    }
+
+    #[test]
+    fn detects_ini_inventories_by_their_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hosts");
+        fs::write(&path, "").unwrap();
+        assert!(IniInventory.detect(&path, "# inventory\nweb1 ansible_host=10.0.0.1\n\n[db]\ndb1\n\n[db:vars]\nansible_user = postgres\n"));
+        assert!(!IniInventory.detect(&path, "Host web1\n  HostName 10.0.0.1\n  User deploy\n"));
+        assert!(!IniInventory.detect(&path, "Include config.d/*\n"));
+        assert!(!IniInventory.detect(&path, "# only a comment\n"));
+    }
+}
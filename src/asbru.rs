@@ -0,0 +1,147 @@
+use crate::*;
+use serde_yaml::{Mapping, Value};
+
+// Parent of top-level groups and connections.
+const ROOT: &str = "__PAC__ROOT__";
+
+// Asbru Connection Manager (formerly PAC) keeps groups and connections in the "environments" of asbru.yml.
+pub struct Asbru;
+
+impl import::Importer for Asbru {
+    // Ansible inventories use the same extensions but have no top-level "environments" key.
+    fn detect(&self, path: &Path, text: &str) -> bool {
+        matches!(import::extension(path).as_str(), "yml" | "yaml")
+            && text.lines().any(|line| line.trim_end() == "environments:")
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let text = fs::read_to_string(path).map_err(|text| format!("{}: {}", path.display(), text))?;
+        let value: Value = serde_yaml::from_str(&text).map_err(|text| format!("{}: {}", path.display(), text))?;
+        let Some(Value::Mapping(environments)) = value.get("environments") else {
+            return Err(format!("{} has no Asbru connections.", path.display()));
+        };
+        let mut imported = import::Imported::default();
+        let mut other_methods = 0;
+        for (_, entry) in environments {
+            let value = |key: &str| entry.get(key).and_then(ansible::scalar).unwrap_or_default().trim().to_string();
+            if matches!(value("_is_group").as_str(), "1" | "true") || value("ip").is_empty() {
+                continue;
+            }
+            if !value("method").eq_ignore_ascii_case("ssh") {
+                other_methods += 1;
+                continue;
+            }
+            let mut options = vec![];
+            if value("auth type") == "publickey" && !value("public key").is_empty() {
+                options.push(format!("-i {}", import::quote(&value("public key"))));
+            }
+            if !value("jump ip").is_empty() {
+                let user = if value("jump user").is_empty() { String::new() } else { format!("{}@", value("jump user")) };
+                let port = if value("jump port").is_empty() { "22".to_string() } else { value("jump port") };
+                options.push(format!("-J {}{}:{}", user, value("jump ip"), port));
+            }
+            // Extra ssh arguments, such as "-X -o ConnectTimeout=20".
+            options.push(value("options"));
+            options.retain(|option| !option.is_empty());
+            let port = if value("port").is_empty() { "22".to_string() } else { value("port") };
+            let name = if value("name").is_empty() { value("ip") } else { value("name") };
+            let mut connection = import::connection(name, value("user"), value("ip"), port, options.join(" "));
+            connection.group_name = group_path(environments, &value("parent"));
+            connection.notes = value("description");
+            imported.connections.push(connection);
+        }
+        if other_methods > 0 {
+            imported.notes.push(format!("Skipped connections with other methods than SSH: {}", other_methods));
+        }
+        Ok(imported)
+    }
+}
+
+// Names of the parent groups, from the top-level one down.
+fn group_path(environments: &Mapping, parent: &str) -> String {
+    let mut names = vec![];
+    let mut parent = parent.to_string();
+    while parent != ROOT && !parent.is_empty() && names.len() < import::MAX_GROUP_DEPTH {
+        let Some(group) = environments.get(parent.as_str()) else {
+            break;
+        };
+        let value = |key: &str| group.get(key).and_then(ansible::scalar).unwrap_or_default();
+        names.insert(0, value("name").trim().to_string());
+        parent = value("parent");
+    }
+    names.retain(|name| !name.is_empty());
+    names.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{Importer, fields};
+
+    #[test]
+    fn reads_connections_in_nested_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("asbru.yml");
+        let text = r#"---
+config:
+  version: 6.4
+environments:
+  g1:
+    _is_group: 1
+    name: Customers
+    parent: __PAC__ROOT__
+  g2:
+    _is_group: 1
+    name: Acme
+    parent: g1
+  c1:
+    _is_group: 0
+    name: acme-app
+    parent: g2
+    ip: app.acme.test
+    port: 2222
+    user: deploy
+    method: SSH
+    auth type: publickey
+    public key: /home/me/.ssh/acme key
+    options: ' -X -o "ConnectTimeout=20"'
+    description: Main app
+    jump ip: bastion.acme.test
+    jump user: j
+  c2:
+    _is_group: 0
+    name: lab
+    parent: __PAC__ROOT__
+    ip: 192.0.2.10
+    method: SSH
+    auth type: userpass
+    public key: /home/me/.ssh/unused
+  c3:
+    _is_group: 0
+    name: vnc
+    parent: g1
+    ip: 192.0.2.20
+    method: VNC
+"#;
+        fs::write(&path, text).unwrap();
+        assert!(Asbru.detect(&path, text));
+        assert!(!Asbru.detect(&path, "all:\n  hosts:\n    web1:\n"));
+
+        let imported = Asbru.read(&path).unwrap();
+        assert_eq!(imported.connections.len(), 2);
+        assert_eq!(
+            fields(&imported.connections[0]),
+            [
+                "acme-app",
+                "Customers/Acme",
+                "deploy",
+                "app.acme.test",
+                "2222",
+                r#"-i '/home/me/.ssh/acme key' -J j@bastion.acme.test:22 -X -o "ConnectTimeout=20""#
+            ]
+        );
+        assert_eq!(imported.connections[0].notes, "Main app");
+        assert_eq!(fields(&imported.connections[1]), ["lab", "", "", "192.0.2.10", "22", ""]);
+        assert_eq!(imported.notes, ["Skipped connections with other methods than SSH: 1"]);
+    }
+}
@@ -0,0 +1,356 @@
+use crate::*;
+
+// Keywords that may be given several times, each applying block adding to the list instead of the first one winning.
+const ADDITIVE: [&str; 6] = ["identityfile", "certificatefile", "localforward", "remoteforward", "dynamicforward", "sendenv"];
+
+// A Host or Match block of an ssh config file and the keywords set in it.
+pub struct Block {
+    keyword: String,
+    criteria: String,
+    pub path: PathBuf,
+    pub line: usize,
+    settings: Vec<(String, String)>,
+}
+
+impl Block {
+    // Host lines with wildcards or negations, and Match lines, apply to hosts they do not name.
+    fn is_pattern(&self) -> bool {
+        self.is_match() || self.criteria.contains(['*', '?', '!'])
+    }
+
+    // "~/.ssh/config:12"
+    fn location(&self) -> String {
+        format!("{}:{}", paths::abbreviate_home(&self.path), self.line)
+    }
+
+    fn is_match(&self) -> bool {
+        self.keyword.eq_ignore_ascii_case("match")
+    }
+
+    // Whether a host without a block of its own falls under this block. Host * and Match blocks without host
+    // criteria apply to every host, so they cannot tell which hosts to import.
+    pub fn expands_to(&self, name: &str) -> bool {
+        if !self.is_pattern() {
+            return false;
+        }
+        let host_lists: Vec<(bool, String)> = if self.is_match() {
+            match_criteria(&self.criteria)
+                .into_iter()
+                .filter(|(_, criterion, _)| matches!(criterion.as_str(), "host" | "originalhost"))
+                .map(|(negated, _, argument)| (negated, argument.replace(',', " ")))
+                .collect()
+        } else {
+            vec![(false, self.criteria.clone())]
+        };
+        let specific = host_lists
+            .iter()
+            .any(|(negated, list)| !negated && list.split_whitespace().any(|pattern| pattern != "*"));
+        specific && host_lists.iter().all(|(negated, list)| matches_list(list, name) != *negated)
+    }
+
+    // None when the block depends on what only ssh can evaluate, such as Match exec.
+    fn applies(&self, alias: &str, hostname: &str, user: &str) -> Option<bool> {
+        if !self.is_match() {
+            return Some(matches_list(&self.criteria, alias));
+        }
+        let mut applies = Some(true);
+        for (negated, criterion, argument) in match_criteria(&self.criteria) {
+            let list = argument.replace(',', " ");
+            let result = match criterion.as_str() {
+                "all" => Some(true),
+                "host" => Some(matches_list(&list, hostname)),
+                "originalhost" => Some(matches_list(&list, alias)),
+                "user" => Some(matches_list(&list, user)),
+                "localuser" => env::var("USER").ok().map(|local_user| matches_list(&list, &local_user)),
+                _ => None,
+            };
+            match result.map(|result| result != negated) {
+                Some(false) => return Some(false),
+                None => applies = None,
+                Some(true) => {}
+            }
+        }
+        applies
+    }
+}
+
+// Blocks in the order ssh reads them. Keywords before the first block apply to every host and are left out.
+// An included file starts in the block of its Include line, and ssh goes back to that block after the file.
+pub fn read_blocks(parts: &[parse::ConfigPart]) -> Vec<Block> {
+    let mut blocks: Vec<Block> = vec![];
+    // The block each file being read is in, by include depth.
+    let mut current: Vec<Option<usize>> = vec![];
+    for part in parts {
+        let Ok(data) = fs::read_to_string(&part.path) else {
+            continue;
+        };
+        let enclosing = part.depth.checked_sub(1).and_then(|depth| current.get(depth).copied().flatten());
+        let resumed = current.get(part.depth).copied().flatten();
+        current.resize(part.depth + 1, None);
+        current[part.depth] = if part.lines.start == 0 { enclosing } else { resumed };
+        for (i, line) in data.lines().enumerate().skip(part.lines.start).take(part.lines.len()) {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let (keyword, value) = match line.split_once(['=', ' ', '\t']) {
+                Some((keyword, value)) => (keyword.trim(), value.trim().trim_start_matches('=').trim()),
+                None => (line, ""),
+            };
+            if keyword.is_empty() {
+                continue;
+            }
+            if keyword.eq_ignore_ascii_case("host") || keyword.eq_ignore_ascii_case("match") {
+                blocks.push(Block {
+                    keyword: keyword.to_string(),
+                    criteria: value.to_string(),
+                    path: part.path.clone(),
+                    line: i + 1,
+                    settings: vec![],
+                });
+                current[part.depth] = Some(blocks.len() - 1);
+            } else if let Some(block) = current[part.depth] {
+                blocks[block].settings.push((keyword.to_string(), value.to_string()));
+            }
+        }
+    }
+    blocks
+}
+
+// The names of Host lines in the order ssh reads them. Patterns are matched against known hosts instead.
+pub fn host_names(blocks: &[Block]) -> Vec<String> {
+    blocks
+        .iter()
+        .filter(|block| !block.is_match())
+        .flat_map(|block| block.criteria.split_whitespace())
+        .filter(|name| !name.contains(['*', '?', '!']))
+        .map(str::to_string)
+        .collect()
+}
+
+// The block a host was imported from: the Host line naming it, or else the first pattern that expands to it.
+pub fn find_source<'a>(blocks: &'a [Block], alias: &str) -> Option<&'a Block> {
+    blocks
+        .iter()
+        .find(|block| {
+            !block.is_match() && block.criteria.split_whitespace().any(|name| name.eq_ignore_ascii_case(alias))
+        })
+        .or_else(|| blocks.iter().find(|block| block.expands_to(alias)))
+}
+
+// Lines such as "Host *.prod: User, IdentityFile (~/.ssh/config:12)" for the pattern blocks that set options of a
+// host. The first block setting a keyword wins, as in ssh, so a keyword set in the host's own block is not listed.
+pub fn origins(blocks: &[Block], alias: &str, hostname: &str, user: &str, options: &[(String, String)]) -> Vec<String> {
+    let mut contributed: Vec<(usize, Vec<String>)> = vec![];
+    for (key, value) in options {
+        let additive = ADDITIVE.contains(&key.as_str());
+        for (i, block) in blocks.iter().enumerate() {
+            let settings: Vec<&(String, String)> =
+                block.settings.iter().filter(|(keyword, _)| keyword.eq_ignore_ascii_case(key)).collect();
+            // Additive keywords can be repeated in a block, and any of the lines may have given this value.
+            let found = if additive {
+                settings.iter().find(|(_, setting)| same_value(value, setting)).or(settings.first())
+            } else {
+                settings.first()
+            };
+            let Some((keyword, setting)) = found.copied() else {
+                continue;
+            };
+            let applies = match block.applies(alias, hostname, user) {
+                Some(applies) => applies,
+                // Unknown criteria: trust the block when ssh ended up with its value.
+                None => same_value(value, setting),
+            };
+            if !applies {
+                continue;
+            }
+            if block.is_pattern() && (!additive || same_value(value, setting)) {
+                match contributed.iter_mut().find(|(index, _)| *index == i) {
+                    Some((_, keywords)) if keywords.contains(keyword) => {}
+                    Some((_, keywords)) => keywords.push(keyword.clone()),
+                    None => contributed.push((i, vec![keyword.clone()])),
+                }
+            }
+            if !additive {
+                break;
+            }
+        }
+    }
+    contributed.sort_by_key(|(i, _)| *i);
+    contributed
+        .into_iter()
+        .map(|(i, keywords)| {
+            let block = &blocks[i];
+            format!("{} {}: {} ({})", block.keyword, block.criteria, keywords.join(", "), block.location())
+        })
+        .collect()
+}
+
+// ssh prints expanded values, such as a full path for ~/.ssh/id_ed25519, and brackets the hosts of forwards.
+fn same_value(resolved: &str, written: &str) -> bool {
+    let normalize = |value: &str| value.replace(['[', ']'], "").split_whitespace().collect::<Vec<_>>().join(" ");
+    let (resolved, written) = (normalize(resolved), normalize(written));
+    resolved.eq_ignore_ascii_case(&written) || resolved.ends_with(written.trim_start_matches('~'))
+}
+
+// Criteria of a Match line as (negated, name, argument); all, canonical and final take no argument.
+fn match_criteria(criteria: &str) -> Vec<(bool, String, String)> {
+    let words = shlex::split(criteria).unwrap_or_else(|| criteria.split_whitespace().map(str::to_string).collect());
+    let mut words = words.into_iter();
+    let mut parsed = vec![];
+    while let Some(word) = words.next() {
+        let (negated, name) = match word.strip_prefix('!') {
+            Some(name) => (true, name.to_lowercase()),
+            None => (false, word.to_lowercase()),
+        };
+        let argument = match name.as_str() {
+            "all" | "canonical" | "final" => String::new(),
+            _ => words.next().unwrap_or_default(),
+        };
+        parsed.push((negated, name, argument));
+    }
+    parsed
+}
+
+// A pattern list matches when one of its patterns does and none of its negated patterns do.
+fn matches_list(list: &str, text: &str) -> bool {
+    let mut matched = false;
+    for pattern in list.split_whitespace() {
+        match pattern.strip_prefix('!') {
+            Some(pattern) if matches_pattern(pattern, text) => return false,
+            Some(_) => {}
+            None => matched |= matches_pattern(pattern, text),
+        }
+    }
+    matched
+}
+
+// ssh patterns: * matches any run of characters and ? a single one, without regard to case.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(keyword: &str, criteria: &str, settings: &[(&str, &str)]) -> Block {
+        Block {
+            keyword: keyword.to_string(),
+            criteria: criteria.to_string(),
+            path: PathBuf::from("/etc/ssh/ssh_config"),
+            line: 1,
+            settings: settings.iter().map(|(keyword, value)| (keyword.to_string(), value.to_string())).collect(),
+        }
+    }
+
+    fn options(options: &[(&str, &str)]) -> Vec<(String, String)> {
+        options.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn matches_ssh_patterns() {
+        assert!(matches_pattern("*.prod", "db.PROD"));
+        assert!(matches_pattern("web?", "web1"));
+        assert!(!matches_pattern("web?", "web10"));
+        assert!(matches_pattern("*a*b", "xaxab"));
+        assert!(matches_pattern("10.0.*.?", "10.0.12.5"));
+        assert!(matches_pattern("*", ""));
+        assert!(!matches_pattern("a*", "ba"));
+        assert!(matches_list("*.prod !bad.prod", "db.prod"));
+        assert!(!matches_list("*.prod !bad.prod", "bad.prod"));
+        assert!(!matches_list("!bad.prod", "db.prod"));
+    }
+
+    #[test]
+    fn patterns_expand_to_hosts_they_do_not_name() {
+        assert!(block("Host", "*.prod", &[]).expands_to("db.prod"));
+        assert!(!block("Host", "*.prod", &[]).expands_to("db.lan"));
+        assert!(!block("Host", "*", &[]).expands_to("db.prod"));
+        assert!(!block("Host", "db.prod", &[]).expands_to("db.prod"));
+        assert!(!block("Host", "*.prod !db.prod", &[]).expands_to("db.prod"));
+        let lan = block("Match", "host *.lan,!printer.lan user root", &[]);
+        assert!(lan.expands_to("nas.lan"));
+        assert!(!lan.expands_to("printer.lan"));
+        assert!(!block("Match", "all", &[]).expands_to("nas.lan"));
+        assert!(!block("Match", "!host *.lan", &[]).expands_to("db.prod"));
+    }
+
+    #[test]
+    fn origins_list_the_pattern_blocks_setting_options() {
+        let blocks = [
+            block("Host", "web1", &[("User", "deploy")]),
+            block("Host", "*.prod", &[("User", "admin"), ("Port", "2222"), ("IdentityFile", "~/.ssh/prod")]),
+            block("Match", "exec \"test -f /tmp/vpn\"", &[("ProxyJump", "gw")]),
+            block("Host", "*", &[("IdentityFile", "~/.ssh/default"), ("Port", "22"), ("User", "nobody")]),
+        ];
+        let prod = options(&[
+            ("user", "admin"),
+            ("port", "2222"),
+            ("identityfile", "/home/me/.ssh/prod"),
+            ("identityfile", "/home/me/.ssh/default"),
+            ("proxyjump", "gw"),
+        ]);
+        assert_eq!(
+            origins(&blocks, "db.prod", "db.prod", "admin", &prod),
+            [
+                "Host *.prod: User, Port, IdentityFile (/etc/ssh/ssh_config:1)",
+                "Match exec \"test -f /tmp/vpn\": ProxyJump (/etc/ssh/ssh_config:1)",
+                "Host *: IdentityFile (/etc/ssh/ssh_config:1)"
+            ]
+        );
+        // The host's own block wins for User, and a Match exec whose value ssh did not use is left out.
+        let web = options(&[("user", "deploy"), ("port", "22"), ("proxyjump", "other")]);
+        assert_eq!(origins(&blocks, "web1", "web1", "deploy", &web), ["Host *: Port (/etc/ssh/ssh_config:1)"]);
+    }
+
+    #[test]
+    fn origins_follow_every_line_of_repeated_keywords() {
+        let blocks = [
+            block("Host", "db.prod", &[("IdentityFile", "~/.ssh/db")]),
+            block("Match", "exec \"test -f /tmp/vpn\"", &[("IdentityFile", "~/.ssh/db"), ("IdentityFile", "~/.ssh/vpn")]),
+            block("Host", "*.prod", &[("LocalForward", "8080 localhost:80"), ("LocalForward", "9090 localhost:90")]),
+        ];
+        // ssh lists an identity file once and brackets the hosts of forwards.
+        let prod = options(&[
+            ("identityfile", "/home/me/.ssh/db"),
+            ("identityfile", "/home/me/.ssh/vpn"),
+            ("localforward", "8080 [localhost]:80"),
+            ("localforward", "9090 [localhost]:90"),
+        ]);
+        assert_eq!(
+            origins(&blocks, "db.prod", "db.prod", "admin", &prod),
+            [
+                "Match exec \"test -f /tmp/vpn\": IdentityFile (/etc/ssh/ssh_config:1)",
+                "Host *.prod: LocalForward (/etc/ssh/ssh_config:1)"
+            ]
+        );
+        // Only the second line of the Match block tells that its criteria held.
+        let prod = options(&[("identityfile", "/home/me/.ssh/vpn")]);
+        assert_eq!(
+            origins(&blocks, "db.prod", "db.prod", "admin", &prod),
+            ["Match exec \"test -f /tmp/vpn\": IdentityFile (/etc/ssh/ssh_config:1)"]
+        );
+    }
+}
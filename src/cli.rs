@@ -0,0 +1,117 @@
+use crate::paths::expand_home;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage: ssh-list [OPTIONS] [COMMAND]
+
+Commands:
+  encrypt                Encrypt the connection store of the profile with a passphrase
+  decrypt                Decrypt the connection store of the profile back to plain JSON
+  export <FILE>          Export the connections of the profile
+    --format <FORMAT>    json, toml, yaml, csv, ini or ansible-yaml (default: from the file extension)
+    --group <GROUP>      Only export a group and its subgroups
+    --no-personal        Leave out usernames and identity files
+  import <FILE>          Import connections from a file; connections already in the list are skipped
+    --replace            Move the connections of the profile to the trash instead of adding to them
+
+Options:
+  --profile <NAME>       Open the connection store of a profile (\"all\" merges every profile, read-only)
+  --config <FILE>        Connection store of the default profile [env: SSH_LIST_CONFIG]
+  --settings <FILE>      Application settings file [env: SSH_LIST_SETTINGS]
+  --ssh-config <FILE>    SSH config to import hosts from [env: SSH_LIST_SSH_CONFIG]
+  --version              Print the version
+  --help                 Print this help
+
+The profile can also be set with SSH_LIST_PROFILE.";
+
+#[derive(Clone, Copy)]
+pub enum StoreCommand {
+    Encrypt,
+    Decrypt,
+}
+
+pub struct ExportArgs {
+    pub path: PathBuf,
+    pub format: Option<String>,
+    pub group: Option<String>,
+    pub personal: bool,
+}
+
+pub struct ImportArgs {
+    pub path: PathBuf,
+    pub replace: bool,
+}
+
+pub enum Command {
+    Store(StoreCommand),
+    Export(ExportArgs),
+    Import(ImportArgs),
+}
+
+#[derive(Default)]
+pub struct Args {
+    pub command: Option<Command>,
+    pub profile: Option<String>,
+    pub config: Option<PathBuf>,
+    pub settings: Option<PathBuf>,
+    pub ssh_config: Option<PathBuf>,
+    pub version: bool,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        while let Some(arg) = args.next() {
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            match name.as_str() {
+                "encrypt" if parsed.command.is_none() => parsed.command = Some(Command::Store(StoreCommand::Encrypt)),
+                "decrypt" if parsed.command.is_none() => parsed.command = Some(Command::Store(StoreCommand::Decrypt)),
+                "export" | "import" if parsed.command.is_none() => {
+                    let path = expand_home(&args.next().ok_or(format!("{} requires a file", name))?);
+                    parsed.command = Some(if name == "export" {
+                        Command::Export(ExportArgs {
+                            path,
+                            format: None,
+                            group: None,
+                            personal: true,
+                        })
+                    } else {
+                        Command::Import(ImportArgs { path, replace: false })
+                    });
+                }
+                "--no-personal" => match &mut parsed.command {
+                    Some(Command::Export(export)) => export.personal = false,
+                    _ => return Err(format!("{} only applies to the export command", name)),
+                },
+                "--replace" => match &mut parsed.command {
+                    Some(Command::Import(import)) => import.replace = true,
+                    _ => return Err(format!("{} only applies to the import command", name)),
+                },
+                "--version" | "-V" => parsed.version = true,
+                "--help" | "-h" => parsed.help = true,
+                "--profile" | "-p" | "--config" | "--settings" | "--ssh-config" | "--format" | "--group" => {
+                    let value = match inline_value {
+                        Some(value) => value,
+                        None => args.next().ok_or(format!("{} requires a value", name))?,
+                    };
+                    match name.as_str() {
+                        "--config" => parsed.config = Some(expand_home(&value)),
+                        "--settings" => parsed.settings = Some(expand_home(&value)),
+                        "--ssh-config" => parsed.ssh_config = Some(expand_home(&value)),
+                        "--format" | "--group" => match &mut parsed.command {
+                            Some(Command::Export(export)) if name == "--format" => export.format = Some(value),
+                            Some(Command::Export(export)) => export.group = Some(value),
+                            _ => return Err(format!("{} only applies to the export command", name)),
+                        },
+                        _ => parsed.profile = Some(value),
+                    }
+                }
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,294 @@
+use crate::SSHConnection;
+use ratatui::layout::Constraint;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Column {
+    Name,
+    Group,
+    Username,
+    Hostname,
+    Port,
+    Options,
+    Notes,
+    LastUsed,
+    Status,
+    Tags,
+    Profile,
+    Source,
+}
+
+impl Column {
+    pub const ALL: [Column; 12] = [
+        Column::Name,
+        Column::Group,
+        Column::Username,
+        Column::Hostname,
+        Column::Port,
+        Column::Options,
+        Column::Notes,
+        Column::LastUsed,
+        Column::Status,
+        Column::Tags,
+        Column::Profile,
+        Column::Source,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Group => "group",
+            Column::Username => "username",
+            Column::Hostname => "hostname",
+            Column::Port => "port",
+            Column::Options => "options",
+            Column::Notes => "notes",
+            Column::LastUsed => "last_used",
+            Column::Status => "status",
+            Column::Tags => "tags",
+            Column::Profile => "profile",
+            Column::Source => "source",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Group => "Group",
+            Column::Username => "Username",
+            Column::Hostname => "Hostname",
+            Column::Port => "Port",
+            Column::Options => "Options",
+            Column::Notes => "Notes",
+            Column::LastUsed => "Last used",
+            Column::Status => "Status",
+            Column::Tags => "Tags",
+            Column::Profile => "Profile",
+            Column::Source => "Source",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.key() == key)
+    }
+
+    fn default_min_width(&self) -> u16 {
+        match self {
+            Column::Port => 7,
+            Column::Options | Column::Notes => 1,
+            Column::LastUsed => 17,
+            _ => 10,
+        }
+    }
+
+    // Columns without a maximum width take the remaining space.
+    fn default_max_width(&self) -> Option<u16> {
+        match self {
+            Column::Port => Some(7),
+            Column::Options | Column::Notes => None,
+            Column::LastUsed => Some(17),
+            _ => Some(50),
+        }
+    }
+
+    pub fn value(&self, connection: &SSHConnection) -> String {
+        match self {
+            Column::Name => connection.server_name.clone(),
+            Column::Group => connection.group_name.clone(),
+            Column::Username => connection.username.clone(),
+            Column::Hostname => connection.hostname.clone(),
+            Column::Port => connection.port.clone(),
+            Column::Options => connection.options.clone(),
+            Column::Notes => connection.notes.lines().next().unwrap_or_default().to_string(),
+            Column::LastUsed => connection
+                .history
+                .last()
+                .and_then(|timestamp| chrono::DateTime::from_timestamp(*timestamp, 0))
+                .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default(),
+            Column::Status => status(connection),
+            Column::Tags => tags(connection).join(" "),
+            Column::Profile => connection.profile.clone(),
+            Column::Source => source(connection),
+        }
+    }
+}
+
+// "personal", "personal (override)" or the name of the shared inventory.
+pub fn source(connection: &SSHConnection) -> String {
+    match (&connection.shared, &connection.overrides) {
+        (Some(shared), _) => shared.clone(),
+        (None, Some(_)) => "personal (override)".to_string(),
+        (None, None) => "personal".to_string(),
+    }
+}
+
+pub fn status(connection: &SSHConnection) -> String {
+    let mut status = vec![];
+    if connection.conflict {
+        status.push("name conflict");
+    }
+    if connection.hostname.trim().is_empty() {
+        status.push("no hostname");
+    }
+    if connection.port.parse::<u16>().is_err() {
+        status.push("invalid port");
+    }
+    if connection.source.as_ref().is_some_and(|source| source.missing) {
+        status.push("source gone");
+    }
+    status.join(", ")
+}
+
+// Tags are the #words written in the notes.
+pub fn tags(connection: &SSHConnection) -> Vec<String> {
+    connection
+        .notes
+        .split_whitespace()
+        .filter(|word| word.len() > 1 && word.starts_with('#'))
+        .map(|word| word.to_string())
+        .collect()
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone)]
+pub struct ColumnConfig {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visible: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_width: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_width: Option<u16>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct ColumnLayout {
+    pub column: Column,
+    pub visible: bool,
+    pub min_width: u16,
+    pub max_width: Option<u16>,
+}
+
+impl ColumnLayout {
+    fn new(column: Column, visible: bool) -> Self {
+        Self {
+            column,
+            visible,
+            min_width: column.default_min_width(),
+            max_width: column.default_max_width(),
+        }
+    }
+
+    pub fn constraint(&self, content_width: usize) -> Constraint {
+        match self.max_width {
+            Some(max_width) => Constraint::Length((content_width as u16).clamp(self.min_width.min(max_width), max_width)),
+            None => Constraint::Min(self.min_width),
+        }
+    }
+
+    // Columns without a max already fill the rest of the table, so they have nothing to grow into.
+    pub fn grow(&mut self) {
+        self.max_width = self.max_width.map(|max_width| max_width.saturating_add(1));
+    }
+
+    pub fn shrink(&mut self) {
+        match self.max_width {
+            Some(max_width) => self.max_width = Some(max_width.saturating_sub(1).max(1)),
+            None => self.max_width = Some(self.min_width.max(10)),
+        }
+        self.min_width = self.min_width.min(self.max_width.unwrap_or(self.min_width));
+    }
+
+    pub fn grow_min(&mut self) {
+        self.min_width = self.min_width.saturating_add(1);
+        self.max_width = self.max_width.map(|max_width| max_width.max(self.min_width));
+    }
+
+    pub fn shrink_min(&mut self) {
+        self.min_width = self.min_width.saturating_sub(1).max(1);
+    }
+}
+
+pub fn default_columns() -> Vec<ColumnLayout> {
+    Column::ALL
+        .into_iter()
+        .map(|column| {
+            let visible = matches!(
+                column,
+                Column::Name | Column::Group | Column::Username | Column::Hostname | Column::Port | Column::Options
+            );
+            ColumnLayout::new(column, visible)
+        })
+        .collect()
+}
+
+// Unknown and duplicate names are ignored; columns missing from the config are appended as hidden.
+pub fn columns_from_config(config: &[ColumnConfig]) -> Vec<ColumnLayout> {
+    let mut columns: Vec<ColumnLayout> = vec![];
+    for column_config in config {
+        let Some(column) = Column::from_key(&column_config.name) else {
+            continue;
+        };
+        if columns.iter().any(|layout| layout.column == column) {
+            continue;
+        }
+        let mut layout = ColumnLayout::new(column, column_config.visible.unwrap_or(true));
+        if let Some(min_width) = column_config.min_width {
+            layout.min_width = min_width;
+        }
+        if let Some(max_width) = column_config.max_width {
+            layout.max_width = Some(max_width);
+        }
+        columns.push(layout);
+    }
+    for column in Column::ALL {
+        if !columns.iter().any(|layout| layout.column == column) {
+            columns.push(ColumnLayout::new(column, false));
+        }
+    }
+    columns
+}
+
+pub fn columns_to_config(columns: &[ColumnLayout]) -> Vec<ColumnConfig> {
+    columns
+        .iter()
+        .map(|layout| ColumnConfig {
+            name: layout.column.key().to_string(),
+            visible: Some(layout.visible),
+            min_width: Some(layout.min_width),
+            max_width: layout.max_width,
+        })
+        .collect()
+}
+
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else if width == 0 {
+        String::new()
+    } else {
+        let mut truncated: String = text.chars().take(width - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_columns_get_a_max_before_they_grow() {
+        let mut options = ColumnLayout::new(Column::Options, true);
+        assert_eq!(options.max_width, None);
+        options.grow();
+        assert_eq!(options.max_width, None);
+        options.shrink();
+        let max_width = options.max_width.unwrap();
+        options.grow();
+        assert_eq!(options.max_width, Some(max_width + 1));
+        options.grow_min();
+        options.shrink_min();
+        assert!(options.min_width <= max_width);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::SSHConnection;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use shlex::split;
+use std::io::{stdout, Write};
+use std::process::Command;
+
+pub struct SshCommand {
+    args: Vec<String>,
+}
+
+impl SshCommand {
+    pub fn new(connection: &SSHConnection) -> Self {
+        let mut args = vec!["-p".to_string(), connection.port.clone()];
+        args.extend(split(&connection.options).unwrap_or_default());
+        args.push(format!("{}@{}", connection.username, connection.hostname));
+        Self { args }
+    }
+
+    pub fn with_remote_command(mut self, command: &str) -> Self {
+        self.args.extend(split(command).unwrap_or_default());
+        self
+    }
+
+    pub fn argv(&self) -> Vec<&str> {
+        let mut argv = vec!["ssh"];
+        argv.extend(self.args.iter().map(String::as_str));
+        argv
+    }
+
+    pub fn to_shell_string(&self) -> String {
+        shlex::try_join(self.argv()).unwrap_or_else(|_| self.argv().join(" "))
+    }
+
+    pub fn to_command(&self) -> Command {
+        let mut command = Command::new("ssh");
+        command.args(&self.args);
+        command
+    }
+}
+
+// OSC 52 is handled by the terminal itself, so it also reaches the local clipboard over nested SSH sessions.
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+    let mut stdout = stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
@@ -0,0 +1,100 @@
+use age::secrecy::SecretString;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use std::io::{self, Read, Write};
+use std::iter;
+
+// Header of files in the age format (https://age-encryption.org/v1).
+const AGE_HEADER: &[u8] = b"age-encryption.org/v1";
+// scrypt work factor (N = 2^16): the store is encrypted again on every save, so this stays well below the
+// one second age targets for interactive use.
+#[cfg(not(test))]
+const WORK_FACTOR: u8 = 16;
+// Keeps the tests fast.
+#[cfg(test)]
+const WORK_FACTOR: u8 = 10;
+
+pub const LOCKED: &str = "The store is encrypted.\nThe passphrase is missing or wrong.";
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(AGE_HEADER)
+}
+
+pub fn encrypt(json: &str, passphrase: &SecretString) -> Result<Vec<u8>, String> {
+    let mut recipient = age::scrypt::Recipient::new(passphrase.clone());
+    recipient.set_work_factor(WORK_FACTOR);
+    let encryptor = age::Encryptor::with_recipients(iter::once(&recipient as &dyn age::Recipient))
+        .map_err(|text| text.to_string())?;
+    let mut encrypted = vec![];
+    let mut writer = encryptor.wrap_output(&mut encrypted).map_err(|text| text.to_string())?;
+    writer.write_all(json.as_bytes()).map_err(|text| text.to_string())?;
+    writer.finish().map_err(|text| text.to_string())?;
+    Ok(encrypted)
+}
+
+pub fn decrypt(data: &[u8], passphrase: Option<&SecretString>) -> Result<String, String> {
+    let Some(passphrase) = passphrase else {
+        return Err(LOCKED.to_string());
+    };
+    let decryptor = age::Decryptor::new(data).map_err(|text| text.to_string())?;
+    let identity = age::scrypt::Identity::new(passphrase.clone());
+    let mut reader = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .map_err(|error| match error {
+            age::DecryptError::DecryptionFailed | age::DecryptError::NoMatchingKeys => LOCKED.to_string(),
+            error => error.to_string(),
+        })?;
+    let mut json = String::new();
+    reader.read_to_string(&mut json).map_err(|text| text.to_string())?;
+    Ok(json)
+}
+
+// Reads a passphrase from the terminal without echoing it.
+pub fn read_passphrase(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    terminal::enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Esc => break Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled")),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+                }
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(error) => break Err(error),
+        }
+    };
+    terminal::disable_raw_mode()?;
+    eprintln!();
+    result.map(|_| passphrase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_json_round_trips() {
+        let passphrase = SecretString::from("correct horse".to_string());
+        let encrypted = encrypt("[{\"server_name\":\"a\"}]", &passphrase).unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.windows(11).any(|window| window == b"server_name"));
+        assert_eq!(decrypt(&encrypted, Some(&passphrase)).unwrap(), "[{\"server_name\":\"a\"}]");
+    }
+
+    #[test]
+    fn wrong_or_missing_passphrase_is_locked() {
+        let encrypted = encrypt("[]", &SecretString::from("right".to_string())).unwrap();
+        assert_eq!(decrypt(&encrypted, Some(&SecretString::from("wrong".to_string()))).unwrap_err(), LOCKED);
+        assert_eq!(decrypt(&encrypted, None).unwrap_err(), LOCKED);
+    }
+}
@@ -0,0 +1,237 @@
+use crate::*;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Toml,
+    Yaml,
+    AnsibleIni,
+    AnsibleYaml,
+}
+
+impl ExportFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "ssh-list JSON",
+            ExportFormat::Toml => "ssh-list TOML",
+            ExportFormat::Yaml => "ssh-list YAML",
+            ExportFormat::AnsibleIni => "Ansible inventory (INI)",
+            ExportFormat::AnsibleYaml => "Ansible inventory (YAML)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Toml => "toml",
+            ExportFormat::Yaml => "yaml",
+            ExportFormat::AnsibleIni => "ini",
+            ExportFormat::AnsibleYaml => "yml",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ExportFormat::Csv => ExportFormat::Json,
+            ExportFormat::Json => ExportFormat::Toml,
+            ExportFormat::Toml => ExportFormat::Yaml,
+            ExportFormat::Yaml => ExportFormat::AnsibleIni,
+            ExportFormat::AnsibleIni => ExportFormat::AnsibleYaml,
+            ExportFormat::AnsibleYaml => ExportFormat::Csv,
+        }
+    }
+
+    // Formats by the name given on the command line, or by the extension of the file. Each extension maps back to
+    // the format that uses it, so .yml is an Ansible inventory as in the export popup.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "toml" => Some(ExportFormat::Toml),
+            "yaml" => Some(ExportFormat::Yaml),
+            "ini" | "ansible-ini" => Some(ExportFormat::AnsibleIni),
+            "yml" | "ansible-yaml" => Some(ExportFormat::AnsibleYaml),
+            _ => None,
+        }
+    }
+}
+
+// Without personal fields, usernames and identity files are left out so that others fill in their own. The store
+// files of the profiles are never written over.
+pub fn write(
+    format: ExportFormat,
+    path: &Path,
+    connections: &[&SSHConnection],
+    personal: bool,
+    profiles: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    if profile::is_store(path, profiles) {
+        return Err(format!("{} is the store of a profile.", paths::abbreviate_home(path)));
+    }
+    let stripped: Vec<SSHConnection> = if personal {
+        vec![]
+    } else {
+        connections.iter().map(|&connection| without_personal_fields(connection)).collect()
+    };
+    let stripped: Vec<&SSHConnection> = stripped.iter().collect();
+    let connections = if personal { connections } else { &stripped };
+    let data = match format {
+        ExportFormat::Csv => return spreadsheet::write_csv(path, connections).map_err(|text| text.to_string()),
+        ExportFormat::Json => portable::write(portable::Format::Json, connections)?,
+        ExportFormat::Toml => portable::write(portable::Format::Toml, connections)?,
+        ExportFormat::Yaml => portable::write(portable::Format::Yaml, connections)?,
+        ExportFormat::AnsibleIni => ansible::write_ini(connections),
+        ExportFormat::AnsibleYaml => ansible::write_yaml(connections)?,
+    };
+    fs::write(path, data).map_err(|text| format!("{}: {}", path.display(), text))
+}
+
+// Swaps the extension of the typed path for the one of the chosen format.
+pub fn with_extension(path: &str, format: ExportFormat) -> String {
+    let (directory, file_name) = match path.rfind('/') {
+        Some(i) => path.split_at(i + 1),
+        None => ("", path),
+    };
+    let stem = match file_name.rfind('.') {
+        Some(i) if i > 0 => &file_name[..i],
+        _ => file_name,
+    };
+    format!("{}{}.{}", directory, stem, format.extension())
+}
+
+// Leaves out the username, identity files and the users of jump hosts, however the options spell them.
+fn without_personal_fields(connection: &SSHConnection) -> SSHConnection {
+    let mut connection = connection.clone();
+    connection.username.clear();
+    let Some(words) = shlex::split(&connection.options) else {
+        return connection;
+    };
+    let mut kept: Vec<String> = vec![];
+    let mut words = words.into_iter();
+    while let Some(word) = words.next() {
+        let flag = match word.get(..2) {
+            Some(flag @ ("-i" | "-l" | "-o" | "-J")) => flag.to_string(),
+            _ => {
+                kept.push(word);
+                continue;
+            }
+        };
+        // The value is either attached (-ifile) or the next word (-i file).
+        let value = if word.len() > 2 { word[2..].to_string() } else { words.next().unwrap_or_default() };
+        match flag.as_str() {
+            "-o" => {
+                let (name, setting) = value
+                    .split_once(|c: char| c == '=' || c.is_whitespace())
+                    .unwrap_or((value.as_str(), ""));
+                let setting = setting.trim_start_matches(|c: char| c == '=' || c.is_whitespace());
+                match name.to_lowercase().as_str() {
+                    "identityfile" | "user" => {}
+                    "proxyjump" => kept.extend([flag, format!("{}={}", name, without_jump_users(setting))]),
+                    _ => kept.extend([flag, value]),
+                }
+            }
+            "-J" => kept.extend([flag, without_jump_users(&value)]),
+            _ => {}
+        }
+    }
+    connection.options = shlex::try_join(kept.iter().map(String::as_str)).unwrap_or_default();
+    connection
+}
+
+// "user@jump1,ssh://user@jump2:2222" becomes "jump1,ssh://jump2:2222".
+fn without_jump_users(jumps: &str) -> String {
+    jumps
+        .split(',')
+        .map(|jump| {
+            let (scheme, host) = jump.strip_prefix("ssh://").map_or(("", jump), |host| ("ssh://", host));
+            format!("{}{}", scheme, host.rsplit_once('@').map_or(host, |(_, host)| host))
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_map_back_to_their_format() {
+        let mut format = ExportFormat::Csv;
+        loop {
+            assert!(ExportFormat::from_name(format.extension()) == Some(format), "{}", format.label());
+            format = format.next();
+            if format == ExportFormat::Csv {
+                break;
+            }
+        }
+        assert!(ExportFormat::from_name("ansible-yaml") == Some(ExportFormat::AnsibleYaml));
+    }
+
+    #[test]
+    fn does_not_write_over_a_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("work.json");
+        fs::write(&store, "[]").unwrap();
+        let profiles = BTreeMap::from([("work".to_string(), store.display().to_string())]);
+        let connection = import::connection("web".to_string(), "u".to_string(), "h".to_string(), "22".to_string(), String::new());
+
+        let error = write(ExportFormat::Csv, &store, &[&connection], true, &profiles).unwrap_err();
+        assert!(error.ends_with("work.json is the store of a profile."));
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link.json");
+            std::os::unix::fs::symlink(&store, &link).unwrap();
+            assert!(write(ExportFormat::Json, &link, &[&connection], true, &profiles).is_err());
+        }
+        assert_eq!(fs::read_to_string(&store).unwrap(), "[]");
+
+        let other = dir.path().join("export.csv");
+        write(ExportFormat::Csv, &other, &[&connection], true, &profiles).unwrap();
+        assert!(fs::read_to_string(&other).unwrap().contains("web,"));
+    }
+
+    fn personal_options_removed(options: &str) -> String {
+        let mut connection = import::connection("web".to_string(), "u".to_string(), "h".to_string(), "22".to_string(), String::new());
+        connection.options = options.to_string();
+        let connection = without_personal_fields(&connection);
+        assert!(connection.username.is_empty());
+        connection.options
+    }
+
+    #[test]
+    fn removes_identity_files() {
+        assert_eq!(personal_options_removed("-i ~/.ssh/id_work -A"), "-A");
+        assert_eq!(personal_options_removed("-i~/.ssh/id_work -A"), "-A");
+        assert_eq!(personal_options_removed("-o 'IdentityFile ~/.ssh/id_work' -A"), "-A");
+        assert_eq!(personal_options_removed("-o IdentityFile=~/.ssh/id_work -A"), "-A");
+        assert_eq!(personal_options_removed("-oIdentityFile=~/.ssh/id_work -A"), "-A");
+    }
+
+    #[test]
+    fn removes_users() {
+        assert_eq!(personal_options_removed("-l admin -C"), "-C");
+        assert_eq!(personal_options_removed("-ladmin -C"), "-C");
+        assert_eq!(personal_options_removed("-o User=admin -C"), "-C");
+        assert_eq!(personal_options_removed("-ouser=admin -C"), "-C");
+    }
+
+    #[test]
+    fn removes_the_users_of_jump_hosts() {
+        assert_eq!(personal_options_removed("-J admin@bastion"), "-J bastion");
+        assert_eq!(personal_options_removed("-Jadmin@bastion:2222,gate"), "-J 'bastion:2222,gate'");
+        assert_eq!(personal_options_removed("-J ssh://admin@bastion:2222"), "-J ssh://bastion:2222");
+        assert_eq!(personal_options_removed("-o ProxyJump=admin@bastion"), "-o 'ProxyJump=bastion'");
+    }
+
+    #[test]
+    fn keeps_other_options() {
+        // Words are quoted again where the shell could read them differently.
+        assert_eq!(
+            personal_options_removed("-o ServerAliveInterval=30 -L 8080:localhost:80"),
+            "-o 'ServerAliveInterval=30' -L 8080:localhost:80"
+        );
+    }
+}
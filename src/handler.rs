@@ -1,53 +1,87 @@
+use crate::keybindings::{Action, Context, Lookup};
 use crate::*;
-use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
+use std::time::{Duration, Instant};
 use tui_input::backend::crossterm::EventHandler;
+use tui_input::InputRequest;
+
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
 
 pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
     match app.app_mode {
-        AppMode::Normal => match key.code {
-            KeyCode::Esc => {
-                ratatui::restore();
-                execute!(stdout(), Show).ok();
-                return false;
-            }
-            KeyCode::Down | KeyCode::Tab => app.next_row(),
-            KeyCode::Up | KeyCode::BackTab => app.previous_row(),
-            KeyCode::Enter => match app.table_state.selected() {
-                Some(_) => {
-                    if check_openssh() {
-                        ratatui::restore();
-                        execute!(stdout(), Show).ok();
-                        app.connect(None);
-                        return false;
-                    } else {
-                        app.search();
-                        app.error_text = "Failed to execute ssh command.\nIs the OpenSSH-client installed?".to_string();
-                        app.show_import_popup = false;
-                        app.show_error_popup = true;
-                        app.app_mode = AppMode::Error;
-                    }
+        AppMode::Normal => {
+            let action = normal_action(app, &key);
+            let count = if action.is_some() { app.count.take() } else { None };
+            match action {
+                Some(Action::Quit) => {
+                    restore_terminal();
+                    execute!(stdout(), Show).ok();
+                    return false;
                 }
-                None => (),
-            },
-            KeyCode::Delete => {
-                app.delete_connection();
-                app.scroll_state = app.scroll_state.content_length(app.ssh_connections.len());
-            }
-            KeyCode::Char('c' | 'C' | 'с' | 'С') => {
-                app.copy_connection();
-                app.scroll_state = app.scroll_state.content_length(app.ssh_connections.len());
-                app.next_row();
-            }
-            KeyCode::Char('i' | 'I' | 'ш' | 'Ш') => {
-                app.show_import_popup = true;
-                app.app_mode = AppMode::Import;
-            }
-            KeyCode::Char('m' | 'M' | 'ь' | 'Ь') => match app.table_state.selected() {
-                Some(_) => app.app_mode = AppMode::Move,
-                None => (),
-            },
-            KeyCode::Char('e' | 'E' | 'у' | 'У') => match app.table_state.selected() {
-                Some(_) => {
+                Some(Action::Down) => app.move_down(count.unwrap_or(1)),
+                Some(Action::Up) => app.move_up(count.unwrap_or(1)),
+                Some(Action::PageDown) => app.move_down(app.page_rows * count.unwrap_or(1)),
+                Some(Action::PageUp) => app.move_up(app.page_rows * count.unwrap_or(1)),
+                Some(Action::HalfPageDown) => app.move_down((app.page_rows / 2).max(1) * count.unwrap_or(1)),
+                Some(Action::HalfPageUp) => app.move_up((app.page_rows / 2).max(1) * count.unwrap_or(1)),
+                Some(Action::Jump) => app.awaiting_jump = true,
+                Some(Action::Connect) if app.table_state.selected().is_some() => return connect_selected(app),
+                Some(
+                    Action::Delete
+                    | Action::Mark
+                    | Action::Trash
+                    | Action::Copy
+                    | Action::Import
+                    | Action::Add
+                    | Action::Move
+                    | Action::Edit
+                    | Action::Sync
+                    | Action::RefreshSource,
+                ) if app.refuse_read_only() => {}
+                Some(Action::Mark | Action::Move | Action::Copy | Action::RefreshSource) if app.refuse_shared() => {}
+                Some(Action::Delete) if app.marked.is_empty() && app.refuse_shared() => {}
+                Some(Action::Profiles) => {
+                    let entries = app.profile_entries();
+                    let current = entries.iter().position(|name| *name == app.profile).unwrap_or(0);
+                    app.profiles_state.select(Some(current));
+                    app.app_mode = AppMode::Profiles;
+                    app.show_profiles_popup = true;
+                }
+                Some(Action::Delete) => app.ask_delete(),
+                Some(Action::Export) => app.open_export(),
+                Some(Action::Sync) if !app.git_sync => {
+                    app.show_config_error("Git sync is off. Set git_sync = true\nin ssh-list_config.toml.".to_string());
+                }
+                Some(Action::Sync) => {
+                    app.sync_message.clear();
+                    app.refresh_sync_status();
+                    app.app_mode = AppMode::Sync;
+                    app.show_sync_popup = true;
+                }
+                Some(Action::Mark) => {
+                    app.toggle_mark();
+                    app.next_row();
+                }
+                Some(Action::Trash) => {
+                    app.last_app_mode = AppMode::Normal;
+                    app.app_mode = AppMode::Trash;
+                    app.show_trash_popup = true;
+                    app.select_trash_row(0);
+                }
+                Some(Action::Copy) => {
+                    app.copy_connection();
+                    app.scroll_state = app.scroll_state.content_length(app.visible_rows());
+                    app.next_row();
+                }
+                Some(Action::Import) => {
+                    app.show_import_popup = true;
+                    app.app_mode = AppMode::Import;
+                }
+                Some(Action::RefreshSource) if app.table_state.selected().is_some() => app.refresh_from_source(),
+                Some(Action::Move) if app.refuse_sorted() => {}
+                Some(Action::Move) if app.table_state.selected().is_some() => app.app_mode = AppMode::Move,
+                Some(Action::Edit) if app.table_state.selected().is_some() => {
                     app.search();
                     app.last_app_mode = AppMode::Normal;
                     app.app_mode = AppMode::Edit;
@@ -55,45 +89,59 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
                     app.selected_config_to_fields();
                     app.show_edit_popup = true;
                 }
-                None => (),
-            },
-            KeyCode::Char('a' | 'A' | 'ф' | 'Ф') => {
-                app.app_mode = AppMode::New;
-                app.reset_fields();
-                app.show_edit_popup = true;
-                app.focus = Focus::ServerNameField;
-            }
-            KeyCode::Char('r' | 'R' | 'к' | 'К') => match app.table_state.selected() {
-                Some(_) => {
+                Some(Action::Add) => {
+                    app.app_mode = AppMode::New;
+                    app.reset_fields();
+                    app.show_edit_popup = true;
+                    app.focus = Focus::ServerNameField;
+                }
+                Some(Action::Run) if app.table_state.selected().is_some() => {
                     app.search();
                     app.app_mode = AppMode::RunCommand;
                     app.last_app_mode = AppMode::Normal;
                     app.reset_fields();
                     app.show_run_popup = true;
                 }
-                None => (),
-            },
-            KeyCode::Char('/') => {
-                app.app_mode = AppMode::Search;
-                app.focus = Focus::SearchField;
-                app.search();
-            }
-            KeyCode::Char('O' | 'o' | 'Щ' | 'щ') => {
-                app.app_mode = AppMode::Options;
-                app.show_options_popup = true;
-            }
-            KeyCode::Char('S' | 's' | 'Ы' | 'ы') => {
-                app.app_mode = AppMode::Sort;
-                app.table_state.select_first();
+                Some(Action::Search) => {
+                    app.app_mode = AppMode::Search;
+                    app.focus = Focus::SearchField;
+                    app.search();
+                }
+                Some(Action::Options) => {
+                    app.app_mode = AppMode::Options;
+                    app.show_options_popup = true;
+                }
+                Some(Action::Sort) => {
+                    app.app_mode = AppMode::Sort;
+                }
+                Some(Action::Preview) if app.table_state.selected().is_some() => {
+                    app.search();
+                    app.last_app_mode = AppMode::Normal;
+                    app.app_mode = AppMode::Preview;
+                    app.preview_copied = false;
+                    app.show_preview_popup = true;
+                }
+                Some(Action::First) => app.select_row(count.map_or(0, |n| n.saturating_sub(1))),
+                Some(Action::Last) => match count {
+                    Some(n) => app.select_row(n.saturating_sub(1)),
+                    None => app.last_row(),
+                },
+                Some(Action::Details) => {
+                    app.show_detail_panel = !app.show_detail_panel;
+                    app.update_appconfig();
+                }
+                _ => {}
             }
-            _ => {}
-        },
+        }
         AppMode::New => match key.code {
+            KeyCode::Enter if app.focus == Focus::NotesField && key.modifiers.contains(KeyModifiers::ALT) => {
+                app.field_inputs.notes_input.handle(InputRequest::InsertChar('\n'));
+            }
             KeyCode::Enter => {
                 app.add_connection();
                 app.app_mode = AppMode::Normal;
                 app.show_edit_popup = false;
-                app.scroll_state = app.scroll_state.content_length(app.ssh_connections.len());
+                app.scroll_state = app.scroll_state.content_length(app.visible_rows());
             }
             KeyCode::Esc => {
                 app.show_edit_popup = false;
@@ -120,10 +168,16 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
                 Focus::OptionsField => {
                     app.field_inputs.options_input.handle_event(&Event::Key(key));
                 }
+                Focus::NotesField => {
+                    app.field_inputs.notes_input.handle_event(&Event::Key(key));
+                }
                 _ => (),
             },
         },
         AppMode::Edit => match key.code {
+            KeyCode::Enter if app.focus == Focus::NotesField && key.modifiers.contains(KeyModifiers::ALT) => {
+                app.field_inputs.notes_input.handle(InputRequest::InsertChar('\n'));
+            }
             KeyCode::Enter => {
                 app.update_connection();
                 if app.last_app_mode == AppMode::Normal {
@@ -167,53 +221,177 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
                 Focus::OptionsField => {
                     app.field_inputs.options_input.handle_event(&Event::Key(key));
                 }
+                Focus::NotesField => {
+                    app.field_inputs.notes_input.handle_event(&Event::Key(key));
+                }
                 _ => (),
             },
         },
-        AppMode::Move => match key.code {
-            KeyCode::Esc => {
+        AppMode::Move => match app.keybindings.action(Context::Move, &key) {
+            _ if key.code == KeyCode::Esc => {
                 app.show_edit_popup = false;
                 app.app_mode = AppMode::Normal;
             }
-            KeyCode::Down => {
+            Some(Action::MoveDown) => {
                 app.move_row_down();
             }
-            KeyCode::Up => {
+            Some(Action::MoveUp) => {
                 app.move_row_up();
             }
             _ => {}
         },
-        AppMode::Import => match key.code {
-            KeyCode::Char('i' | 'I' | 'ш' | 'Ш') => {
+        AppMode::Import => match app.keybindings.action(Context::Import, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.show_import_popup = false;
+                app.app_mode = AppMode::Normal;
+            }
+            Some(Action::ImportSshConfig) => {
                 if !parse::check_blank_sshconfig(&parse::get_sshconfig_path()) {
                     if check_openssh() {
-                        parse::import_config(app);
-                        app.show_import_popup = false;
-                        app.app_mode = AppMode::Normal;
-                        app.scroll_state = app.scroll_state.content_length(app.ssh_connections.len());
+                        match parse::import_config() {
+                            Ok(imported) => {
+                                let preview = import::ImportPreview::new(
+                                    import::SourceKind::SshConfig,
+                                    &parse::get_sshconfig_path(),
+                                    imported,
+                                    &app.ssh_connections,
+                                );
+                                app.preview_import(preview);
+                            }
+                            Err(text) => app.show_failed_import(parse::get_sshconfig_path().display().to_string(), text),
+                        }
                     } else {
-                        app.search();
-                        app.error_text = "Failed to import ssh config.\nIs the OpenSSH-client installed?".to_string();
-                        app.show_import_popup = false;
-                        app.show_error_popup = true;
-                        app.app_mode = AppMode::Error;
+                        app.show_import_error("Failed to import ssh config.\nIs the OpenSSH-client installed?".to_string());
                     }
                 } else {
-                    app.search();
-                    app.error_text = "The config is empty or does not exist.".to_string();
-                    app.show_import_popup = false;
-                    app.show_error_popup = true;
-                    app.app_mode = AppMode::Error;
+                    app.show_import_error("The config is empty or does not exist.".to_string());
                 }
             }
-            KeyCode::Esc => {
+            Some(Action::ImportKnownHosts) => {
+                let path = known_hosts::get_known_hosts_path();
+                match known_hosts::read_known_hosts(&path) {
+                    Ok(known_hosts) => {
+                        let mut imported = import::Imported {
+                            connections: known_hosts.connections,
+                            ..Default::default()
+                        };
+                        if known_hosts.hashed > 0 {
+                            imported.notes.push(format!(
+                                "{} hashed entries skipped (HashKnownHosts)",
+                                known_hosts.hashed
+                            ));
+                        }
+                        let preview =
+                            import::ImportPreview::new(import::SourceKind::KnownHosts, &path, imported, &app.ssh_connections);
+                        app.preview_import(preview);
+                    }
+                    Err(_) => app.show_import_error("The known_hosts file does not exist\nor cannot be read.".to_string()),
+                }
+            }
+            Some(Action::ImportFile) => {
                 app.show_import_popup = false;
-                app.app_mode = AppMode::Normal;
+                app.show_import_path_popup = true;
+                app.app_mode = AppMode::ImportPath;
             }
             _ => {}
         },
-        AppMode::Error => match key.code {
+        AppMode::ImportPath => match key.code {
             KeyCode::Esc => {
+                app.show_import_path_popup = false;
+                app.show_import_popup = true;
+                app.app_mode = AppMode::Import;
+            }
+            KeyCode::Enter => app.import_from_path(),
+            _ => {
+                app.import_path_input.handle_event(&Event::Key(key));
+            }
+        },
+        AppMode::CsvColumns => match app.keybindings.action(Context::CsvColumns, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.csv_import = None;
+                app.show_csv_columns_popup = false;
+                app.show_import_path_popup = true;
+                app.app_mode = AppMode::ImportPath;
+            }
+            _ if key.code == KeyCode::Enter => app.preview_csv_import(),
+            _ if key.code == KeyCode::Down => app.csv_cursor = (app.csv_cursor + 1).min(spreadsheet::FIELDS.len() - 1),
+            _ if key.code == KeyCode::Up => app.csv_cursor = app.csv_cursor.saturating_sub(1),
+            _ if matches!(key.code, KeyCode::Right | KeyCode::Left) => {
+                if let Some(csv_import) = app.csv_import.as_mut() {
+                    csv_import.cycle(app.csv_cursor, key.code == KeyCode::Right);
+                }
+            }
+            Some(Action::CsvHeader) => {
+                if let Some(csv_import) = app.csv_import.as_mut() {
+                    csv_import.toggle_header();
+                }
+            }
+            _ => {}
+        },
+        AppMode::Export => match app.keybindings.action(Context::Export, &key) {
+            _ if key.code == KeyCode::Esc => app.close_export(),
+            _ if key.code == KeyCode::Enter => app.export(),
+            _ if key.code == KeyCode::Tab => app.next_export_format(),
+            _ if key.code == KeyCode::Down => app.next_export_scope(true),
+            _ if key.code == KeyCode::Up => app.next_export_scope(false),
+            Some(Action::ExportPersonal) => {
+                app.export_personal = !app.export_personal;
+                app.export_status = None;
+            }
+            _ => {
+                app.export_input.handle_event(&Event::Key(key));
+                app.export_status = None;
+            }
+        },
+        AppMode::ImportPreview => match app.keybindings.action(Context::ImportPreview, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.import_preview = None;
+                app.show_import_preview_popup = false;
+                app.show_import_popup = true;
+                app.app_mode = AppMode::Import;
+            }
+            _ if key.code == KeyCode::Enter && app.refuse_empty_replace() => {}
+            _ if key.code == KeyCode::Enter => app.finish_import(),
+            Some(Action::Down) => {
+                app.select_import_row(app.import_state.selected().map_or(0, |row| row + 1));
+            }
+            Some(Action::Up) => {
+                app.select_import_row(app.import_state.selected().map_or(0, |row| row.saturating_sub(1)));
+            }
+            Some(Action::Mark) => {
+                if let (Some(preview), Some(row)) = (app.import_preview.as_mut(), app.import_state.selected()) {
+                    preview.toggle(row);
+                }
+            }
+            Some(Action::ImportSelectAll) => {
+                if let Some(preview) = app.import_preview.as_mut() {
+                    preview.toggle_all();
+                }
+            }
+            Some(Action::ImportReplace) => app.toggle_import_replace(),
+            _ => {}
+        },
+        AppMode::ImportReport => {
+            let last_line = app.import_report.as_ref().map_or(0, |report| report.line_count().saturating_sub(1)) as u16;
+            match app.keybindings.action(Context::ImportReport, &key) {
+                _ if matches!(key.code, KeyCode::Esc | KeyCode::Enter) => {
+                    app.import_report = None;
+                    app.show_import_report_popup = false;
+                    app.app_mode = AppMode::Normal;
+                }
+                Some(Action::Down) => app.import_report_scroll = (app.import_report_scroll + 1).min(last_line),
+                Some(Action::Up) => app.import_report_scroll = app.import_report_scroll.saturating_sub(1),
+                Some(Action::PageDown) => {
+                    app.import_report_scroll = (app.import_report_scroll + app.page_rows as u16).min(last_line);
+                }
+                Some(Action::PageUp) => {
+                    app.import_report_scroll = app.import_report_scroll.saturating_sub(app.page_rows as u16);
+                }
+                _ => {}
+            }
+        }
+        AppMode::Error => {
+            if key.code == KeyCode::Esc {
                 app.show_error_popup = false;
                 if app.last_app_mode == AppMode::Normal {
                     app.app_mode = AppMode::Normal
@@ -222,9 +400,11 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
                     app.app_mode = AppMode::Search;
                     app.focus = Focus::SearchField;
                 };
+                if app.last_app_mode == AppMode::ImportPreview {
+                    app.app_mode = AppMode::ImportPreview
+                };
             }
-            _ => {}
-        },
+        }
         AppMode::RunCommand => match key.code {
             KeyCode::Esc => {
                 app.show_run_popup = false;
@@ -240,7 +420,7 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
             }
             KeyCode::Enter => {
                 if check_openssh() {
-                    ratatui::restore();
+                    restore_terminal();
                     execute!(stdout(), Show).ok();
                     app.connect(Some(app.run_input.to_string()));
                     return false;
@@ -251,122 +431,394 @@ pub fn handle_key_event(app: &mut App, key: KeyEvent) -> bool {
                     app.app_mode = AppMode::Error;
                 }
             }
-            _ => match app.focus {
-                Focus::RunField => {
+            _ => {
+                if app.focus == Focus::RunField {
                     app.run_input.handle_event(&Event::Key(key));
                 }
-                _ => (),
-            },
+            }
         },
-        AppMode::Search => match key.code {
-            KeyCode::Esc => {
+        AppMode::Search => match app.keybindings.action(Context::Search, &key) {
+            _ if key.code == KeyCode::Esc => {
                 app.app_mode = AppMode::Normal;
                 app.search_input = Input::default();
                 app.search();
-                app.table_state.select_first();
-            }
-            KeyCode::Down | KeyCode::Tab => app.next_row(),
-            KeyCode::Up | KeyCode::BackTab => app.previous_row(),
-            KeyCode::Enter => match app.table_state.selected() {
-                Some(_) => {
-                    if check_openssh() {
-                        ratatui::restore();
-                        execute!(stdout(), Show).ok();
-                        app.connect(None);
-                        return false;
-                    } else {
-                        app.error_text = "Failed to execute ssh command.\nIs the OpenSSH-client installed?".to_string();
-                        app.show_import_popup = false;
-                        app.show_error_popup = true;
-                        app.app_mode = AppMode::Error;
-                    }
-                }
-                None => (),
-            },
-            KeyCode::Char('e' | 'E' | 'у' | 'У') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                match app.table_state.selected() {
-                    Some(_) => {
-                        app.search();
-                        app.last_app_mode = AppMode::Search;
-                        app.app_mode = AppMode::Edit;
-                        app.focus = Focus::ServerNameField;
-                        app.selected_config_to_fields();
-                        app.show_edit_popup = true;
-                    }
-                    None => (),
+                app.first_row();
+            }
+            Some(Action::Down) => app.next_row(),
+            Some(Action::Up) => app.previous_row(),
+            Some(Action::PageDown) => app.move_down(app.page_rows),
+            Some(Action::PageUp) => app.move_up(app.page_rows),
+            Some(Action::HalfPageDown) => app.move_down((app.page_rows / 2).max(1)),
+            Some(Action::HalfPageUp) => app.move_up((app.page_rows / 2).max(1)),
+            Some(Action::First) => app.first_row(),
+            Some(Action::Last) => app.last_row(),
+            Some(Action::Connect) if app.table_state.selected().is_some() => return connect_selected(app),
+            Some(Action::SearchEdit | Action::Delete) if app.refuse_read_only() => {}
+            Some(Action::Delete) if app.marked.is_empty() && app.refuse_shared() => {}
+            Some(Action::SearchEdit) => {
+                if app.table_state.selected().is_some() {
+                    app.search();
+                    app.last_app_mode = AppMode::Search;
+                    app.app_mode = AppMode::Edit;
+                    app.focus = Focus::ServerNameField;
+                    app.selected_config_to_fields();
+                    app.show_edit_popup = true;
                 }
             }
-            KeyCode::Char('r' | 'R' | 'к' | 'К') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                match app.table_state.selected() {
-                    Some(_) => {
-                        app.app_mode = AppMode::RunCommand;
-                        app.last_app_mode = AppMode::Search;
-                        app.reset_fields();
-                        app.show_run_popup = true;
-                    }
-                    None => (),
+            Some(Action::SearchRun) => {
+                if app.table_state.selected().is_some() {
+                    app.app_mode = AppMode::RunCommand;
+                    app.last_app_mode = AppMode::Search;
+                    app.reset_fields();
+                    app.show_run_popup = true;
                 }
             }
-            KeyCode::Delete => {
-                app.delete_connection();
-                app.search();
-                app.scroll_state = app.scroll_state.content_length(app.ssh_connections.len());
+            Some(Action::SearchPreview) => {
+                if app.table_state.selected().is_some() {
+                    app.last_app_mode = AppMode::Search;
+                    app.app_mode = AppMode::Preview;
+                    app.preview_copied = false;
+                    app.show_preview_popup = true;
+                }
             }
-            _ => match app.focus {
-                Focus::SearchField => {
+            Some(Action::Delete) => app.ask_delete(),
+            Some(Action::SearchExport) => app.open_export(),
+            _ => {
+                if app.focus == Focus::SearchField {
                     app.search_input.handle_event(&Event::Key(key));
                     app.search();
-                    app.scroll_state = app.scroll_state.content_length(app.search_index.len());
-                    app.table_state.select_first();
+                    app.first_row();
                 }
-                _ => (),
-            },
+            }
         },
-        AppMode::Options => match key.code {
-            KeyCode::Esc => {
+        AppMode::Options => match app.keybindings.action(Context::Options, &key) {
+            _ if key.code == KeyCode::Esc => {
                 app.app_mode = AppMode::Normal;
                 app.show_options_popup = false;
             }
-            KeyCode::Up => {
+            _ if key.code == KeyCode::Up => {
                 app.row_height = 3;
                 app.update_appconfig();
             }
-            KeyCode::Down => {
+            _ if key.code == KeyCode::Down => {
                 app.row_height = 1;
                 app.update_appconfig();
             }
-            KeyCode::Right => {
+            _ if key.code == KeyCode::Right => {
                 app.next_color();
                 app.update_appconfig();
             }
-            KeyCode::Left => {
+            _ if key.code == KeyCode::Left => {
                 app.previous_color();
                 app.update_appconfig();
             }
+            Some(Action::OptionsTheme) => {
+                app.next_theme();
+                app.update_appconfig();
+            }
+            Some(Action::OptionsColumns) => {
+                app.app_mode = AppMode::Columns;
+                app.show_options_popup = false;
+                app.show_columns_popup = true;
+            }
             _ => {}
         },
-        AppMode::Sort => match key.code {
-            KeyCode::Esc => {
+        AppMode::Columns => {
+            let cursor = app.column_cursor;
+            match key.code {
+                KeyCode::Esc => {
+                    app.app_mode = AppMode::Options;
+                    app.show_columns_popup = false;
+                    app.show_options_popup = true;
+                }
+                KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) && cursor > 0 => {
+                    app.columns.swap(cursor, cursor - 1);
+                    app.column_cursor -= 1;
+                    app.update_appconfig();
+                }
+                KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) && cursor + 1 < app.columns.len() => {
+                    app.columns.swap(cursor, cursor + 1);
+                    app.column_cursor += 1;
+                    app.update_appconfig();
+                }
+                KeyCode::Up if !key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    app.column_cursor = cursor.saturating_sub(1);
+                }
+                KeyCode::Down if !key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    app.column_cursor = (cursor + 1).min(app.columns.len() - 1);
+                }
+                KeyCode::Char(' ') => {
+                    // At least one column stays visible.
+                    let visible = app.columns.iter().filter(|layout| layout.visible).count();
+                    if !app.columns[cursor].visible || visible > 1 {
+                        app.columns[cursor].visible = !app.columns[cursor].visible;
+                        app.update_appconfig();
+                    }
+                }
+                KeyCode::Right => {
+                    app.columns[cursor].grow();
+                    app.update_appconfig();
+                }
+                KeyCode::Left => {
+                    app.columns[cursor].shrink();
+                    app.update_appconfig();
+                }
+                KeyCode::Char('+' | '=') => {
+                    app.columns[cursor].grow_min();
+                    app.update_appconfig();
+                }
+                KeyCode::Char('-') => {
+                    app.columns[cursor].shrink_min();
+                    app.update_appconfig();
+                }
+                _ => {}
+            }
+        }
+        AppMode::Preview => match app.keybindings.action(Context::Preview, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.show_preview_popup = false;
+                if app.last_app_mode == AppMode::Normal {
+                    app.app_mode = AppMode::Normal
+                };
+                if app.last_app_mode == AppMode::Search {
+                    app.app_mode = AppMode::Search;
+                    app.focus = Focus::SearchField;
+                };
+            }
+            Some(Action::CopyCommand) => {
+                if let Some(i) = app.get_row_index() {
+                    let command_line = command::SshCommand::new(&app.ssh_connections[i]).to_shell_string();
+                    app.preview_copied = command::copy_to_clipboard(&command_line).is_ok();
+                }
+            }
+            _ => {}
+        },
+        AppMode::Confirm => match app.keybindings.action(Context::Confirm, &key) {
+            action if key.code == KeyCode::Enter || action == Some(Action::ConfirmYes) => {
+                app.show_confirm_popup = false;
+                match app.confirm_action {
+                    ConfirmAction::Delete => {
+                        app.delete_connection();
+                        app.app_mode = app.last_app_mode;
+                        if app.app_mode == AppMode::Search {
+                            app.search();
+                        }
+                        app.select_row(app.table_state.selected().unwrap_or_default());
+                    }
+                    ConfirmAction::Purge => {
+                        app.purge_connections();
+                        app.app_mode = AppMode::Trash;
+                    }
+                }
+            }
+            action if key.code == KeyCode::Esc || action == Some(Action::ConfirmNo) => {
+                app.show_confirm_popup = false;
+                app.confirm_indexes.clear();
+                app.app_mode = match app.confirm_action {
+                    ConfirmAction::Delete => app.last_app_mode,
+                    ConfirmAction::Purge => AppMode::Trash,
+                };
+            }
+            _ => {}
+        },
+        AppMode::Profiles => match app.keybindings.action(Context::Profiles, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.show_profiles_popup = false;
+                app.app_mode = AppMode::Normal;
+            }
+            _ if key.code == KeyCode::Enter => {
+                app.show_profiles_popup = false;
+                let entries = app.profile_entries();
+                if let Some(name) = app.profiles_state.selected().and_then(|row| entries.get(row)) {
+                    app.switch_profile(name);
+                }
+            }
+            Some(Action::Down) => {
+                let last = app.profile_entries().len() - 1;
+                let row = app.profiles_state.selected().map_or(0, |row| (row + 1).min(last));
+                app.profiles_state.select(Some(row));
+            }
+            Some(Action::Up) => {
+                let row = app.profiles_state.selected().map_or(0, |row| row.saturating_sub(1));
+                app.profiles_state.select(Some(row));
+            }
+            _ => {}
+        },
+        AppMode::Trash => match app.keybindings.action(Context::Trash, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.show_trash_popup = false;
                 app.app_mode = AppMode::Normal;
-                app.update_config();
+                app.select_row(app.table_state.selected().unwrap_or_default());
             }
-            KeyCode::Char('n' | 'N' | 'т' | 'Т') => {
-                app.sort("name".to_string());
+            Some(Action::Down) => {
+                app.select_trash_row(app.trash_state.selected().map_or(0, |row| row + 1));
             }
-            KeyCode::Char('g' | 'G' | 'п' | 'П') => {
-                app.sort("group".to_string());
+            Some(Action::Up) => {
+                app.select_trash_row(app.trash_state.selected().map_or(0, |row| row.saturating_sub(1)));
             }
-            KeyCode::Char('u' | 'U' | 'г' | 'Г') => {
-                app.sort("username".to_string());
+            Some(Action::TrashRestore) => app.restore_connection(),
+            Some(Action::TrashPurge) => app.ask_purge(false),
+            Some(Action::TrashPurgeAll) => app.ask_purge(true),
+            _ => {}
+        },
+        AppMode::Unlock => match key.code {
+            KeyCode::Esc if app.unlocking_at_startup() => {
+                restore_terminal();
+                execute!(stdout(), Show).ok();
+                return false;
             }
-            KeyCode::Char('h' | 'H' | 'р' | 'Р') => {
-                app.sort("hostname".to_string());
+            KeyCode::Esc => {
+                app.passphrase_input = Input::default();
+                app.show_unlock_popup = false;
+                app.app_mode = AppMode::Normal;
             }
-            KeyCode::Char('p' | 'P' | 'з' | 'З') => {
-                app.sort("port".to_string());
+            KeyCode::Enter => app.unlock(),
+            _ => {
+                app.passphrase_input.handle_event(&Event::Key(key));
             }
+        },
+        AppMode::Sync => match app.keybindings.action(Context::Sync, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.show_sync_popup = false;
+                app.app_mode = AppMode::Normal;
+            }
+            Some(Action::SyncPull) => app.pull_store(),
+            Some(Action::SyncPush) => app.push_store(),
             _ => {}
         },
+        AppMode::Merge => match app.keybindings.action(Context::Merge, &key) {
+            _ if key.code == KeyCode::Esc => {
+                // Nothing was changed yet, so cancelling leaves the repository as it was before the pull.
+                app.merge = None;
+                app.sync_message = "Merge cancelled".to_string();
+                app.show_merge_popup = false;
+                app.show_sync_popup = true;
+                app.app_mode = AppMode::Sync;
+            }
+            _ if key.code == KeyCode::Enter => {
+                app.resolve_merge();
+                app.show_merge_popup = false;
+                app.show_sync_popup = true;
+                app.app_mode = AppMode::Sync;
+            }
+            Some(action @ (Action::MergeLocal | Action::MergeRemote)) => {
+                app.choose_merge_side(action == Action::MergeLocal)
+            }
+            Some(Action::Down) => {
+                let count = app.merge.as_ref().map_or(0, |merge| merge.conflicts().count());
+                app.merge_selected = (app.merge_selected + 1).min(count.saturating_sub(1));
+            }
+            Some(Action::Up) => app.merge_selected = app.merge_selected.saturating_sub(1),
+            _ => {}
+        },
+        AppMode::Sort => match app.keybindings.action(Context::Sort, &key) {
+            _ if key.code == KeyCode::Esc => {
+                app.app_mode = AppMode::Normal;
+            }
+            Some(Action::SortName) => app.sort_by(Column::Name),
+            Some(Action::SortGroup) => app.sort_by(Column::Group),
+            Some(Action::SortUsername) => app.sort_by(Column::Username),
+            Some(Action::SortHostname) => app.sort_by(Column::Hostname),
+            Some(Action::SortPort) => app.sort_by(Column::Port),
+            Some(Action::SortManual) => app.sort_manual(),
+            _ => {}
+        },
+    }
+    true
+}
+
+// Resolves multi-key sequences (e.g. `gg`), numeric counts (e.g. `5j`) and the letter that follows a jump.
+fn normal_action(app: &mut App, key: &KeyEvent) -> Option<Action> {
+    if app.awaiting_jump {
+        app.awaiting_jump = false;
+        if let KeyCode::Char(letter) = key.code {
+            app.jump_to_letter(letter);
+        }
+        return None;
+    }
+    if let KeyCode::Char(digit @ '0'..='9') = key.code {
+        if app.pending_keys.is_empty() && (digit != '0' || app.count.is_some()) {
+            let digit = digit.to_digit(10).unwrap_or_default() as usize;
+            app.count = Some(app.count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+            return None;
+        }
+    }
+    app.pending_keys.push(KeyChord::from_event(key));
+    match app.keybindings.lookup(Context::Normal, &app.pending_keys) {
+        Lookup::Pending => None,
+        Lookup::Action(action) => {
+            app.pending_keys.clear();
+            Some(action)
+        }
+        Lookup::None => {
+            app.pending_keys.clear();
+            app.count = None;
+            None
+        }
+    }
+}
+
+fn connect_selected(app: &mut App) -> bool {
+    if check_openssh() {
+        restore_terminal();
+        execute!(stdout(), Show).ok();
+        app.connect(None);
+        false
+    } else {
+        app.search();
+        app.error_text = "Failed to execute ssh command.\nIs the OpenSSH-client installed?".to_string();
+        app.show_import_popup = false;
+        app.show_error_popup = true;
+        app.app_mode = AppMode::Error;
+        true
+    }
+}
+
+pub fn handle_mouse_event(app: &mut App, mouse: MouseEvent) -> bool {
+    let position = Position::new(mouse.column, mouse.row);
+    match app.app_mode {
+        AppMode::Normal | AppMode::Search => match mouse.kind {
+            MouseEventKind::ScrollDown if app.table_area.contains(position) => app.move_down(1),
+            MouseEventKind::ScrollUp if app.table_area.contains(position) => app.move_up(1),
+            MouseEventKind::Down(MouseButton::Left) if app.table_area.contains(position) => {
+                if mouse.row == app.table_area.y {
+                    if app.app_mode == AppMode::Normal {
+                        let clicked = ui::visible_columns(app)
+                            .into_iter()
+                            .zip(&app.column_areas)
+                            .find(|(_, area)| mouse.column >= area.x && mouse.column < area.x + area.width)
+                            .map(|(layout, _)| layout.column);
+                        if let Some(column) = clicked {
+                            app.sort_by(column);
+                        }
+                    }
+                } else {
+                    let row = (mouse.row - app.table_area.y - 1) / app.row_height;
+                    let i = app.table_state.offset() + row as usize;
+                    if i < app.visible_rows() {
+                        let now = Instant::now();
+                        let double_click = app
+                            .last_click
+                            .is_some_and(|(time, row)| row == i && now.duration_since(time) < DOUBLE_CLICK_INTERVAL);
+                        app.select_row(i);
+                        if double_click {
+                            app.last_click = None;
+                            return connect_selected(app);
+                        }
+                        app.last_click = Some((now, i));
+                    }
+                }
+            }
+            _ => {}
+        },
+        AppMode::New | AppMode::Edit if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+            for (focus, area) in &app.field_areas {
+                if area.contains(position) {
+                    app.focus = *focus;
+                }
+            }
+        }
+        _ => {}
     }
     true
 }
@@ -0,0 +1,358 @@
+use crate::*;
+
+// A connection found by an importer, added to the list only if it stays selected in the import preview.
+pub struct Candidate {
+    pub connection: SSHConnection,
+    pub selected: bool,
+    pub duplicate: bool,
+    // Pattern blocks of the ssh config that set options of the connection.
+    pub origin: Vec<String>,
+}
+
+// What a file importer found, with notes about entries it skipped or could not translate.
+#[derive(Default)]
+pub struct Imported {
+    pub connections: Vec<SSHConnection>,
+    pub notes: Vec<String>,
+    // Where the options of each connection came from, in the order of the connections, when the importer knows.
+    pub origins: Vec<Vec<String>>,
+    // Entries that could not be imported, with the reason.
+    pub failures: Vec<String>,
+    // Settings of imported entries that have no ssh option and were left out.
+    pub untranslated: Vec<String>,
+    // Files read, when the importer read more than the chosen one.
+    pub files: Vec<String>,
+}
+
+// What an import found and did, shown once it is done.
+#[derive(Default)]
+pub struct ImportReport {
+    pub source: String,
+    pub found: usize,
+    pub imported: usize,
+    pub duplicates: usize,
+    pub deselected: usize,
+    pub replaced: Option<usize>,
+    pub failures: Vec<String>,
+    pub untranslated: Vec<String>,
+    pub notes: Vec<String>,
+    pub files: Vec<String>,
+}
+
+impl ImportReport {
+    // Titled sections; empty ones are left out.
+    pub fn sections(&self) -> Vec<(String, Vec<String>)> {
+        let mut summary = vec![
+            format!("Source: {}", self.source),
+            format!("Hosts found: {}", self.found),
+            format!("Imported: {}", self.imported),
+            format!("Skipped as duplicates: {}", self.duplicates),
+        ];
+        if self.deselected > 0 {
+            summary.push(format!("Deselected: {}", self.deselected));
+        }
+        if let Some(replaced) = self.replaced {
+            summary.push(format!("Moved to the trash: {}", replaced));
+        }
+        let mut sections = vec![("Summary".to_string(), summary)];
+        let mut add = |title: String, lines: &[String]| {
+            if !lines.is_empty() {
+                sections.push((title, lines.to_vec()));
+            }
+        };
+        add(format!("Failed ({})", self.failures.len()), &self.failures);
+        add(format!("Options not imported ({})", self.untranslated.len()), &self.untranslated);
+        add("Notes".to_string(), &self.notes);
+        add(format!("Files read ({})", self.files.len()), &self.files);
+        sections
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.sections().iter().map(|(_, lines)| lines.len() + 2).sum()
+    }
+}
+
+// Where an imported connection was read from, kept so it can be read again from there.
+#[derive(Deserialize, Serialize, PartialEq, Clone)]
+pub struct ImportSource {
+    pub kind: SourceKind,
+    pub path: String,
+    // Line of the Host or Match block in an ssh config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    // The Host alias, or the name the entry had in the file, which may since have been renamed in the list.
+    pub alias: String,
+    pub imported: i64,
+    // Set when the Host block or the file is gone; checked when the store is loaded.
+    #[serde(skip)]
+    pub missing: bool,
+}
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceKind {
+    SshConfig,
+    KnownHosts,
+    File,
+}
+
+pub struct ImportPreview {
+    pub source: String,
+    pub candidates: Vec<Candidate>,
+    // Summary lines shown above the list, such as entries that were skipped.
+    pub notes: Vec<String>,
+    // Set when the import replaces the list: the number of connections that move to the trash.
+    pub replaced: Option<usize>,
+    // Kept for the report shown after the import.
+    pub failures: Vec<String>,
+    pub untranslated: Vec<String>,
+    pub files: Vec<String>,
+}
+
+impl ImportPreview {
+    // Connections without a source of their own are marked as read from `path`.
+    pub fn new(kind: SourceKind, path: &Path, imported: Imported, existing: &[SSHConnection]) -> Self {
+        let now = chrono::Local::now().timestamp();
+        let file = std::path::absolute(path).unwrap_or(path.to_path_buf()).display().to_string();
+        let mut preview = ImportPreview {
+            source: path.display().to_string(),
+            candidates: imported
+                .connections
+                .into_iter()
+                .map(|mut connection| {
+                    connection.source.get_or_insert_with(|| ImportSource {
+                        kind,
+                        path: file.clone(),
+                        line: None,
+                        alias: connection.server_name.clone(),
+                        imported: now,
+                        missing: false,
+                    });
+                    connection
+                })
+                .zip(imported.origins.into_iter().chain(std::iter::repeat(vec![])))
+                .map(|(connection, origin)| Candidate {
+                    connection,
+                    selected: true,
+                    duplicate: false,
+                    origin,
+                })
+                .collect(),
+            notes: imported.notes,
+            replaced: None,
+            failures: imported.failures,
+            untranslated: imported.untranslated,
+            files: if imported.files.is_empty() { vec![path.display().to_string()] } else { imported.files },
+        };
+        preview.mark_duplicates(existing);
+        preview
+    }
+
+    // Connections that already exist in the list, or earlier in the import, start deselected.
+    fn mark_duplicates(&mut self, existing: &[SSHConnection]) {
+        for i in 0..self.candidates.len() {
+            let (earlier, rest) = self.candidates.split_at_mut(i);
+            let candidate = &mut rest[0];
+            candidate.duplicate = existing
+                .iter()
+                .filter(|other| other.deleted.is_none())
+                .chain(earlier.iter().map(|candidate| &candidate.connection))
+                .any(|other| is_duplicate(&candidate.connection, other));
+            candidate.selected = !candidate.duplicate;
+        }
+    }
+
+    // Switches between adding to the list and replacing it; a replaced list is not checked for duplicates.
+    pub fn toggle_replace(&mut self, existing: &[SSHConnection], replaceable: usize) {
+        self.replaced = match self.replaced {
+            Some(_) => None,
+            None => Some(replaceable),
+        };
+        self.mark_duplicates(if self.replaced.is_some() { &[] } else { existing });
+    }
+
+    pub fn report(&self) -> ImportReport {
+        let unselected = |duplicate: bool| {
+            self.candidates.iter().filter(|candidate| !candidate.selected && candidate.duplicate == duplicate).count()
+        };
+        ImportReport {
+            source: self.source.clone(),
+            found: self.candidates.len() + self.failures.len(),
+            imported: self.selected(),
+            duplicates: unselected(true),
+            deselected: unselected(false),
+            replaced: self.replaced,
+            failures: self.failures.clone(),
+            untranslated: self.untranslated.clone(),
+            notes: self.notes.clone(),
+            files: self.files.clone(),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.candidates.iter().filter(|candidate| candidate.selected).count()
+    }
+
+    pub fn toggle(&mut self, i: usize) {
+        if let Some(candidate) = self.candidates.get_mut(i) {
+            candidate.selected = !candidate.selected;
+        }
+    }
+
+    // Selects every candidate, or clears the selection when all are selected already.
+    pub fn toggle_all(&mut self) {
+        let select = self.candidates.iter().any(|candidate| !candidate.selected);
+        for candidate in &mut self.candidates {
+            candidate.selected = select;
+        }
+    }
+
+    pub fn into_selected(self) -> Vec<SSHConnection> {
+        self.candidates
+            .into_iter()
+            .filter(|candidate| candidate.selected)
+            .map(|candidate| candidate.connection)
+            .collect()
+    }
+}
+
+// A file format connections can be imported from. Formats are recognized by the path and the file content, so new
+// ones only need an entry in IMPORTERS.
+pub trait Importer {
+    // `text` is the decoded file, or empty for a directory.
+    fn detect(&self, path: &Path, text: &str) -> bool;
+    fn read(&self, path: &Path) -> Result<Imported, String>;
+}
+
+// Nested groups deeper than this are cut off when importers follow parent groups, which also stops cycles of parents.
+pub const MAX_GROUP_DEPTH: usize = 16;
+
+// Asked in order: formats recognized by their content come before those recognized by the extension alone.
+const IMPORTERS: [&dyn Importer; 8] = [
+    &portable::Portable,
+    &termius::TermiusJson,
+    &termius::TermiusCsv,
+    &remmina::Remmina,
+    &asbru::Asbru,
+    &putty::Sessions,
+    &ansible::YamlInventory,
+    &ansible::IniInventory,
+];
+
+// Picks the importer for a file or directory. None means an unknown format, or CSV that needs its columns mapped.
+pub fn find_importer(path: &Path) -> Result<Option<&'static dyn Importer>, String> {
+    if !path.exists() {
+        return Err(format!("{} does not exist.", path.display()));
+    }
+    let text = if path.is_dir() {
+        String::new()
+    } else {
+        decode(&fs::read(path).map_err(|text| format!("{}: {}", path.display(), text))?)
+    };
+    Ok(IMPORTERS.into_iter().find(|importer| importer.detect(path, &text)))
+}
+
+// Reads a file without the app, where CSV columns are mapped by their header.
+pub fn read_file(path: &Path) -> Result<Imported, String> {
+    match find_importer(path)? {
+        Some(importer) => importer.read(path),
+        None if matches!(extension(path).as_str(), "csv" | "tsv") => Ok(spreadsheet::CsvImport::read(path)?.connections()),
+        None => Err(format!("{}: unknown file format.", path.display())),
+    }
+}
+
+pub fn extension(path: &Path) -> String {
+    path.extension().unwrap_or_default().to_string_lossy().to_lowercase()
+}
+
+// Registry exports and some spreadsheet exports are written in UTF-16, most other files in UTF-8.
+pub fn decode(data: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| from([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+    match data {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes),
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).to_string(),
+        _ => String::from_utf8_lossy(data).to_string(),
+    }
+}
+
+// Quotes a value for the options field, which is split like a shell command line.
+pub fn quote(text: &str) -> String {
+    shlex::try_quote(text).map(|text| text.to_string()).unwrap_or(text.to_string())
+}
+
+// The same name, or the same host and port reached as the same user (an importer without usernames matches any).
+fn is_duplicate(connection: &SSHConnection, other: &SSHConnection) -> bool {
+    if connection.server_name.eq_ignore_ascii_case(&other.server_name) {
+        return true;
+    }
+    !connection.hostname.is_empty()
+        && connection.hostname.eq_ignore_ascii_case(&other.hostname)
+        && port_or_default(&connection.port) == port_or_default(&other.port)
+        && (connection.username.is_empty() || connection.username == other.username)
+}
+
+fn port_or_default(port: &str) -> &str {
+    if port.is_empty() { "22" } else { port }
+}
+
+// The fields importer tests compare.
+#[cfg(test)]
+pub fn fields(connection: &SSHConnection) -> [&str; 6] {
+    [
+        &connection.server_name,
+        &connection.group_name,
+        &connection.username,
+        &connection.hostname,
+        &connection.port,
+        &connection.options,
+    ]
+}
+
+pub fn connection(server_name: String, username: String, hostname: String, port: String, options: String) -> SSHConnection {
+    SSHConnection {
+        server_name,
+        group_name: String::new(),
+        username,
+        hostname,
+        port,
+        options,
+        notes: String::new(),
+        history: vec![],
+        deleted: None,
+        overrides: None,
+        source: None,
+        profile: String::new(),
+        shared: None,
+        conflict: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str, username: &str, hostname: &str, port: &str) -> SSHConnection {
+        connection(name.to_string(), username.to_string(), hostname.to_string(), port.to_string(), String::new())
+    }
+
+    #[test]
+    fn names_match_without_case() {
+        assert!(is_duplicate(&host("Web1", "a", "10.0.0.1", "22"), &host("web1", "b", "10.0.0.2", "2222")));
+        assert!(!is_duplicate(&host("web1", "a", "10.0.0.1", "22"), &host("web2", "a", "10.0.0.2", "22")));
+    }
+
+    #[test]
+    fn hosts_match_on_port_and_user() {
+        let existing = host("web", "admin", "Web.Example.com", "22");
+        assert!(is_duplicate(&host("other", "admin", "web.example.com", ""), &existing));
+        assert!(!is_duplicate(&host("other", "admin", "web.example.com", "2222"), &existing));
+        assert!(!is_duplicate(&host("other", "root", "web.example.com", "22"), &existing));
+        // Importers without usernames, such as known_hosts, match any user.
+        assert!(is_duplicate(&host("other", "", "web.example.com", "22"), &existing));
+        assert!(!is_duplicate(&host("other", "", "", "22"), &host("web", "", "", "22")));
+    }
+}
@@ -0,0 +1,488 @@
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    First,
+    Last,
+    PageDown,
+    PageUp,
+    HalfPageDown,
+    HalfPageUp,
+    Jump,
+    Connect,
+    Run,
+    Search,
+    Import,
+    RefreshSource,
+    Export,
+    Options,
+    Details,
+    Add,
+    Edit,
+    Copy,
+    Move,
+    Sort,
+    Delete,
+    Preview,
+    SearchEdit,
+    SearchRun,
+    SearchPreview,
+    SearchExport,
+    MoveDown,
+    MoveUp,
+    SortName,
+    SortGroup,
+    SortUsername,
+    SortHostname,
+    SortPort,
+    SortManual,
+    Mark,
+    Trash,
+    TrashRestore,
+    TrashPurge,
+    TrashPurgeAll,
+    Profiles,
+    Sync,
+    SyncPull,
+    SyncPush,
+    MergeLocal,
+    MergeRemote,
+    ImportSelectAll,
+    ImportReplace,
+    CopyCommand,
+    OptionsTheme,
+    OptionsColumns,
+    ConfirmYes,
+    ConfirmNo,
+    ImportSshConfig,
+    ImportKnownHosts,
+    ImportFile,
+    CsvHeader,
+    ExportPersonal,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Context {
+    Normal,
+    Search,
+    Move,
+    Sort,
+    Preview,
+    Trash,
+    Profiles,
+    Sync,
+    Merge,
+    ImportPreview,
+    ImportReport,
+    Options,
+    Confirm,
+    Import,
+    CsvColumns,
+    Export,
+}
+
+const ACTIONS: [(&str, Action, &[Context], &[&str]); 60] = [
+    ("quit", Action::Quit, &[Context::Normal], &["esc"]),
+    ("down", Action::Down, &[Context::Normal, Context::Search, Context::Trash, Context::Profiles, Context::Merge, Context::ImportPreview, Context::ImportReport], &["down", "tab", "j", "о"]),
+    ("up", Action::Up, &[Context::Normal, Context::Search, Context::Trash, Context::Profiles, Context::Merge, Context::ImportPreview, Context::ImportReport], &["up", "shift+tab", "k", "л"]),
+    ("first", Action::First, &[Context::Normal, Context::Search], &["home", "g g", "п п"]),
+    ("last", Action::Last, &[Context::Normal, Context::Search], &["end", "G", "П"]),
+    ("page_down", Action::PageDown, &[Context::Normal, Context::Search, Context::ImportReport], &["pagedown"]),
+    ("page_up", Action::PageUp, &[Context::Normal, Context::Search, Context::ImportReport], &["pageup"]),
+    ("half_page_down", Action::HalfPageDown, &[Context::Normal, Context::Search], &["ctrl+d", "ctrl+в"]),
+    ("half_page_up", Action::HalfPageUp, &[Context::Normal, Context::Search], &["ctrl+u", "ctrl+г"]),
+    ("jump", Action::Jump, &[Context::Normal], &["f", "а"]),
+    ("connect", Action::Connect, &[Context::Normal, Context::Search], &["enter"]),
+    ("run", Action::Run, &[Context::Normal], &["R", "r", "К", "к"]),
+    ("search", Action::Search, &[Context::Normal], &["/"]),
+    ("import", Action::Import, &[Context::Normal], &["I", "i", "Ш", "ш"]),
+    ("refresh_source", Action::RefreshSource, &[Context::Normal], &["U", "u", "Г", "г"]),
+    ("export", Action::Export, &[Context::Normal], &["X", "x", "Ч", "ч"]),
+    ("options", Action::Options, &[Context::Normal], &["O", "o", "Щ", "щ"]),
+    ("details", Action::Details, &[Context::Normal], &["D", "d", "В", "в"]),
+    ("add", Action::Add, &[Context::Normal], &["A", "a", "Ф", "ф"]),
+    ("edit", Action::Edit, &[Context::Normal], &["E", "e", "У", "у"]),
+    ("copy", Action::Copy, &[Context::Normal], &["C", "c", "С", "с"]),
+    ("move", Action::Move, &[Context::Normal], &["M", "m", "Ь", "ь"]),
+    ("sort", Action::Sort, &[Context::Normal], &["S", "s", "Ы", "ы"]),
+    ("delete", Action::Delete, &[Context::Normal, Context::Search], &["del"]),
+    ("preview", Action::Preview, &[Context::Normal], &["P", "p", "З", "з"]),
+    ("mark", Action::Mark, &[Context::Normal, Context::ImportPreview], &["space"]),
+    ("trash", Action::Trash, &[Context::Normal], &["T", "t", "Е", "е"]),
+    ("profiles", Action::Profiles, &[Context::Normal], &["W", "w", "Ц", "ц"]),
+    ("sync", Action::Sync, &[Context::Normal], &["Y", "y", "Н", "н"]),
+    ("search_edit", Action::SearchEdit, &[Context::Search], &["ctrl+e", "ctrl+E", "ctrl+у", "ctrl+У"]),
+    ("search_run", Action::SearchRun, &[Context::Search], &["ctrl+r", "ctrl+R", "ctrl+к", "ctrl+К"]),
+    ("search_preview", Action::SearchPreview, &[Context::Search], &["ctrl+p", "ctrl+P", "ctrl+з", "ctrl+З"]),
+    ("search_export", Action::SearchExport, &[Context::Search], &["ctrl+x", "ctrl+X", "ctrl+ч", "ctrl+Ч"]),
+    ("move_down", Action::MoveDown, &[Context::Move], &["down"]),
+    ("move_up", Action::MoveUp, &[Context::Move], &["up"]),
+    ("sort_name", Action::SortName, &[Context::Sort], &["n", "N", "т", "Т"]),
+    ("sort_group", Action::SortGroup, &[Context::Sort], &["g", "G", "п", "П"]),
+    ("sort_username", Action::SortUsername, &[Context::Sort], &["u", "U", "г", "Г"]),
+    ("sort_hostname", Action::SortHostname, &[Context::Sort], &["h", "H", "р", "Р"]),
+    ("sort_port", Action::SortPort, &[Context::Sort], &["p", "P", "з", "З"]),
+    ("sort_manual", Action::SortManual, &[Context::Sort], &["m", "M", "ь", "Ь"]),
+    ("copy_command", Action::CopyCommand, &[Context::Preview], &["Y", "y", "Н", "н"]),
+    ("trash_restore", Action::TrashRestore, &[Context::Trash], &["R", "r", "К", "к"]),
+    ("trash_purge", Action::TrashPurge, &[Context::Trash], &["del"]),
+    ("trash_purge_all", Action::TrashPurgeAll, &[Context::Trash], &["X", "x", "Ч", "ч"]),
+    ("sync_pull", Action::SyncPull, &[Context::Sync], &["L", "l", "Д", "д"]),
+    ("sync_push", Action::SyncPush, &[Context::Sync], &["U", "u", "Г", "г"]),
+    ("merge_local", Action::MergeLocal, &[Context::Merge], &["L", "l", "Д", "д"]),
+    ("merge_remote", Action::MergeRemote, &[Context::Merge], &["R", "r", "К", "к"]),
+    ("import_select_all", Action::ImportSelectAll, &[Context::ImportPreview], &["A", "a", "Ф", "ф"]),
+    ("import_replace", Action::ImportReplace, &[Context::ImportPreview], &["R", "r", "К", "к"]),
+    ("options_theme", Action::OptionsTheme, &[Context::Options], &["T", "t", "Е", "е"]),
+    ("options_columns", Action::OptionsColumns, &[Context::Options], &["C", "c", "С", "с"]),
+    ("confirm_yes", Action::ConfirmYes, &[Context::Confirm], &["Y", "y", "Н", "н"]),
+    ("confirm_no", Action::ConfirmNo, &[Context::Confirm], &["N", "n", "Т", "т"]),
+    ("import_ssh_config", Action::ImportSshConfig, &[Context::Import], &["I", "i", "Ш", "ш"]),
+    ("import_known_hosts", Action::ImportKnownHosts, &[Context::Import], &["K", "k", "Л", "л"]),
+    ("import_file", Action::ImportFile, &[Context::Import], &["F", "f", "А", "а"]),
+    ("csv_header", Action::CsvHeader, &[Context::CsvColumns], &["H", "h", "Р", "р"]),
+    ("export_personal", Action::ExportPersonal, &[Context::Export], &["ctrl+p", "ctrl+P", "ctrl+з", "ctrl+З"]),
+];
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn to_vec(&self) -> Vec<String> {
+        match self {
+            KeyList::One(key) => vec![key.clone()],
+            KeyList::Many(keys) => keys.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        // Shift is already reflected in the character itself, and BackTab always carries it.
+        let modifiers = match code {
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let parts: Vec<&str> = if text == "+" { vec!["+"] } else { text.split('+').collect() };
+        let (key, modifier_parts) = parts.split_last()?;
+        for modifier in modifier_parts {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+        let code = match key.to_lowercase().as_str() {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "del" | "delete" => KeyCode::Delete,
+            "backspace" => KeyCode::Backspace,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            name if name.len() > 1 && name.starts_with('f') => KeyCode::F(name[1..].parse().ok()?),
+            _ => {
+                let mut chars = key.chars();
+                let c = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    KeyCode::Char(c.to_uppercase().next().unwrap_or(c))
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+        };
+        Some(Self::new(code, modifiers))
+    }
+
+    fn is_digit(&self) -> bool {
+        matches!(self.code, KeyCode::Char('0'..='9'))
+    }
+
+    fn is_printable(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift+Tab".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Insert => "Ins".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::CONTROL) => c.to_uppercase().to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            _ => "?".to_string(),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl+");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt+");
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            label.push_str("Shift+");
+        }
+        label.push_str(&key);
+        label
+    }
+}
+
+type KeySequence = Vec<KeyChord>;
+
+fn parse_sequence(text: &str) -> Option<KeySequence> {
+    let sequence: Option<KeySequence> = text.split_whitespace().map(KeyChord::parse).collect();
+    sequence.filter(|sequence| !sequence.is_empty())
+}
+
+fn sequence_label(sequence: &[KeyChord]) -> String {
+    sequence.iter().map(KeyChord::label).collect()
+}
+
+pub enum Lookup {
+    Action(Action),
+    Pending,
+    None,
+}
+
+pub struct Keybindings {
+    bindings: Vec<(Action, KeySequence)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        let mut bindings = vec![];
+        for (_, action, _, keys) in ACTIONS {
+            for key in keys {
+                bindings.push((action, parse_sequence(key).unwrap()));
+            }
+        }
+        Self { bindings }
+    }
+}
+
+impl Keybindings {
+    pub fn from_config(config: &BTreeMap<String, KeyList>) -> Result<Self, String> {
+        let mut keybindings = Self::default();
+        for (name, keys) in config {
+            let Some((_, action, _, _)) = ACTIONS.iter().find(|(action_name, ..)| action_name == name) else {
+                return Err(format!("Unknown keybinding action: {}", name));
+            };
+            let mut sequences = vec![];
+            for key in keys.to_vec() {
+                match parse_sequence(&key) {
+                    // Digits typed first in the table start a count (e.g. `5j`), so they can't start a binding there.
+                    Some(sequence) if sequence[0].is_digit() && contexts(*action).contains(&Context::Normal) => {
+                        return Err(format!("Key \"{}\" for {} is a count digit", key, name))
+                    }
+                    Some(sequence) => sequences.push(sequence),
+                    None => return Err(format!("Invalid key \"{}\" for {}", key, name)),
+                }
+            }
+            keybindings.bindings.retain(|(bound_action, _)| bound_action != action);
+            keybindings.bindings.extend(sequences.into_iter().map(|sequence| (*action, sequence)));
+        }
+        keybindings.check_conflicts()?;
+        Ok(keybindings)
+    }
+
+    // A sequence that is a prefix of another one in the same mode would make the longer one unreachable.
+    fn check_conflicts(&self) -> Result<(), String> {
+        for (i, (action, sequence)) in self.bindings.iter().enumerate() {
+            for (other_action, other_sequence) in &self.bindings[i + 1..] {
+                let (shorter, longer) = if sequence.len() <= other_sequence.len() {
+                    (sequence, other_sequence)
+                } else {
+                    (other_sequence, sequence)
+                };
+                if longer.starts_with(shorter)
+                    && action != other_action
+                    && contexts(*action).iter().any(|context| contexts(*other_action).contains(context))
+                {
+                    return Err(format!(
+                        "Key {} is bound to both {} and {}",
+                        sequence_label(shorter),
+                        name(*action),
+                        name(*other_action)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn lookup(&self, context: Context, keys: &[KeyChord]) -> Lookup {
+        let mut pending = false;
+        for (action, sequence) in &self.bindings {
+            if !contexts(*action).contains(&context) || !sequence.starts_with(keys) {
+                continue;
+            }
+            if sequence.len() == keys.len() {
+                return Lookup::Action(*action);
+            }
+            pending = true;
+        }
+        if pending {
+            Lookup::Pending
+        } else {
+            Lookup::None
+        }
+    }
+
+    pub fn action(&self, context: Context, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        // Printable keys belong to the search and export path inputs, so only chords with Ctrl/Alt or special keys act
+        // there.
+        if matches!(context, Context::Search | Context::Export) && chord.is_printable() {
+            return None;
+        }
+        match self.lookup(context, &[chord]) {
+            Lookup::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(bound_action, _)| *bound_action == action)
+            .map(|(_, sequence)| sequence_label(sequence))
+            .unwrap_or_else(|| "-".to_string())
+    }
+}
+
+fn contexts(action: Action) -> &'static [Context] {
+    ACTIONS
+        .iter()
+        .find(|(_, bound_action, ..)| *bound_action == action)
+        .map(|(_, _, contexts, _)| *contexts)
+        .unwrap_or(&[])
+}
+
+fn name(action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(_, bound_action, ..)| *bound_action == action)
+        .map(|(name, ..)| *name)
+        .unwrap_or("")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(bindings: &[(&str, &str)]) -> BTreeMap<String, KeyList> {
+        bindings.iter().map(|(name, key)| (name.to_string(), KeyList::One(key.to_string()))).collect()
+    }
+
+    fn chord(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord::new(code, modifiers)
+    }
+
+    #[test]
+    fn parses_chords_and_sequences() {
+        assert_eq!(KeyChord::parse("ctrl+x"), Some(chord(KeyCode::Char('x'), KeyModifiers::CONTROL)));
+        assert_eq!(KeyChord::parse("Ctrl+Alt+Del"), Some(chord(KeyCode::Delete, KeyModifiers::CONTROL | KeyModifiers::ALT)));
+        assert_eq!(KeyChord::parse("shift+a"), Some(chord(KeyCode::Char('A'), KeyModifiers::NONE)));
+        assert_eq!(KeyChord::parse("shift+tab"), Some(chord(KeyCode::BackTab, KeyModifiers::NONE)));
+        assert_eq!(KeyChord::parse("f5"), Some(chord(KeyCode::F(5), KeyModifiers::NONE)));
+        assert_eq!(KeyChord::parse("+"), Some(chord(KeyCode::Char('+'), KeyModifiers::NONE)));
+        assert_eq!(
+            parse_sequence("g g"),
+            Some(vec![chord(KeyCode::Char('g'), KeyModifiers::NONE), chord(KeyCode::Char('g'), KeyModifiers::NONE)])
+        );
+        for invalid in ["hyper+x", "xy", "fx", "ctrl+", ""] {
+            assert_eq!(KeyChord::parse(invalid), None, "{}", invalid);
+        }
+        assert_eq!(parse_sequence("  "), None);
+        assert!(Keybindings::from_config(&config(&[("down", "ctrl+nope")])).is_err());
+        assert!(Keybindings::from_config(&config(&[("fly", "x")])).is_err());
+    }
+
+    #[test]
+    fn sequences_wait_for_their_next_key() {
+        let keybindings = Keybindings::default();
+        let g = chord(KeyCode::Char('g'), KeyModifiers::NONE);
+        assert!(matches!(keybindings.lookup(Context::Normal, &[g]), Lookup::Pending));
+        assert!(matches!(keybindings.lookup(Context::Normal, &[g, g]), Lookup::Action(Action::First)));
+        assert!(matches!(keybindings.lookup(Context::Sort, &[g]), Lookup::Action(Action::SortGroup)));
+        assert!(matches!(keybindings.lookup(Context::Normal, &[chord(KeyCode::Char('z'), KeyModifiers::NONE)]), Lookup::None));
+    }
+
+    #[test]
+    fn rejects_prefixes_within_one_context() {
+        assert_eq!(
+            Keybindings::from_config(&config(&[("jump", "g")])).err().as_deref(),
+            Some("Key g is bound to both first and jump")
+        );
+        assert_eq!(
+            Keybindings::from_config(&config(&[("details", "ctrl+d")])).err().as_deref(),
+            Some("Key Ctrl+D is bound to both half_page_down and details")
+        );
+        // The same key may do different things in different contexts.
+        let keybindings = Keybindings::from_config(&config(&[("sort_name", "f"), ("first", "g")])).unwrap();
+        assert_eq!(keybindings.label(Action::SortName), "f");
+        assert_eq!(keybindings.label(Action::First), "g");
+    }
+
+    #[test]
+    fn rejects_count_digits_in_the_table() {
+        assert_eq!(
+            Keybindings::from_config(&config(&[("down", "5")])).err().as_deref(),
+            Some("Key \"5\" for down is a count digit")
+        );
+        assert!(Keybindings::from_config(&config(&[("details", "1 d")])).is_err());
+        assert!(Keybindings::from_config(&config(&[("details", "d 1")])).is_ok());
+        assert!(Keybindings::from_config(&config(&[("sort_name", "1")])).is_ok());
+    }
+}
@@ -0,0 +1,171 @@
+use crate::*;
+use std::net::IpAddr;
+
+pub struct KnownHosts {
+    pub connections: Vec<SSHConnection>,
+    // Lines whose host names are hashed (HashKnownHosts yes) and cannot be read back.
+    pub hashed: usize,
+}
+
+pub fn get_known_hosts_path() -> PathBuf {
+    let mut path = env::home_dir().unwrap_or_default();
+    path.push(".ssh");
+    path.push("known_hosts");
+    path
+}
+
+// Each host becomes one connection; names sharing a key line (usually a host name and its address) and the lines
+// for its other key types are folded into it.
+pub fn read_known_hosts(path: &Path) -> std::io::Result<KnownHosts> {
+    let data = fs::read_to_string(path)?;
+    let mut hosts: Vec<(Vec<String>, String)> = vec![];
+    let mut hashed = 0;
+    for line in data.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(names) = parts.next() else {
+            continue;
+        };
+        // Comments, @cert-authority and @revoked lines, and lines without a key.
+        if names.starts_with('#') || names.starts_with('@') || parts.next().is_none() {
+            continue;
+        }
+        if names.starts_with("|1|") {
+            hashed += 1;
+            continue;
+        }
+        let line_hosts: Vec<(String, String)> = names.split(',').filter_map(parse_host).collect();
+        let mut ports: Vec<&String> = line_hosts.iter().map(|(_, port)| port).collect();
+        ports.sort();
+        ports.dedup();
+        for port in ports {
+            let line_names: Vec<&String> =
+                line_hosts.iter().filter(|(_, other)| other == port).map(|(name, _)| name).collect();
+            let known = |names: &Vec<String>, name: &String| names.iter().any(|known| known.eq_ignore_ascii_case(name));
+            let i = match hosts
+                .iter()
+                .position(|(names, known_port)| known_port == port && line_names.iter().any(|name| known(names, name)))
+            {
+                Some(i) => i,
+                None => {
+                    hosts.push((vec![], port.clone()));
+                    hosts.len() - 1
+                }
+            };
+            for name in line_names {
+                if !known(&hosts[i].0, name) {
+                    hosts[i].0.push(name.clone());
+                }
+            }
+        }
+    }
+    let connections = hosts
+        .into_iter()
+        .map(|(names, port)| {
+            let primary = names
+                .iter()
+                .find(|name| name.parse::<IpAddr>().is_err())
+                .unwrap_or(&names[0])
+                .clone();
+            let server_name = if port == "22" {
+                primary.clone()
+            } else {
+                format!("{}:{}", primary, port)
+            };
+            let mut connection = import::connection(server_name, String::new(), primary.clone(), port, String::new());
+            let others: Vec<&String> = names.iter().filter(|name| **name != primary).collect();
+            if !others.is_empty() {
+                connection.notes = format!(
+                    "Also known as {}",
+                    others.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", ")
+                );
+            }
+            connection
+        })
+        .collect();
+    Ok(KnownHosts { connections, hashed })
+}
+
+// Reads "host" or "[host]:port"; wildcard and negated patterns do not name a host.
+fn parse_host(name: &str) -> Option<(String, String)> {
+    if name.is_empty() || name.contains(['*', '?', '!']) {
+        return None;
+    }
+    if let Some(rest) = name.strip_prefix('[') {
+        let (host, port) = rest.split_once("]:")?;
+        if host.is_empty() || port.parse::<u16>().is_err() {
+            return None;
+        }
+        return Some((host.to_string(), port.to_string()));
+    }
+    Some((name.to_string(), "22".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(data: &str) -> KnownHosts {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_hosts");
+        fs::write(&path, data).unwrap();
+        read_known_hosts(&path).unwrap()
+    }
+
+    fn summary(known_hosts: &KnownHosts) -> Vec<(&str, &str, &str, &str)> {
+        known_hosts
+            .connections
+            .iter()
+            .map(|connection| {
+                (
+                    connection.server_name.as_str(),
+                    connection.hostname.as_str(),
+                    connection.port.as_str(),
+                    connection.notes.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_bracketed_hosts_with_ports() {
+        let known_hosts = read("[git.example.com]:2222 ssh-ed25519 AAAA\n[10.0.0.5]:22 ssh-rsa AAAA\n");
+        assert_eq!(
+            summary(&known_hosts),
+            [("git.example.com:2222", "git.example.com", "2222", ""), ("10.0.0.5", "10.0.0.5", "22", "")]
+        );
+    }
+
+    #[test]
+    fn folds_aliases_and_key_types_into_one_connection() {
+        let known_hosts = read(
+            "10.0.0.1,web1 ssh-ed25519 AAAA\n\
+             web1 ecdsa-sha2-nistp256 AAAA\n\
+             WEB1,web1.example.com ssh-rsa AAAA\n",
+        );
+        assert_eq!(
+            summary(&known_hosts),
+            [("web1", "web1", "22", "Also known as 10.0.0.1, web1.example.com")]
+        );
+    }
+
+    #[test]
+    fn skips_and_counts_hashed_names() {
+        let known_hosts = read("|1|c2FsdA==|aGFzaA== ssh-ed25519 AAAA\n|1|b3RoZXI=|aGFzaA== ssh-rsa AAAA\ndb1 ssh-rsa AAAA\n");
+        assert_eq!(known_hosts.hashed, 2);
+        assert_eq!(summary(&known_hosts), [("db1", "db1", "22", "")]);
+    }
+
+    #[test]
+    fn skips_markers_comments_and_patterns() {
+        let known_hosts = read(
+            "# comment\n\
+             @cert-authority *.example.com ssh-rsa AAAA\n\
+             @revoked web2 ssh-rsa AAAA\n\
+             *.internal,!db?.internal ssh-rsa AAAA\n\
+             web3\n\
+             web4 ssh-rsa AAAA\n",
+        );
+        assert_eq!(known_hosts.hashed, 0);
+        assert_eq!(summary(&known_hosts), [("web4", "web4", "22", "")]);
+    }
+}
@@ -1,38 +1,115 @@
+mod ansible;
+mod asbru;
+mod blocks;
+mod cli;
+mod columns;
+mod command;
+mod crypt;
+mod export;
 mod handler;
+mod import;
+mod keybindings;
+mod known_hosts;
 mod parse;
+mod paths;
+mod portable;
+mod profile;
+mod putty;
+mod remmina;
+mod sort;
+mod spreadsheet;
+mod store;
+mod sync;
+mod termius;
+mod theme;
 mod ui;
 
 use crossterm::cursor::Show;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use crossterm::execute;
 use ratatui::{
     crossterm::event::{self, Event, KeyEventKind},
     layout::{Constraint, Flex, Layout, Rect},
-    widgets::{ScrollbarState, TableState},
+    style::Color,
+    widgets::{ListState, ScrollbarState, TableState},
     DefaultTerminal, Frame,
 };
+use age::secrecy::SecretString;
+use columns::{Column, ColumnConfig, ColumnLayout};
+use keybindings::{Action, KeyChord, KeyList, Keybindings};
 use serde::{Deserialize, Serialize};
-use shlex::split;
+use sort::SortKey;
+use std::collections::{BTreeMap, HashMap};
+use theme::{Theme, ThemeConfig};
 use std::io::stdout;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
 use std::{env, fs};
 use tui_input::Input;
 
 fn main() -> std::io::Result<()> {
     let version = env!("CARGO_PKG_VERSION");
-    let version_arg = "--version".to_string();
-    let args: Vec<String> = env::args().collect();
-    if args.last().unwrap() == &version_arg {
+    let args = match cli::Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(text) => {
+            eprintln!("Error: {}", text);
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.version {
         println!("ssh-list {}",version);
         return Ok(());
     }
+    if args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
 
+    paths::set_overrides(paths::Overrides {
+        config: args.config,
+        settings: args.settings,
+        ssh_config: args.ssh_config,
+    });
+    let profile = match args.profile.or(env::var(paths::PROFILE_ENV).ok().filter(|name| !name.is_empty())) {
+        Some(name) => name,
+        None => profile::DEFAULT_PROFILE.to_string(),
+    };
+    if let Err(text) = profile::validate_name(&profile) {
+        eprintln!("Error: {}", text);
+        std::process::exit(2);
+    }
+    if let Some(command) = args.command {
+        let result = match command {
+            cli::Command::Store(command) => run_store_command(command, &profile),
+            cli::Command::Export(export) => export_store(&export, &profile),
+            cli::Command::Import(import) => import_into_store(&import, &profile),
+        };
+        match result {
+            Ok(text) => {
+                println!("{}", text);
+                return Ok(());
+            }
+            Err(text) => {
+                eprintln!("Error: {}", text);
+                std::process::exit(1);
+            }
+        }
+    }
+    let app = App::new(profile);
     let terminal = ratatui::init();
-    let app_result = App::new().run(terminal);
-    ratatui::restore();
+    execute!(stdout(), EnableMouseCapture).ok();
+    let app_result = app.run(terminal);
+    restore_terminal();
     app_result
 }
 
+fn restore_terminal() {
+    execute!(stdout(), DisableMouseCapture).ok();
+    ratatui::restore();
+}
+
 #[derive(Deserialize, Serialize, PartialEq, Clone)]
 pub struct SSHConnection {
     server_name: String,
@@ -41,20 +118,32 @@ pub struct SSHConnection {
     hostname: String,
     port: String,
     options: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    history: Vec<i64>,
+    // Set when the connection is moved to the trash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted: Option<i64>,
+    // Name of a shared inventory entry this personal entry replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    overrides: Option<String>,
+    // File the connection was imported from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<import::ImportSource>,
+    // Profile the connection was loaded from.
+    #[serde(skip)]
+    profile: String,
+    // Shared inventory the connection was loaded from; such entries are read-only and never saved.
+    #[serde(skip)]
+    shared: Option<String>,
+    #[serde(skip)]
+    conflict: bool,
 }
 
-impl SSHConnection {
-    const fn ref_array(&self) -> [&String; 6] {
-        [
-            &self.server_name,
-            &self.group_name,
-            &self.username,
-            &self.hostname,
-            &self.port,
-            &self.options,
-        ]
-    }
-}
+const HISTORY_LIMIT: usize = 20;
+// How often the store files are checked for changes made outside the app.
+const STORE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct FieldInputs {
     server_name_input: Input,
@@ -63,15 +152,26 @@ pub struct FieldInputs {
     hostname_input: Input,
     port_input: Input,
     options_input: Input,
+    notes_input: Input,
 }
 
 #[derive(Deserialize, Serialize, PartialEq, Default)]
 pub struct AppConfig {
     color: Option<String>,
     row_height: Option<u16>,
+    detail_panel: Option<bool>,
+    theme: Option<String>,
+    themes: Option<BTreeMap<String, ThemeConfig>>,
+    keybindings: Option<BTreeMap<String, KeyList>>,
+    columns: Option<Vec<ColumnConfig>>,
+    sort: Option<Vec<String>>,
+    profiles: Option<BTreeMap<String, String>>,
+    inventories: Option<Vec<String>>,
+    git_sync: Option<bool>,
+    git_remote: Option<String>,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum Focus {
     ServerNameField,
     GroupNameField,
@@ -79,11 +179,20 @@ pub enum Focus {
     HostnameField,
     PortField,
     OptionsField,
+    NotesField,
     RunField,
     SearchField,
+    ImportPathField,
+    ExportPathField,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum ConfirmAction {
+    Delete,
+    Purge,
+}
+
+#[derive(PartialEq, Clone, Copy)]
 pub enum AppMode {
     Normal,
     Edit,
@@ -94,7 +203,20 @@ pub enum AppMode {
     RunCommand,
     Search,
     Options,
+    Columns,
     Sort,
+    Preview,
+    Confirm,
+    Trash,
+    Profiles,
+    Sync,
+    Merge,
+    Unlock,
+    ImportPath,
+    ImportPreview,
+    ImportReport,
+    CsvColumns,
+    Export,
 }
 
 pub struct App {
@@ -106,30 +228,118 @@ pub struct App {
     show_error_popup: bool,
     show_run_popup: bool,
     show_options_popup: bool,
+    show_columns_popup: bool,
+    show_confirm_popup: bool,
+    show_trash_popup: bool,
+    show_profiles_popup: bool,
+    show_sync_popup: bool,
+    show_merge_popup: bool,
+    show_unlock_popup: bool,
+    show_import_path_popup: bool,
+    show_import_preview_popup: bool,
+    show_import_report_popup: bool,
+    show_csv_columns_popup: bool,
+    show_export_popup: bool,
+    show_detail_panel: bool,
+    show_preview_popup: bool,
+    preview_copied: bool,
     focus: Focus,
     field_inputs: FieldInputs,
     run_input: Input,
     search_input: Input,
     search_index: Vec<usize>,
+    view_index: Vec<usize>,
+    sort_keys: Vec<SortKey>,
+    marked: Vec<usize>,
+    confirm_action: ConfirmAction,
+    confirm_indexes: Vec<usize>,
+    trash_state: TableState,
+    profile: String,
+    profiles: BTreeMap<String, String>,
+    inventories: Vec<PathBuf>,
+    inventory_error: Option<String>,
+    profiles_state: ListState,
+    git_sync: bool,
+    git_remote: Option<String>,
+    // Personal connections as last saved, used to describe each commit.
+    saved_connections: Vec<SSHConnection>,
+    sync_status: Vec<String>,
+    sync_message: String,
+    // Shown once the app is back in normal mode.
+    pending_error: Option<String>,
+    store_stamps: Vec<(PathBuf, store::FileStamp)>,
+    merge: Option<sync::Merge>,
+    merge_selected: usize,
+    // Passphrase of encrypted stores, asked for when one is opened.
+    passphrase: Option<SecretString>,
+    passphrase_input: Input,
+    unlock_profile: String,
+    unlock_error: String,
+    import_path_input: Input,
+    import_preview: Option<import::ImportPreview>,
+    import_state: TableState,
+    import_report: Option<import::ImportReport>,
+    import_report_scroll: u16,
+    csv_import: Option<spreadsheet::CsvImport>,
+    csv_cursor: usize,
+    export_input: Input,
+    export_format: export::ExportFormat,
+    // Choices of connections to export with their labels, fixed when the export popup opens.
+    export_scopes: Vec<(String, Vec<usize>)>,
+    export_scope: usize,
+    // Whether usernames and identity files are exported.
+    export_personal: bool,
+    export_status: Option<Result<String, String>>,
+    // An existing file the user was warned about; Enter again while the warning is shown writes over it.
+    export_overwrite: Option<PathBuf>,
     app_mode: AppMode,
     last_app_mode: AppMode,
     error_text: String,
     row_height: u16,
     color: String,
+    theme_name: String,
+    theme: Theme,
+    themes: BTreeMap<String, ThemeConfig>,
+    keybindings: Keybindings,
+    columns: Vec<ColumnLayout>,
+    column_cursor: usize,
+    pending_keys: Vec<KeyChord>,
+    count: Option<usize>,
+    awaiting_jump: bool,
+    page_rows: usize,
+    table_area: Rect,
+    column_areas: Vec<Rect>,
+    field_areas: Vec<(Focus, Rect)>,
+    last_click: Option<(Instant, usize)>,
 }
 
 impl App {
-    fn new() -> Self {
-        let data_vec = read_config();
-        Self {
+    fn new(profile: String) -> Self {
+        let appconfig = read_appconfig();
+        let mut app = Self {
             table_state: TableState::default().with_selected(0),
-            scroll_state: ScrollbarState::new(data_vec.len()),
-            ssh_connections: data_vec,
+            scroll_state: ScrollbarState::new(0),
+            ssh_connections: vec![],
             show_edit_popup: false,
             show_import_popup: false,
             show_error_popup: false,
             show_run_popup: false,
             show_options_popup: false,
+            show_columns_popup: false,
+            show_confirm_popup: false,
+            show_trash_popup: false,
+            show_profiles_popup: false,
+            show_sync_popup: false,
+            show_merge_popup: false,
+            show_unlock_popup: false,
+            show_import_path_popup: false,
+            show_import_preview_popup: false,
+            show_import_report_popup: false,
+            show_csv_columns_popup: false,
+            show_export_popup: false,
+            show_detail_panel: false,
+            show_preview_popup: false,
+            preview_copied: false,
             focus: Focus::ServerNameField,
             field_inputs: FieldInputs {
                 server_name_input: Input::default(),
@@ -138,30 +348,319 @@ impl App {
                 hostname_input: Input::default(),
                 port_input: Input::default().with_value("22".to_string()),
                 options_input: Input::default(),
+                notes_input: Input::default(),
             },
             run_input: Input::default(),
             search_input: Input::default(),
             search_index: vec![],
+            view_index: vec![],
+            sort_keys: vec![],
+            marked: vec![],
+            confirm_action: ConfirmAction::Delete,
+            confirm_indexes: vec![],
+            trash_state: TableState::default().with_selected(0),
+            profile: profile.clone(),
+            profiles: appconfig.profiles.unwrap_or_default(),
+            inventories: appconfig
+                .inventories
+                .unwrap_or_default()
+                .iter()
+                .map(|path| paths::expand_home(path))
+                .collect(),
+            inventory_error: None,
+            profiles_state: ListState::default().with_selected(Some(0)),
+            git_sync: appconfig.git_sync.unwrap_or(false),
+            git_remote: appconfig.git_remote,
+            saved_connections: vec![],
+            sync_status: vec![],
+            sync_message: String::new(),
+            pending_error: None,
+            store_stamps: vec![],
+            merge: None,
+            merge_selected: 0,
+            passphrase: None,
+            passphrase_input: Input::default(),
+            unlock_profile: String::new(),
+            unlock_error: String::new(),
+            import_path_input: Input::default(),
+            import_preview: None,
+            import_state: TableState::default().with_selected(0),
+            import_report: None,
+            import_report_scroll: 0,
+            csv_import: None,
+            csv_cursor: 0,
+            export_input: Input::default().with_value("~/ssh-list.csv".to_string()),
+            export_format: export::ExportFormat::Csv,
+            export_scopes: vec![],
+            export_scope: 0,
+            export_personal: true,
+            export_status: None,
+            export_overwrite: None,
             app_mode: AppMode::Normal,
             last_app_mode: AppMode::Normal,
             error_text: String::new(),
             row_height: 3,
             color: "yellow".to_string(),
+            theme_name: "dark".to_string(),
+            theme: Theme::dark(),
+            themes: BTreeMap::new(),
+            keybindings: Keybindings::default(),
+            columns: columns::default_columns(),
+            column_cursor: 0,
+            pending_keys: vec![],
+            count: None,
+            awaiting_jump: false,
+            page_rows: 1,
+            table_area: Rect::default(),
+            column_areas: vec![],
+            field_areas: vec![],
+            last_click: None,
+        };
+        match app.load_profile(&profile) {
+            Ok(()) => {}
+            Err(text) if text == crypt::LOCKED => app.ask_passphrase(&profile),
+            Err(text) => {
+                eprintln!("Error: {}", text);
+                std::process::exit(1);
+            }
+        }
+        app
+    }
+
+    fn ask_passphrase(&mut self, name: &str) {
+        self.unlock_profile = name.to_string();
+        self.passphrase_input = Input::default();
+        self.unlock_error.clear();
+        self.show_unlock_popup = true;
+        self.app_mode = AppMode::Unlock;
+    }
+
+    // Loads the profile waiting for a passphrase; a wrong passphrase keeps the prompt open.
+    fn unlock(&mut self) {
+        let passphrase = SecretString::from(self.passphrase_input.value().to_string());
+        let previous = self.passphrase.replace(passphrase);
+        let name = self.unlock_profile.clone();
+        match self.load_profile(&name) {
+            Ok(()) => {
+                self.passphrase_input = Input::default();
+                self.show_unlock_popup = false;
+                self.app_mode = AppMode::Normal;
+                if let Some(text) = self.inventory_error.take() {
+                    self.show_config_error(text);
+                }
+            }
+            Err(text) => {
+                self.passphrase = previous;
+                self.passphrase_input = Input::default();
+                self.unlock_error = if text == crypt::LOCKED {
+                    "Wrong passphrase".to_string()
+                } else {
+                    text
+                };
+            }
+        }
+    }
+
+    // The store was not opened yet when the prompt comes up at startup.
+    fn unlocking_at_startup(&self) -> bool {
+        self.profile == self.unlock_profile
+    }
+
+    // Loads the store of a profile, or every store for the merged view.
+    fn load_profile(&mut self, name: &str) -> Result<(), String> {
+        let names = if name == profile::MERGED_PROFILE {
+            profile::profile_names(&self.profiles)
+        } else {
+            vec![name.to_string()]
+        };
+        let mut connections = vec![];
+        for profile_name in names {
+            let path = profile::store_path(&profile_name, &self.profiles);
+            let mut loaded = load_store(&path, self.passphrase.as_ref())?;
+            for connection in &mut loaded {
+                connection.profile = profile_name.clone();
+            }
+            connections.extend(loaded);
+        }
+        self.saved_connections = connections.clone();
+        connections.extend(self.load_inventories());
+        self.ssh_connections = connections;
+        self.check_sources();
+        self.profile = name.to_string();
+        self.marked.clear();
+        self.apply_sort();
+        self.select_row(0);
+        self.refresh_store_stamps();
+        Ok(())
+    }
+
+    fn refresh_store_stamps(&mut self) {
+        let names = if self.is_merged_view() {
+            profile::profile_names(&self.profiles)
+        } else {
+            vec![self.profile.clone()]
+        };
+        self.store_stamps = names
+            .iter()
+            .map(|name| profile::store_path(name, &self.profiles))
+            .chain(self.inventories.iter().cloned())
+            .map(|path| {
+                let stamp = store::stamp(&path);
+                (path, stamp)
+            })
+            .collect();
+    }
+
+    // Reloads files changed by hand or by another instance, but not while a popup is open.
+    fn check_store_changes(&mut self) {
+        if self.app_mode != AppMode::Normal && self.app_mode != AppMode::Search {
+            return;
+        }
+        if self.store_stamps.iter().all(|(path, stamp)| store::stamp(path) == *stamp) {
+            return;
+        }
+        if let Err(text) = self.reload_store() {
+            // Retried on the next change of the file.
+            self.refresh_store_stamps();
+            self.pending_error = Some(text);
+        }
+    }
+
+    // Shared inventories are optional; unreadable files are skipped and reported.
+    fn load_inventories(&mut self) -> Vec<SSHConnection> {
+        let mut connections = vec![];
+        let mut errors = vec![];
+        for path in &self.inventories {
+            let name = path.file_stem().map_or(path.display().to_string(), |stem| stem.to_string_lossy().to_string());
+            if !path.exists() {
+                errors.push(format!("Shared inventory not found:\n{}", path.display()));
+                continue;
+            }
+            match load_store(path, self.passphrase.as_ref()) {
+                Ok(loaded) => {
+                    for mut connection in loaded.into_iter().filter(|connection| connection.deleted.is_none()) {
+                        connection.shared = Some(name.clone());
+                        connection.overrides = None;
+                        connections.push(connection);
+                    }
+                }
+                Err(text) => errors.push(text),
+            }
+        }
+        self.inventory_error = if errors.is_empty() { None } else { Some(errors.join("\n")) };
+        connections
+    }
+
+    fn is_merged_view(&self) -> bool {
+        self.profile == profile::MERGED_PROFILE
+    }
+
+    // Shows an error and returns true when the highlighted connection comes from a shared inventory.
+    fn refuse_shared(&mut self) -> bool {
+        let Some(source) = self.highlighted_connection().and_then(|connection| connection.shared.clone()) else {
+            return false;
+        };
+        self.error_text = format!("This connection comes from the shared\ninventory \"{}\" and is read-only.", source);
+        self.last_app_mode = self.app_mode;
+        self.show_error_popup = true;
+        self.app_mode = AppMode::Error;
+        true
+    }
+
+    // Rows can only be moved in the manual order, which a sorted view hides.
+    fn refuse_sorted(&mut self) -> bool {
+        if self.sort_keys.is_empty() {
+            return false;
+        }
+        self.error_text = format!(
+            "Switch to manual order to move rows\n([{}] then [{}]).",
+            self.keybindings.label(Action::Sort),
+            self.keybindings.label(Action::SortManual)
+        );
+        self.last_app_mode = self.app_mode;
+        self.show_error_popup = true;
+        self.app_mode = AppMode::Error;
+        true
+    }
+
+    // Shows an error and returns true when an import would replace the list with nothing, as the CLI refuses to.
+    fn refuse_empty_replace(&mut self) -> bool {
+        if !self.import_preview.as_ref().is_some_and(|preview| preview.replaced.is_some() && preview.selected() == 0) {
+            return false;
+        }
+        self.error_text = format!(
+            "Select connections to replace the list with,\nor add to the list instead ([{}]).",
+            self.keybindings.label(Action::ImportReplace)
+        );
+        self.last_app_mode = self.app_mode;
+        self.show_error_popup = true;
+        self.app_mode = AppMode::Error;
+        true
+    }
+
+    // Shows an error and returns true when the current view cannot be changed.
+    fn refuse_read_only(&mut self) -> bool {
+        if !self.is_merged_view() {
+            return false;
+        }
+        self.error_text = "The merged view of all profiles\nis read-only.".to_string();
+        self.last_app_mode = self.app_mode;
+        self.show_error_popup = true;
+        self.app_mode = AppMode::Error;
+        true
+    }
+
+    fn switch_profile(&mut self, name: &str) {
+        match self.load_profile(name) {
+            Ok(()) => {
+                self.app_mode = AppMode::Normal;
+                if let Some(text) = self.inventory_error.take() {
+                    self.show_config_error(text);
+                }
+            }
+            Err(text) if text == crypt::LOCKED => self.ask_passphrase(name),
+            Err(text) => {
+                self.error_text = text;
+                self.last_app_mode = AppMode::Normal;
+                self.show_error_popup = true;
+                self.app_mode = AppMode::Error;
+            }
         }
     }
 
+    fn profile_entries(&self) -> Vec<String> {
+        let mut entries = profile::profile_names(&self.profiles);
+        entries.push(profile::MERGED_PROFILE.to_string());
+        entries
+    }
+
     fn run(mut self, mut terminal: DefaultTerminal) -> std::io::Result<()> {
-        self.check_blank_config();
+        if self.app_mode != AppMode::Unlock {
+            self.check_blank_config();
+        }
         self.apply_appconfig();
+        if let Some(text) = self.inventory_error.take() {
+            self.show_config_error(text);
+        }
         loop {
+            if self.app_mode == AppMode::Normal {
+                if let Some(text) = self.pending_error.take() {
+                    self.show_config_error(text);
+                }
+            }
             terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(STORE_CHECK_INTERVAL)? {
+                self.check_store_changes();
+                continue;
+            }
             let event = event::read()?;
-            if let Event::Key(key) = event {
-                if key.kind == KeyEventKind::Press {
-                    if handler::handle_key_event(&mut self, key) == false {
-                        break;
-                    }
-                }
+            let keep_running = match event {
+                Event::Key(key) if key.kind == KeyEventKind::Press => handler::handle_key_event(&mut self, key),
+                Event::Mouse(mouse) => handler::handle_mouse_event(&mut self, mouse),
+                _ => true,
+            };
+            if !keep_running {
+                break;
             }
         }
         Ok(())
@@ -171,7 +670,7 @@ impl App {
         let vertical = match self.app_mode {
             AppMode::Search => Layout::vertical([Constraint::Min(5), Constraint::Length(3), Constraint::Length(3)]),
             AppMode::Normal => {
-                if self.ssh_connections.is_empty() {
+                if self.view_index.is_empty() {
                     Layout::vertical([Constraint::Min(5), Constraint::Length(3)])
                 } else {
                     Layout::vertical([Constraint::Min(5), Constraint::Length(4)])
@@ -182,11 +681,19 @@ impl App {
 
         let rects_v = vertical.split(frame.area());
 
-        let horizontal = Layout::horizontal([Constraint::Min(0), Constraint::Length(3)]);
+        let horizontal = if self.show_detail_panel {
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(3), Constraint::Percentage(35)])
+        } else {
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(3)])
+        };
         let rects_h = horizontal.split(rects_v[0]);
+        self.page_rows = (rects_h[0].height.saturating_sub(1) / self.row_height).max(1) as usize;
 
         ui::render_table(self, frame, rects_h[0]);
         ui::render_scrollbar(self, frame, rects_h[1]);
+        if self.show_detail_panel {
+            ui::render_detail_panel(self, frame, rects_h[2]);
+        }
 
         match self.app_mode {
             AppMode::Search => {
@@ -201,11 +708,7 @@ impl App {
         }
 
         if self.show_import_popup {
-            ui::render_config_popup(frame, rects_v[0]);
-        }
-
-        if self.show_error_popup {
-            ui::render_error_popup(frame, rects_v[0], self.error_text.clone());
+            ui::render_config_popup(self, frame, rects_v[0]);
         }
 
         if self.show_run_popup {
@@ -214,68 +717,149 @@ impl App {
         }
 
         if self.show_options_popup {
-            ui::render_options_popup(frame, rects_v[0]);
+            ui::render_options_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_columns_popup {
+            ui::render_columns_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_preview_popup {
+            ui::render_preview_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_profiles_popup {
+            ui::render_profiles_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_trash_popup {
+            ui::render_trash_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_confirm_popup {
+            ui::render_confirm_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_sync_popup {
+            ui::render_sync_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_merge_popup {
+            ui::render_merge_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_unlock_popup {
+            ui::render_unlock_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_import_path_popup {
+            self.focus = Focus::ImportPathField;
+            ui::render_import_path_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_csv_columns_popup {
+            ui::render_csv_columns_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_export_popup {
+            self.focus = Focus::ExportPathField;
+            ui::render_export_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_import_preview_popup {
+            ui::render_import_preview_popup(self, frame, rects_v[0]);
+        }
+
+        if self.show_import_report_popup {
+            ui::render_import_report_popup(self, frame, rects_v[0]);
+        }
+
+        // Errors are drawn last, above the popup they were raised from.
+        if self.show_error_popup {
+            ui::render_error_popup(self, frame, rects_v[0], self.error_text.clone());
         }
     }
 
     fn check_blank_config(&mut self) {
-        if self.ssh_connections == vec![] && parse::check_blank_sshconfig(&parse::get_sshconfig_path()) == true {
+        // A store holding only trashed connections counts as empty.
+        let no_connections = self.ssh_connections.iter().all(|connection| connection.deleted.is_some());
+        if no_connections && parse::check_blank_sshconfig(&parse::get_sshconfig_path()) {
             self.show_edit_popup = true;
             self.app_mode = AppMode::New
-        } else if self.ssh_connections == vec![] && parse::check_blank_sshconfig(&parse::get_sshconfig_path()) == false
-        {
+        } else if no_connections && !parse::check_blank_sshconfig(&parse::get_sshconfig_path()) {
             self.show_import_popup = true;
             self.app_mode = AppMode::Import
-        } else if self.ssh_connections != vec![] {
+        } else {
             self.app_mode = AppMode::Normal;
         }
     }
 
-    fn next_row(&mut self) {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i >= self.ssh_connections.len() - 1 {
-                    i
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
-        };
+    fn visible_rows(&self) -> usize {
+        if self.shows_all_rows() {
+            self.view_index.len()
+        } else {
+            self.search_index.len()
+        }
+    }
+
+    fn select_row(&mut self, i: usize) {
+        let i = i.min(self.visible_rows().saturating_sub(1));
         self.table_state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i);
+        self.scroll_state = self.scroll_state.content_length(self.visible_rows()).position(i);
+    }
+
+    fn move_down(&mut self, rows: usize) {
+        let i = self.table_state.selected().map_or(0, |i| i.saturating_add(rows));
+        self.select_row(i);
+    }
+
+    fn move_up(&mut self, rows: usize) {
+        let i = self.table_state.selected().map_or(0, |i| i.saturating_sub(rows));
+        self.select_row(i);
+    }
+
+    fn next_row(&mut self) {
+        self.move_down(1);
     }
 
     fn previous_row(&mut self) {
-        let i = match self.table_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    i
-                } else {
-                    i - 1
-                }
+        self.move_up(1);
+    }
+
+    fn first_row(&mut self) {
+        self.select_row(0);
+    }
+
+    fn last_row(&mut self) {
+        self.select_row(self.visible_rows().saturating_sub(1));
+    }
+
+    fn jump_to_letter(&mut self, letter: char) {
+        let letter = letter.to_lowercase().next().unwrap_or(letter);
+        let start = self.table_state.selected().map_or(0, |i| i + 1);
+        let rows = self.visible_rows();
+        for offset in 0..rows {
+            let row = (start + offset) % rows;
+            let Some(i) = self.row_index(row) else {
+                continue;
+            };
+            if self.ssh_connections[i].server_name.to_lowercase().starts_with(letter) {
+                self.select_row(row);
+                return;
             }
-            None => 0,
-        };
-        self.table_state.select(Some(i));
-        self.scroll_state = self.scroll_state.position(i);
+        }
     }
 
     fn connect(&mut self, command: Option<String>) {
         if let Some(i) = self.get_row_index() {
+            self.record_history(i);
             println!(
                 "Connecting to {} ({})...",
                 self.ssh_connections[i].server_name, self.ssh_connections[i].group_name
             );
-            match Command::new("ssh")
-                .arg("-p")
-                .arg(&self.ssh_connections[i].port)
-                .args(split(&self.ssh_connections[i].options).unwrap_or_default())
-                .arg(format!(
-                    "{}@{}",
-                    self.ssh_connections[i].username, self.ssh_connections[i].hostname
-                ))
-                .args(split(&command.unwrap_or_default()).unwrap_or_default())
+            match command::SshCommand::new(&self.ssh_connections[i])
+                .with_remote_command(&command.unwrap_or_default())
+                .to_command()
                 .status()
             {
                 Ok(_) => std::process::exit(0),
@@ -289,6 +873,20 @@ impl App {
         }
     }
 
+    // Shared entries are never saved, so their connections are not recorded.
+    fn record_history(&mut self, i: usize) {
+        if self.is_merged_view() || self.ssh_connections[i].shared.is_some() {
+            return;
+        }
+        let history = &mut self.ssh_connections[i].history;
+        history.push(chrono::Local::now().timestamp());
+        if history.len() > HISTORY_LIMIT {
+            history.drain(..history.len() - HISTORY_LIMIT);
+        }
+        // Connecting is not an edit worth a commit; the history goes into the next one.
+        self.save_store(false);
+    }
+
     fn add_connection(&mut self) {
         let new_connection = SSHConnection {
             server_name: self.field_inputs.server_name_input.to_string(),
@@ -297,11 +895,21 @@ impl App {
             hostname: self.field_inputs.hostname_input.to_string(),
             port: self.field_inputs.port_input.to_string(),
             options: self.field_inputs.options_input.to_string(),
+            notes: self.field_inputs.notes_input.to_string(),
+            history: vec![],
+            deleted: None,
+            overrides: None,
+            source: None,
+            profile: self.profile.clone(),
+            shared: None,
+            conflict: false,
         };
         self.ssh_connections.push(new_connection);
         self.update_config();
         self.reset_fields();
-        self.table_state.select(Some(self.ssh_connections.len()));
+        let new_index = self.ssh_connections.len() - 1;
+        let row = self.view_index.iter().position(|&i| i == new_index).unwrap_or(new_index);
+        self.table_state.select(Some(row));
     }
 
     fn reset_fields(&mut self) {
@@ -312,21 +920,223 @@ impl App {
             hostname_input: Input::default(),
             port_input: Input::default().with_value("22".to_string()),
             options_input: Input::default(),
+            notes_input: Input::default(),
         };
         self.focus = Focus::ServerNameField;
     }
 
     pub fn update_config(&mut self) {
-        let json = serde_json::to_string_pretty(&self.ssh_connections).unwrap();
-        match fs::write(get_config_path(), json) {
-            Ok(_) => (),
-            Err(text) => {
-                ratatui::restore();
-                execute!(stdout(), Show).ok();
-                eprintln!("Error writing to file {}: {}", get_config_path().display(), text);
-                std::process::exit(1);
+        self.save_store(true);
+    }
+
+    fn save_store(&mut self, commit: bool) {
+        if self.is_merged_view() {
+            self.marked.clear();
+            self.apply_sort();
+            return;
+        }
+        let store_path = profile::store_path(&self.profile, &self.profiles);
+        let lock = store::lock(&store_path).unwrap_or_else(|text| exit_on_write_error(&store_path, text));
+        // Encrypted stores stay encrypted, even when encrypted from the command line while the app runs.
+        let encrypted = store_is_encrypted(&store_path);
+        if encrypted && self.passphrase.is_none() {
+            self.pending_error = Some("The store was encrypted on disk.\nRestart ssh-list to unlock it.".to_string());
+            return;
+        }
+        let mut personal: Vec<SSHConnection> = self
+            .ssh_connections
+            .iter()
+            .filter(|connection| connection.shared.is_none())
+            .cloned()
+            .collect();
+        if let Some(on_disk) = self.external_changes(&store_path) {
+            personal = self.merge_external(personal, &on_disk);
+        }
+        let json = serde_json::to_string_pretty(&personal).unwrap();
+        let contents = match &self.passphrase {
+            Some(passphrase) if encrypted => match crypt::encrypt(&json, passphrase) {
+                Ok(contents) => contents,
+                Err(text) => exit_on_write_error(&store_path, std::io::Error::other(text)),
+            },
+            _ => json.into_bytes(),
+        };
+        if let Err(text) = store::write(&store_path, &contents) {
+            exit_on_write_error(&store_path, text);
+        }
+        drop(lock);
+        let stamp = store::stamp(&store_path);
+        for (path, saved_stamp) in &mut self.store_stamps {
+            if *path == store_path {
+                *saved_stamp = stamp;
+            }
+        }
+        if commit {
+            self.commit_store(personal, encrypted);
+        } else {
+            self.saved_connections = personal;
+        }
+        // Marks refer to positions in ssh_connections, which may have changed.
+        self.marked.clear();
+        self.apply_sort();
+    }
+
+    // The store as saved by another instance or a text editor since it was loaded, if it differs.
+    fn external_changes(&mut self, store_path: &Path) -> Option<Vec<SSHConnection>> {
+        let stamp = store::stamp(store_path);
+        if stamp.is_none() || self.store_stamps.iter().any(|(path, saved)| path == store_path && *saved == stamp) {
+            return None;
+        }
+        match load_store(store_path, self.passphrase.as_ref()) {
+            Ok(on_disk) if !same_store(&on_disk, &self.saved_connections) => Some(on_disk),
+            Ok(_) => None,
+            Err(_) => {
+                self.pending_error =
+                    Some("The store file was changed on disk but is invalid;\nit was replaced with this list.".to_string());
+                None
+            }
+        }
+    }
+
+    // Applies external changes on top of the local ones; connections changed on both sides keep the local version.
+    fn merge_external(&mut self, personal: Vec<SSHConnection>, on_disk: &[SSHConnection]) -> Vec<SSHConnection> {
+        let merge = sync::merge(&self.saved_connections, &personal, on_disk);
+        let conflicts: Vec<String> = merge
+            .iter()
+            .filter_map(|entry| match entry {
+                sync::MergeEntry::Conflict { local, remote, .. } => local.as_ref().or(remote.as_ref()),
+                sync::MergeEntry::Resolved(_) => None,
+            })
+            .map(|connection| connection.server_name.clone())
+            .collect();
+        if !conflicts.is_empty() {
+            self.pending_error = Some(format!(
+                "Also changed outside ssh-list: {}.\nYour version was kept.",
+                conflicts.join(", ")
+            ));
+        }
+        let mut merged = sync::resolve(&merge);
+        for connection in &mut merged {
+            connection.profile = self.profile.clone();
+        }
+        let shared: Vec<SSHConnection> = self
+            .ssh_connections
+            .iter()
+            .filter(|connection| connection.shared.is_some())
+            .cloned()
+            .collect();
+        self.ssh_connections = merged.iter().cloned().chain(shared).collect();
+        merged
+    }
+
+    fn open_repo(&self) -> Result<sync::GitRepo, String> {
+        let mut repo = sync::GitRepo::open(&profile::store_path(&self.profile, &self.profiles))?;
+        repo.passphrase = self.passphrase.clone();
+        if let Some(remote) = &self.git_remote {
+            repo.set_remote(&paths::expand_home(remote).to_string_lossy())?;
+        }
+        Ok(repo)
+    }
+
+    // With git sync on, every save becomes a commit named after what changed. Messages of encrypted stores
+    // leave the connection names out.
+    fn commit_store(&mut self, personal: Vec<SSHConnection>, encrypted: bool) {
+        let message = if encrypted {
+            "update encrypted connections".to_string()
+        } else {
+            sync::describe_changes(&self.saved_connections, &personal)
+        };
+        self.saved_connections = personal;
+        if !self.git_sync {
+            return;
+        }
+        match self.open_repo().and_then(|repo| repo.commit(&message)) {
+            Ok(true) => self.sync_message = format!("Committed \"{}\"", message),
+            Ok(false) => {}
+            Err(text) => self.pending_error = Some(format!("Git commit failed:\n{}", text)),
+        }
+    }
+
+    fn refresh_sync_status(&mut self) {
+        self.sync_status = match self.open_repo() {
+            Ok(repo) => repo.status(),
+            Err(text) => text.lines().map(String::from).collect(),
+        };
+    }
+
+    // Reloads the current profile from disk, keeping the highlighted connection selected.
+    fn reload_store(&mut self) -> Result<(), String> {
+        let selected = self.highlighted_connection().map(|connection| connection.server_name.clone());
+        let profile = self.profile.clone();
+        self.load_profile(&profile)?;
+        if self.app_mode == AppMode::Search {
+            self.search();
+        }
+        let row = selected.and_then(|name| {
+            (0..self.visible_rows())
+                .find(|&row| self.row_index(row).is_some_and(|i| self.ssh_connections[i].server_name == name))
+        });
+        self.select_row(row.unwrap_or(0));
+        Ok(())
+    }
+
+    fn pull_store(&mut self) {
+        match self.open_repo().and_then(|repo| repo.pull()) {
+            Ok(sync::PullResult::UpToDate) => self.sync_message = "Already up to date".to_string(),
+            Ok(sync::PullResult::Updated) => {
+                self.sync_message = "Pulled remote changes".to_string();
+                if let Err(text) = self.reload_store() {
+                    self.sync_message = text;
+                }
+            }
+            Ok(sync::PullResult::Conflict(merge)) => {
+                self.merge = Some(merge);
+                self.merge_selected = 0;
+                self.show_sync_popup = false;
+                self.show_merge_popup = true;
+                self.app_mode = AppMode::Merge;
             }
+            Err(text) => self.sync_message = format!("Pull failed: {}", text),
+        }
+        self.refresh_sync_status();
+    }
+
+    fn push_store(&mut self) {
+        self.sync_message = match self.open_repo().and_then(|repo| repo.push()) {
+            Ok(text) => text,
+            Err(text) => format!("Push failed: {}", text),
+        };
+        self.refresh_sync_status();
+    }
+
+    // Sets the side of the highlighted conflict and moves on to the next one.
+    fn choose_merge_side(&mut self, keep_local: bool) {
+        if let Some(merge) = &mut self.merge {
+            merge.choose(self.merge_selected, keep_local);
+            let count = merge.conflicts().count();
+            self.merge_selected = (self.merge_selected + 1).min(count.saturating_sub(1));
+        }
+    }
+
+    // Finishes the pending merge with the side chosen for each conflict.
+    fn resolve_merge(&mut self) {
+        let Some(merge) = self.merge.take() else {
+            return;
         };
+        match self.open_repo().and_then(|repo| repo.finish_merge(&merge)) {
+            Ok(()) => {
+                let local = merge.conflicts().filter(|(_, _, keep_local)| *keep_local).count();
+                let remote = merge.conflicts().count() - local;
+                self.sync_message = format!(
+                    "Merged {}: {} local and {} remote versions kept",
+                    merge.upstream, local, remote
+                );
+                if let Err(text) = self.reload_store() {
+                    self.sync_message = text;
+                }
+            }
+            Err(text) => self.sync_message = format!("Merge failed: {}", text),
+        }
+        self.refresh_sync_status();
     }
 
     fn selected_config_to_fields(&mut self) {
@@ -341,69 +1151,450 @@ impl App {
                 Input::default().with_value(self.ssh_connections[i].hostname.to_string());
             self.field_inputs.port_input = Input::default().with_value(self.ssh_connections[i].port.to_string());
             self.field_inputs.options_input = Input::default().with_value(self.ssh_connections[i].options.to_string());
+            self.field_inputs.notes_input = Input::default().with_value(self.ssh_connections[i].notes.to_string());
         };
     }
 
     fn update_connection(&mut self) {
-        let edited_connection = SSHConnection {
-            server_name: self.field_inputs.server_name_input.to_string(),
-            group_name: self.field_inputs.group_name_input.to_string(),
-            username: self.field_inputs.username_input.to_string(),
-            hostname: self.field_inputs.hostname_input.to_string(),
-            port: self.field_inputs.port_input.to_string(),
-            options: self.field_inputs.options_input.to_string(),
-        };
         if let Some(i) = self.get_row_index() {
-            self.ssh_connections[i] = edited_connection;
+            let shared = self.ssh_connections[i].shared.is_some();
+            let edited_connection = SSHConnection {
+                server_name: self.field_inputs.server_name_input.to_string(),
+                group_name: self.field_inputs.group_name_input.to_string(),
+                username: self.field_inputs.username_input.to_string(),
+                hostname: self.field_inputs.hostname_input.to_string(),
+                port: self.field_inputs.port_input.to_string(),
+                options: self.field_inputs.options_input.to_string(),
+                notes: self.field_inputs.notes_input.to_string(),
+                history: self.ssh_connections[i].history.clone(),
+                deleted: None,
+                overrides: self.ssh_connections[i].overrides.clone(),
+                source: self.ssh_connections[i].source.clone(),
+                profile: self.ssh_connections[i].profile.clone(),
+                shared: None,
+                conflict: false,
+            };
+            if shared {
+                // Shared entries stay untouched; the edit is saved as a personal override.
+                let overrides = self.ssh_connections[i].server_name.clone();
+                self.ssh_connections.push(SSHConnection {
+                    overrides: Some(overrides),
+                    history: vec![],
+                    ..edited_connection
+                });
+            } else {
+                self.ssh_connections[i] = edited_connection;
+            }
         }
         self.update_config();
         self.reset_fields();
     }
 
-    fn delete_connection(&mut self) {
+    fn toggle_mark(&mut self) {
         if let Some(i) = self.get_row_index() {
-            self.ssh_connections.remove(i);
-            self.update_config();
-        };
+            match self.marked.iter().position(|&m| m == i) {
+                Some(position) => {
+                    self.marked.remove(position);
+                }
+                None => self.marked.push(i),
+            }
+        }
     }
 
-    fn copy_connection(&mut self) {
-        if let Some(i) = self.table_state.selected() {
-            self.ssh_connections.insert(i + 1, self.ssh_connections[i].clone());
-            self.update_config();
+    // Asks to move the marked connections, or the highlighted one, to the trash.
+    fn ask_delete(&mut self) {
+        let indexes: Vec<usize> = if self.marked.is_empty() {
+            self.get_row_index().into_iter().collect()
+        } else {
+            self.marked.clone()
         };
+        let indexes: Vec<usize> = indexes
+            .into_iter()
+            .filter(|&i| self.ssh_connections[i].shared.is_none())
+            .collect();
+        if indexes.is_empty() {
+            return;
+        }
+        self.confirm_indexes = indexes;
+        self.confirm_action = ConfirmAction::Delete;
+        self.last_app_mode = self.app_mode;
+        self.app_mode = AppMode::Confirm;
+        self.show_confirm_popup = true;
     }
 
-    fn focus_next_field(&mut self) {
-        self.focus = match self.focus {
-            Focus::ServerNameField => Focus::GroupNameField,
-            Focus::GroupNameField => Focus::UsernameField,
-            Focus::UsernameField => Focus::HostnameField,
-            Focus::HostnameField => Focus::PortField,
-            Focus::PortField => Focus::OptionsField,
-            Focus::OptionsField => Focus::OptionsField,
-            _ => Focus::ServerNameField,
+    fn ask_purge(&mut self, all: bool) {
+        let trash = self.trash_indexes();
+        let indexes: Vec<usize> = if all {
+            trash
+        } else {
+            self.trash_state.selected().and_then(|row| trash.get(row).copied()).into_iter().collect()
         };
+        if indexes.is_empty() {
+            return;
+        }
+        self.confirm_indexes = indexes;
+        self.confirm_action = ConfirmAction::Purge;
+        self.app_mode = AppMode::Confirm;
+        self.show_confirm_popup = true;
     }
 
-    fn focus_previous_field(&mut self) {
-        self.focus = match self.focus {
-            Focus::ServerNameField => Focus::ServerNameField,
-            Focus::GroupNameField => Focus::ServerNameField,
-            Focus::UsernameField => Focus::GroupNameField,
-            Focus::HostnameField => Focus::UsernameField,
-            Focus::PortField => Focus::HostnameField,
-            Focus::OptionsField => Focus::PortField,
+    fn delete_connection(&mut self) {
+        let now = chrono::Local::now().timestamp();
+        for &i in &self.confirm_indexes {
+            if let Some(connection) = self.ssh_connections.get_mut(i) {
+                connection.deleted = Some(now);
+            }
+        }
+        self.confirm_indexes.clear();
+        self.update_config();
+    }
+
+    // Trashed connections, most recently deleted first.
+    fn trash_indexes(&self) -> Vec<usize> {
+        let mut indexes: Vec<usize> = (0..self.ssh_connections.len())
+            .filter(|&i| self.ssh_connections[i].deleted.is_some())
+            .collect();
+        indexes.sort_by_key(|&i| std::cmp::Reverse(self.ssh_connections[i].deleted));
+        indexes
+    }
+
+    fn restore_connection(&mut self) {
+        let trash = self.trash_indexes();
+        if let Some(&i) = self.trash_state.selected().and_then(|row| trash.get(row)) {
+            self.ssh_connections[i].deleted = None;
+            self.update_config();
+            self.select_trash_row(self.trash_state.selected().unwrap_or_default());
+        }
+    }
+
+    fn purge_connections(&mut self) {
+        let mut indexes = std::mem::take(&mut self.confirm_indexes);
+        indexes.sort_unstable();
+        for i in indexes.into_iter().rev() {
+            self.ssh_connections.remove(i);
+        }
+        self.update_config();
+        self.select_trash_row(self.trash_state.selected().unwrap_or_default());
+    }
+
+    fn toggle_import_replace(&mut self) {
+        let replaceable = self
+            .ssh_connections
+            .iter()
+            .filter(|connection| connection.shared.is_none() && connection.deleted.is_none())
+            .count();
+        if let Some(preview) = self.import_preview.as_mut() {
+            preview.toggle_replace(&self.ssh_connections, replaceable);
+        }
+    }
+
+    fn preview_import(&mut self, preview: import::ImportPreview) {
+        self.import_preview = Some(preview);
+        self.import_state.select(Some(0));
+        self.show_import_popup = false;
+        self.show_import_preview_popup = true;
+        self.app_mode = AppMode::ImportPreview;
+    }
+
+    // Files in a known format go straight to the preview; other CSV files get their columns mapped first.
+    fn import_from_path(&mut self) {
+        let path = paths::expand_home(self.import_path_input.value().trim());
+        let importer = match import::find_importer(&path) {
+            Ok(importer) => importer,
+            Err(text) => return self.show_import_error(text),
+        };
+        if let Some(importer) = importer {
+            match importer.read(&path) {
+                Ok(imported) => {
+                    let preview =
+                        import::ImportPreview::new(import::SourceKind::File, &path, imported, &self.ssh_connections);
+                    self.show_import_path_popup = false;
+                    self.preview_import(preview);
+                }
+                Err(text) => self.show_import_error(text),
+            }
+        } else if matches!(import::extension(&path).as_str(), "csv" | "tsv") {
+            self.show_import_path_popup = false;
+            match spreadsheet::CsvImport::read(&path) {
+                Ok(csv_import) => {
+                    self.csv_import = Some(csv_import);
+                    self.csv_cursor = 0;
+                    self.show_csv_columns_popup = true;
+                    self.app_mode = AppMode::CsvColumns;
+                }
+                Err(text) => self.show_import_error(text),
+            }
+        } else {
+            self.show_import_error(
+                "Unknown file format (CSV, Ansible, PuTTY,\nKiTTY, Termius, Remmina or Asbru expected).".to_string(),
+            );
+        }
+    }
+
+    // Closes the import popups and shows why the import failed.
+    fn show_import_error(&mut self, text: String) {
+        self.show_import_popup = false;
+        self.show_import_path_popup = false;
+        self.error_text = text;
+        self.last_app_mode = AppMode::Normal;
+        self.show_error_popup = true;
+        self.app_mode = AppMode::Error;
+    }
+
+    // Reasons too long for the error popup, such as ssh's own errors, are shown in the import report instead.
+    fn show_failed_import(&mut self, source: String, text: String) {
+        self.show_import_popup = false;
+        self.import_report = Some(import::ImportReport {
+            source,
+            failures: vec![text],
+            ..Default::default()
+        });
+        self.import_report_scroll = 0;
+        self.show_import_report_popup = true;
+        self.app_mode = AppMode::ImportReport;
+    }
+
+    fn preview_csv_import(&mut self) {
+        let Some(csv_import) = self.csv_import.take() else {
+            return;
+        };
+        let imported = csv_import.connections();
+        let preview =
+            import::ImportPreview::new(import::SourceKind::File, &csv_import.path, imported, &self.ssh_connections);
+        self.show_csv_columns_popup = false;
+        self.preview_import(preview);
+    }
+
+    // Offers the marked rows, the rows shown (the whole list, or the search results when opened from search mode) and
+    // the group of the highlighted row with its subgroups.
+    fn open_export(&mut self) {
+        let shown = if self.app_mode == AppMode::Search {
+            ("Search results".to_string(), self.search_index.clone())
+        } else {
+            ("All connections".to_string(), self.view_index.clone())
+        };
+        self.export_scopes = vec![];
+        if !self.marked.is_empty() {
+            let marked = self.view_index.iter().copied().filter(|i| self.marked.contains(i)).collect();
+            self.export_scopes.push(("Marked connections".to_string(), marked));
+        }
+        self.export_scopes.push(shown);
+        let group = self.highlighted_connection().map(|connection| connection.group_name.clone()).unwrap_or_default();
+        if !group.is_empty() {
+            let prefix = format!("{}/", group);
+            let members = self
+                .view_index
+                .iter()
+                .copied()
+                .filter(|&i| {
+                    let name = &self.ssh_connections[i].group_name;
+                    *name == group || name.starts_with(&prefix)
+                })
+                .collect();
+            self.export_scopes.push((format!("Group {}", group), members));
+        }
+        self.export_scope = 0;
+        self.export_status = None;
+        self.last_app_mode = self.app_mode;
+        self.show_export_popup = true;
+        self.app_mode = AppMode::Export;
+    }
+
+    fn export_indexes(&self) -> &[usize] {
+        self.export_scopes.get(self.export_scope).map_or(&[], |(_, indexes)| indexes.as_slice())
+    }
+
+    fn export(&mut self) {
+        let path = paths::expand_home(self.export_input.value().trim());
+        let confirmed = self.export_status.is_some() && self.export_overwrite.as_ref() == Some(&path);
+        if path.is_file() && !confirmed && !profile::is_store(&path, &self.profiles) {
+            self.export_status = Some(Err(format!("{} exists: Enter again to replace it", paths::abbreviate_home(&path))));
+            self.export_overwrite = Some(path);
+            return;
+        }
+        self.export_overwrite = None;
+        let connections: Vec<&SSHConnection> = self.export_indexes().iter().map(|&i| &self.ssh_connections[i]).collect();
+        self.export_status = Some(
+            match export::write(self.export_format, &path, &connections, self.export_personal, &self.profiles) {
+                Ok(()) => Ok(format!("Exported {} connections", connections.len())),
+                Err(text) => Err(text),
+            },
+        );
+    }
+
+    fn next_export_scope(&mut self, forward: bool) {
+        let count = self.export_scopes.len().max(1);
+        self.export_scope = if forward { (self.export_scope + 1) % count } else { (self.export_scope + count - 1) % count };
+        self.export_status = None;
+    }
+
+    fn next_export_format(&mut self) {
+        self.export_format = self.export_format.next();
+        let path = export::with_extension(self.export_input.value(), self.export_format);
+        self.export_input = Input::default().with_value(path);
+        self.export_status = None;
+    }
+
+    fn close_export(&mut self) {
+        self.show_export_popup = false;
+        self.app_mode = self.last_app_mode;
+        if self.app_mode == AppMode::Search {
+            self.focus = Focus::SearchField;
+        }
+    }
+
+    fn select_import_row(&mut self, row: usize) {
+        let rows = self.import_preview.as_ref().map_or(0, |preview| preview.candidates.len());
+        self.import_state.select(Some(row.min(rows.saturating_sub(1))));
+    }
+
+    // Adds the selected connections and shows the report of the import.
+    fn finish_import(&mut self) {
+        self.show_import_preview_popup = false;
+        self.app_mode = AppMode::Normal;
+        let Some(preview) = self.import_preview.take() else {
+            return;
+        };
+        self.import_report = Some(preview.report());
+        self.import_report_scroll = 0;
+        self.show_import_report_popup = true;
+        self.app_mode = AppMode::ImportReport;
+        let replace = preview.replaced.is_some();
+        let connections = preview.into_selected();
+        if connections.is_empty() {
+            return;
+        }
+        // Replaced connections go to the trash, where they can be restored.
+        if replace {
+            let now = chrono::Local::now().timestamp();
+            for connection in &mut self.ssh_connections {
+                if connection.shared.is_none() && connection.deleted.is_none() {
+                    connection.deleted = Some(now);
+                }
+            }
+        }
+        for mut connection in connections {
+            connection.profile = self.profile.clone();
+            self.ssh_connections.push(connection);
+        }
+        self.update_config();
+        self.last_row();
+    }
+
+    // Flags imported connections whose Host block, or whose file, is gone. The ssh config is only read when a
+    // connection was imported from it.
+    fn check_sources(&mut self) {
+        let from_config = self.ssh_connections.iter().any(|connection| {
+            connection.source.as_ref().is_some_and(|source| source.kind == import::SourceKind::SshConfig)
+        });
+        let blocks = if from_config { parse::config_blocks() } else { vec![] };
+        for source in self.ssh_connections.iter_mut().filter_map(|connection| connection.source.as_mut()) {
+            source.missing = match source.kind {
+                import::SourceKind::SshConfig => blocks::find_source(&blocks, &source.alias).is_none(),
+                _ => !Path::new(&source.path).exists(),
+            };
+        }
+    }
+
+    // Reads the highlighted connection again from where it was imported. The name, group, notes and history stay
+    // as they are in the list.
+    fn refresh_from_source(&mut self) {
+        let Some(i) = self.get_row_index() else {
+            return;
+        };
+        let Some(source) = self.ssh_connections[i].source.clone() else {
+            return self.show_source_error("This connection was not imported.".to_string());
+        };
+        let find = |connections: Vec<SSHConnection>| {
+            connections
+                .into_iter()
+                .find(|connection| connection.server_name == source.alias)
+                .ok_or(format!("\"{}\" is no longer in\n{}", source.alias, source.path))
+        };
+        let resolved = match source.kind {
+            import::SourceKind::SshConfig if !check_openssh() => {
+                Err("Failed to read the ssh config.\nIs the OpenSSH-client installed?".to_string())
+            }
+            import::SourceKind::SshConfig => parse::resolve_host(&source.alias),
+            import::SourceKind::KnownHosts => known_hosts::read_known_hosts(Path::new(&source.path))
+                .map_err(|text| format!("{}: {}", source.path, text))
+                .and_then(|known_hosts| find(known_hosts.connections)),
+            import::SourceKind::File => {
+                import::read_file(Path::new(&source.path)).and_then(|imported| find(imported.connections))
+            }
+        };
+        match resolved {
+            Ok(resolved) => {
+                let connection = &mut self.ssh_connections[i];
+                connection.username = resolved.username;
+                connection.hostname = resolved.hostname;
+                connection.port = resolved.port;
+                connection.options = resolved.options;
+                connection.source = Some(import::ImportSource {
+                    imported: chrono::Local::now().timestamp(),
+                    ..resolved.source.unwrap_or(source)
+                });
+                self.check_sources();
+                self.update_config();
+            }
+            Err(text) => {
+                self.check_sources();
+                self.show_source_error(text);
+            }
+        }
+    }
+
+    fn show_source_error(&mut self, text: String) {
+        self.error_text = text;
+        self.last_app_mode = self.app_mode;
+        self.show_error_popup = true;
+        self.app_mode = AppMode::Error;
+    }
+
+    fn select_trash_row(&mut self, row: usize) {
+        let rows = self.trash_indexes().len();
+        self.trash_state.select(Some(row.min(rows.saturating_sub(1))));
+    }
+
+    fn copy_connection(&mut self) {
+        if let Some(i) = self.get_row_index() {
+            self.ssh_connections.insert(i + 1, self.ssh_connections[i].clone());
+            self.update_config();
+        };
+    }
+
+    fn focus_next_field(&mut self) {
+        self.focus = match self.focus {
+            Focus::ServerNameField => Focus::GroupNameField,
+            Focus::GroupNameField => Focus::UsernameField,
+            Focus::UsernameField => Focus::HostnameField,
+            Focus::HostnameField => Focus::PortField,
+            Focus::PortField => Focus::OptionsField,
+            Focus::OptionsField => Focus::NotesField,
+            Focus::NotesField => Focus::NotesField,
+            _ => Focus::ServerNameField,
+        };
+    }
+
+    fn focus_previous_field(&mut self) {
+        self.focus = match self.focus {
+            Focus::ServerNameField => Focus::ServerNameField,
+            Focus::GroupNameField => Focus::ServerNameField,
+            Focus::UsernameField => Focus::GroupNameField,
+            Focus::HostnameField => Focus::UsernameField,
+            Focus::PortField => Focus::HostnameField,
+            Focus::OptionsField => Focus::PortField,
+            Focus::NotesField => Focus::OptionsField,
             _ => Focus::ServerNameField,
         };
     }
 
     fn move_row_down(&mut self) {
         if let Some(i) = self.table_state.selected() {
-            if i >= self.ssh_connections.len() - 1 {
+            if i + 1 >= self.view_index.len() {
                 self.table_state.select(Some(i));
             } else {
-                self.ssh_connections.swap(i, i + 1);
+                self.ssh_connections.swap(self.view_index[i], self.view_index[i + 1]);
                 self.table_state.select(Some(i + 1));
                 self.scroll_state = self.scroll_state.position(i + 1);
             }
@@ -416,7 +1607,7 @@ impl App {
             if i == 0 {
                 self.table_state.select(Some(i));
             } else {
-                self.ssh_connections.swap(i, i - 1);
+                self.ssh_connections.swap(self.view_index[i], self.view_index[i - 1]);
                 self.table_state.select(Some(i - 1));
                 self.scroll_state = self.scroll_state.position(i - 1);
             }
@@ -427,240 +1618,406 @@ impl App {
     pub fn search(&mut self) {
         let search_input = self.search_input.to_string().to_lowercase();
         self.search_index.clear();
-        for (index, connection) in self.ssh_connections.iter().enumerate() {
+        for &index in &self.view_index {
+            let connection = &self.ssh_connections[index];
             if connection.server_name.to_lowercase().contains(&search_input)
                 || connection.hostname.to_lowercase().contains(&search_input)
                 || connection.username.to_lowercase().contains(&search_input)
                 || connection.port.to_lowercase().contains(&search_input)
                 || connection.group_name.to_lowercase().contains(&search_input)
                 || connection.options.to_lowercase().contains(&search_input)
+                || connection.notes.to_lowercase().contains(&search_input)
             {
                 self.search_index.push(index);
             }
         }
-        let i = match self.table_state.selected() {
-            Some(i) => i,
-            None => 0,
-        };
+        let i = self.table_state.selected().unwrap_or_default();
         self.table_state.select(Some(i));
         self.scroll_state = self.scroll_state.position(i);
     }
 
-    fn get_row_index(&self) -> Option<usize> {
-        let selected_row = match self.table_state.selected() {
-            Some(row) => row,
-            None => return None,
-        };
-        if self.app_mode == AppMode::Normal {
-            Some(selected_row)
+    fn shows_all_rows(&self) -> bool {
+        self.app_mode == AppMode::Normal
+            || self.app_mode == AppMode::New
+            || self.app_mode == AppMode::Move
+            || self.app_mode == AppMode::Options
+            || self.app_mode == AppMode::Columns
+            || self.app_mode == AppMode::Import
+            || self.app_mode == AppMode::Sort
+            || self.app_mode == AppMode::Trash
+            || self.app_mode == AppMode::Profiles
+            || self.app_mode == AppMode::Sync
+            || self.app_mode == AppMode::Merge
+            || self.app_mode == AppMode::Unlock
+            || self.app_mode == AppMode::ImportPath
+            || self.app_mode == AppMode::ImportPreview
+            || self.app_mode == AppMode::ImportReport
+            || self.app_mode == AppMode::CsvColumns
+            || (self.app_mode == AppMode::Export && self.last_app_mode != AppMode::Search)
+            || (self.app_mode == AppMode::Confirm && self.last_app_mode != AppMode::Search)
+    }
+
+    // Maps a table row to its position in ssh_connections.
+    fn row_index(&self, row: usize) -> Option<usize> {
+        if self.shows_all_rows() {
+            self.view_index.get(row).copied()
         } else {
-            self.search_index.get(selected_row).copied()
+            self.search_index.get(row).copied()
         }
     }
 
+    fn highlighted_connection(&self) -> Option<&SSHConnection> {
+        self.ssh_connections.get(self.get_row_index()?)
+    }
+
+    fn get_row_index(&self) -> Option<usize> {
+        self.row_index(self.table_state.selected()?)
+    }
+
     fn apply_appconfig(&mut self) {
         let appconfig = read_appconfig();
         self.color = match appconfig.color {
-            Some(color) => {
-                if color == "red"
-                    || color == "green"
-                    || color == "yellow"
-                    || color == "blue"
-                    || color == "magenta"
-                    || color == "cyan"
-                    || color == "gray"
-                    || color == "darkgray"
-                    || color == "lightred"
-                    || color == "lightgreen"
-                    || color == "lightyellow"
-                    || color == "lightblue"
-                    || color == "lightmagenta"
-                    || color == "lightcyan"
-                    || color == "white"
-                {
-                    color
-                } else {
-                    "yellow".to_string()
-                }
-            }
-            None => "yellow".to_string(),
+            Some(color) if theme::parse_color(&color).is_some() => color,
+            _ => "yellow".to_string(),
         };
         if let Some(c) = appconfig.row_height {
             if c == 1 || c == 3 {
                 self.row_height = c;
             }
         }
+        self.show_detail_panel = appconfig.detail_panel.unwrap_or(false);
+        self.themes = appconfig.themes.unwrap_or_default();
+        if let Some(theme_name) = appconfig.theme {
+            match Theme::resolve(&theme_name, &self.themes) {
+                Ok(theme) => {
+                    self.theme = theme;
+                    self.theme_name = theme_name;
+                }
+                Err(text) => self.show_config_error(format!("{}.\nThe dark theme is used.", text)),
+            }
+        }
+        if let Some(keybindings) = appconfig.keybindings {
+            match Keybindings::from_config(&keybindings) {
+                Ok(keybindings) => self.keybindings = keybindings,
+                Err(text) => self.show_config_error(format!("{}.\nDefault keybindings are used.", text)),
+            }
+        }
+        self.sort_keys = sort::keys_from_config(&appconfig.sort.unwrap_or_default());
+        self.apply_sort();
+        if let Some(columns) = appconfig.columns {
+            self.columns = columns::columns_from_config(&columns);
+            if !self.columns.iter().any(|layout| layout.visible) {
+                self.columns[0].visible = true;
+            }
+        }
+    }
+
+    // Outside the table (first run form, import popup, another error) the error waits until Normal mode.
+    fn show_config_error(&mut self, text: String) {
+        if self.app_mode == AppMode::Normal {
+            self.error_text = text;
+            self.last_app_mode = AppMode::Normal;
+            self.show_error_popup = true;
+            self.app_mode = AppMode::Error;
+        } else if self.pending_error.is_none() {
+            self.pending_error = Some(text);
+        }
     }
 
     pub fn update_appconfig(&mut self) {
-        let appconfig = AppConfig {
-            color: Some(self.color.clone()),
-            row_height: Some(self.row_height),
+        let mut appconfig = read_appconfig();
+        appconfig.color = Some(self.color.clone());
+        appconfig.row_height = Some(self.row_height);
+        appconfig.detail_panel = Some(self.show_detail_panel);
+        appconfig.theme = Some(self.theme_name.clone());
+        appconfig.columns = if self.columns == columns::default_columns() {
+            None
+        } else {
+            Some(columns::columns_to_config(&self.columns))
         };
+        appconfig.sort = Some(self.sort_keys.iter().map(|key| key.to_config()).collect());
         let toml = toml::to_string(&appconfig).unwrap();
-        match fs::write(get_appconfig_path(), toml) {
-            Ok(_) => (),
-            Err(_) => (),
-        };
+        fs::write(get_appconfig_path(), toml).ok();
+    }
+
+    pub fn accent_color(&self) -> Color {
+        theme::parse_color(&self.color).unwrap_or(Color::Yellow)
     }
 
     pub fn next_color(&mut self) {
-        if self.color == "yellow" {
-            self.color = "lightyellow".to_string()
-        } else if self.color == "lightyellow" {
-            self.color = "white".to_string()
-        } else if self.color == "white" {
-            self.color = "darkgray".to_string()
-        } else if self.color == "darkgray" {
-            self.color = "gray".to_string()
-        } else if self.color == "gray" {
-            self.color = "red".to_string()
-        } else if self.color == "red" {
-            self.color = "lightred".to_string()
-        } else if self.color == "lightred" {
-            self.color = "green".to_string()
-        } else if self.color == "green" {
-            self.color = "lightgreen".to_string()
-        } else if self.color == "lightgreen" {
-            self.color = "blue".to_string()
-        } else if self.color == "blue" {
-            self.color = "lightblue".to_string()
-        } else if self.color == "lightblue" {
-            self.color = "magenta".to_string()
-        } else if self.color == "magenta" {
-            self.color = "lightmagenta".to_string()
-        } else if self.color == "lightmagenta" {
-            self.color = "cyan".to_string()
-        } else if self.color == "cyan" {
-            self.color = "lightcyan".to_string()
-        } else if self.color == "lightcyan" {
-            self.color = "yellow".to_string()
-        }
+        let i = theme::ACCENT_COLORS.iter().position(|color| *color == self.color);
+        let i = i.map_or(0, |i| (i + 1) % theme::ACCENT_COLORS.len());
+        self.color = theme::ACCENT_COLORS[i].to_string();
     }
 
     pub fn previous_color(&mut self) {
-        if self.color == "yellow" {
-            self.color = "lightcyan".to_string()
-        } else if self.color == "lightcyan" {
-            self.color = "cyan".to_string()
-        } else if self.color == "cyan" {
-            self.color = "lightmagenta".to_string()
-        } else if self.color == "lightmagenta" {
-            self.color = "magenta".to_string()
-        } else if self.color == "magenta" {
-            self.color = "lightblue".to_string()
-        } else if self.color == "lightblue" {
-            self.color = "blue".to_string()
-        } else if self.color == "blue" {
-            self.color = "lightgreen".to_string()
-        } else if self.color == "lightgreen" {
-            self.color = "green".to_string()
-        } else if self.color == "green" {
-            self.color = "lightred".to_string()
-        } else if self.color == "lightred" {
-            self.color = "red".to_string()
-        } else if self.color == "red" {
-            self.color = "gray".to_string()
-        } else if self.color == "gray" {
-            self.color = "darkgray".to_string()
-        } else if self.color == "darkgray" {
-            self.color = "white".to_string()
-        } else if self.color == "white" {
-            self.color = "lightyellow".to_string()
-        } else if self.color == "lightyellow" {
-            self.color = "yellow".to_string()
-        }
-    }
-
-    pub fn sort(&mut self, column: String) {
-        match column.as_str() {
-            "name" => self
-                .ssh_connections
-                .sort_by_key(|connection| connection.server_name.to_lowercase().clone()),
-            "group" => self
-                .ssh_connections
-                .sort_by_key(|connection| connection.group_name.to_lowercase().clone()),
-            "username" => self
-                .ssh_connections
-                .sort_by_key(|connection| connection.username.to_lowercase().clone()),
-            "hostname" => self
-                .ssh_connections
-                .sort_by_key(|connection| connection.hostname.to_lowercase().clone()),
-            "port" => self.ssh_connections.sort_by_key(|connection| connection.port.parse::<u16>().unwrap_or_default().clone()),
-            _ => (),
+        let i = theme::ACCENT_COLORS.iter().position(|color| *color == self.color);
+        let i = i.map_or(0, |i| (i + theme::ACCENT_COLORS.len() - 1) % theme::ACCENT_COLORS.len());
+        self.color = theme::ACCENT_COLORS[i].to_string();
+    }
+
+    pub fn next_theme(&mut self) {
+        let names = theme::theme_names(&self.themes);
+        let i = names.iter().position(|name| *name == self.theme_name).map_or(0, |i| i + 1);
+        // Themes with invalid colors are skipped while cycling.
+        for offset in 0..names.len() {
+            let name = &names[(i + offset) % names.len()];
+            if let Ok(theme) = Theme::resolve(name, &self.themes) {
+                self.theme = theme;
+                self.theme_name = name.clone();
+                return;
+            }
+        }
+    }
+
+    // The sort only changes the displayed order; ssh_connections keeps the manual order.
+    fn apply_sort(&mut self) {
+        self.flag_conflicts();
+        let overridden: Vec<&String> = self
+            .ssh_connections
+            .iter()
+            .filter(|connection| connection.deleted.is_none())
+            .filter_map(|connection| connection.overrides.as_ref())
+            .collect();
+        let hidden = |connection: &SSHConnection| {
+            connection.deleted.is_some()
+                || (connection.shared.is_some() && overridden.contains(&&connection.server_name))
+        };
+        self.view_index = (0..self.ssh_connections.len())
+            .filter(|&i| !hidden(&self.ssh_connections[i]))
+            .collect();
+        if !self.sort_keys.is_empty() {
+            let connections = &self.ssh_connections;
+            let keys = &self.sort_keys;
+            self.view_index.sort_by(|&a, &b| sort::compare(&connections[a], &connections[b], keys));
+        }
+    }
+
+    // Flags names used by a shared entry and by another shared or personal entry that does not override it.
+    fn flag_conflicts(&mut self) {
+        let mut counts: HashMap<String, (usize, bool)> = HashMap::new();
+        for connection in &self.ssh_connections {
+            if connection.deleted.is_some() || connection.overrides.is_some() {
+                continue;
+            }
+            let entry = counts.entry(connection.server_name.clone()).or_default();
+            entry.0 += 1;
+            entry.1 |= connection.shared.is_some();
+        }
+        for connection in &mut self.ssh_connections {
+            connection.conflict = connection.deleted.is_none()
+                && connection.overrides.is_none()
+                && counts
+                    .get(&connection.server_name)
+                    .is_some_and(|(count, shared)| *count > 1 && *shared);
+        }
+    }
+
+    pub fn sort_by(&mut self, column: Column) {
+        sort::push_key(&mut self.sort_keys, column);
+        self.refresh_sort();
+    }
+
+    pub fn sort_manual(&mut self) {
+        self.sort_keys.clear();
+        self.refresh_sort();
+    }
+
+    fn refresh_sort(&mut self) {
+        let selected = self.get_row_index();
+        self.apply_sort();
+        if let Some(row) = selected.and_then(|index| self.view_index.iter().position(|&i| i == index)) {
+            self.select_row(row);
         }
+        self.update_appconfig();
     }
 }
 
 fn get_config_path() -> PathBuf {
-    let mut config_dir_pathbuf = match env::home_dir() {
-        Some(path) => path,
-        None => {
-            ratatui::restore();
-            eprintln!("Error: Could not find the home directory.");
-            execute!(stdout(), Show).ok();
-            std::process::exit(1);
-        }
+    resolve_config_file(paths::store_override(), "ssh-list.json")
+}
+
+fn load_store(path: &Path, passphrase: Option<&SecretString>) -> Result<Vec<SSHConnection>, String> {
+    // A missing store is empty; any other read error must not let the next save replace it.
+    let file_data = match fs::read(path) {
+        Ok(data) => data,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(error) => return Err(format!("Could not read {}\nDetails: {}", path.display(), error)),
     };
-    config_dir_pathbuf.push(".ssh");
-    let config_dir_path = config_dir_pathbuf.display().to_string();
-    match fs::create_dir_all(&config_dir_path) {
-        Ok(_) => (),
-        Err(text) => {
-            ratatui::restore();
-            eprintln!("{}: {}", config_dir_path, text);
-            execute!(stdout(), Show).ok();
-            std::process::exit(1);
+    let file_data = if crypt::is_encrypted(&file_data) {
+        crypt::decrypt(&file_data, passphrase)?
+    } else {
+        String::from_utf8_lossy(&file_data).to_string()
+    };
+    if file_data.is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&file_data).map_err(|text| {
+        format!(
+            "Configuration file is invalid. Check the syntax in {}\nDetails: {}",
+            path.display(),
+            text
+        )
+    })
+}
+
+fn store_is_encrypted(path: &Path) -> bool {
+    fs::read(path).is_ok_and(|data| crypt::is_encrypted(&data))
+}
+
+// Encrypts or decrypts the store of a profile in place.
+fn run_store_command(command: cli::StoreCommand, profile_name: &str) -> Result<String, String> {
+    if profile_name == profile::MERGED_PROFILE {
+        return Err("Choose a single profile to encrypt or decrypt.".to_string());
+    }
+    let profiles = read_appconfig().profiles.unwrap_or_default();
+    let path = profile::store_path(profile_name, &profiles);
+    let _lock = store::lock(&path).map_err(|text| format!("{}: {}", path.display(), text))?;
+    let data = fs::read(&path).map_err(|text| format!("{}: {}", path.display(), text))?;
+    let contents = match command {
+        cli::StoreCommand::Encrypt => {
+            if crypt::is_encrypted(&data) {
+                return Err(format!("{} is already encrypted.", path.display()));
+            }
+            // Refuse to encrypt a file that could not be opened afterwards.
+            load_store(&path, None)?;
+            let passphrase = crypt::read_passphrase("New passphrase: ").map_err(|text| text.to_string())?;
+            if passphrase.is_empty() {
+                return Err("The passphrase cannot be empty.".to_string());
+            }
+            if crypt::read_passphrase("Repeat the passphrase: ").map_err(|text| text.to_string())? != passphrase {
+                return Err("The passphrases do not match.".to_string());
+            }
+            crypt::encrypt(&String::from_utf8_lossy(&data), &SecretString::from(passphrase))?
+        }
+        cli::StoreCommand::Decrypt => {
+            if !crypt::is_encrypted(&data) {
+                return Err(format!("{} is not encrypted.", path.display()));
+            }
+            let passphrase = crypt::read_passphrase("Passphrase: ").map_err(|text| text.to_string())?;
+            crypt::decrypt(&data, Some(&SecretString::from(passphrase)))
+                .map_err(|text| text.replace('\n', " "))?
+                .into_bytes()
         }
     };
-    config_dir_pathbuf.push("ssh-list.json");
-    config_dir_pathbuf
+    store::write(&path, &contents).map_err(|text| format!("{}: {}", path.display(), text))?;
+    Ok(match command {
+        cli::StoreCommand::Encrypt => format!("Encrypted {}", path.display()),
+        cli::StoreCommand::Decrypt => format!("Decrypted {}", path.display()),
+    })
 }
 
-fn read_config() -> Vec<SSHConnection> {
-    let config_path = get_config_path();
-    let file_data: String = fs::read_to_string(&config_path).unwrap_or_default();
-    if file_data.is_empty() {
-        return Vec::new();
+fn command_store_path(profile_name: &str) -> Result<PathBuf, String> {
+    if profile_name == profile::MERGED_PROFILE {
+        return Err("Choose a single profile to export or import.".to_string());
     }
-    match serde_json::from_str(&file_data) {
-        Ok(data) => data,
-        Err(text) => {
-            ratatui::restore();
-            eprintln!(
-                "Error: Configuration file is invalid. Check the syntax in {}",
-                &config_path.display()
-            );
-            eprintln!("Details: {}", text);
-            execute!(stdout(), Show).ok();
-            std::process::exit(1);
+    let profiles = read_appconfig().profiles.unwrap_or_default();
+    Ok(profile::store_path(profile_name, &profiles))
+}
+
+// Reads a store for a command, asking for the passphrase when it is encrypted.
+fn read_store(path: &Path) -> Result<(Vec<SSHConnection>, Option<SecretString>), String> {
+    let passphrase = if store_is_encrypted(path) {
+        let passphrase = crypt::read_passphrase("Passphrase: ").map_err(|text| text.to_string())?;
+        Some(SecretString::from(passphrase))
+    } else {
+        None
+    };
+    let connections = load_store(path, passphrase.as_ref()).map_err(|text| text.replace('\n', " "))?;
+    Ok((connections, passphrase))
+}
+
+// Exports the connections of a profile, or of one of its groups, without opening the app.
+fn export_store(export: &cli::ExportArgs, profile_name: &str) -> Result<String, String> {
+    let name = export.format.clone().unwrap_or(import::extension(&export.path));
+    let format = export::ExportFormat::from_name(&name).ok_or(format!("Unknown export format: {}", name))?;
+    let (connections, _) = read_store(&command_store_path(profile_name)?)?;
+    let group = export.group.clone().unwrap_or_default();
+    let prefix = format!("{}/", group);
+    let connections: Vec<&SSHConnection> = connections
+        .iter()
+        .filter(|connection| connection.deleted.is_none())
+        .filter(|connection| group.is_empty() || connection.group_name == group || connection.group_name.starts_with(&prefix))
+        .collect();
+    let profiles = read_appconfig().profiles.unwrap_or_default();
+    export::write(format, &export.path, &connections, export.personal, &profiles)?;
+    Ok(format!("Exported {} connections to {}", connections.len(), export.path.display()))
+}
+
+// Adds the connections of a file to the store of a profile, or replaces the store with them. Connections already in
+// the store are skipped, like in the import preview of the app.
+fn import_into_store(import: &cli::ImportArgs, profile_name: &str) -> Result<String, String> {
+    let imported = import::read_file(&import.path)?;
+    let path = command_store_path(profile_name)?;
+    let _lock = store::lock(&path).map_err(|text| format!("{}: {}", path.display(), text))?;
+    let (mut connections, passphrase) = read_store(&path)?;
+    let mut preview = import::ImportPreview::new(import::SourceKind::File, &import.path, imported, &connections);
+    if import.replace {
+        let replaceable = connections.iter().filter(|connection| connection.deleted.is_none()).count();
+        preview.toggle_replace(&connections, replaceable);
+    }
+    let report = preview.report();
+    let selected = preview.into_selected();
+    if selected.is_empty() && report.replaced.is_some() {
+        return Err(format!("{} has no connections to replace the store with.", import.path.display()));
+    }
+    if report.replaced.is_some() {
+        let now = chrono::Local::now().timestamp();
+        for connection in connections.iter_mut().filter(|connection| connection.deleted.is_none()) {
+            connection.deleted = Some(now);
         }
     }
+    connections.extend(selected);
+    let json = serde_json::to_string_pretty(&connections).map_err(|text| text.to_string())?;
+    let contents = match &passphrase {
+        Some(passphrase) => crypt::encrypt(&json, passphrase)?,
+        None => json.into_bytes(),
+    };
+    store::write(&path, &contents).map_err(|text| format!("{}: {}", path.display(), text))?;
+    let mut lines = vec![format!("Imported into {}", path.display())];
+    for (title, section) in report.sections() {
+        lines.push(format!("{}:", title));
+        lines.extend(section.iter().map(|line| format!("  {}", line)));
+    }
+    Ok(lines.join("\n"))
+}
+
+// Compares what would be written to the file, ignoring fields that are never saved.
+fn same_store(a: &[SSHConnection], b: &[SSHConnection]) -> bool {
+    serde_json::to_string(a).ok() == serde_json::to_string(b).ok()
+}
+
+fn exit_on_write_error(path: &Path, text: std::io::Error) -> ! {
+    restore_terminal();
+    execute!(stdout(), Show).ok();
+    eprintln!("Error writing to file {}: {}", path.display(), text);
+    std::process::exit(1);
 }
 
 fn get_appconfig_path() -> PathBuf {
-    let mut config_dir_pathbuf = match env::home_dir() {
+    resolve_config_file(paths::settings_override(), "ssh-list_config.toml")
+}
+
+// Uses the override when given, otherwise the file in the config directory, and creates its parent directory.
+fn resolve_config_file(path_override: Option<PathBuf>, file_name: &str) -> PathBuf {
+    let config_pathbuf = match path_override.or_else(|| paths::config_dir().map(|dir| dir.join(file_name))) {
         Some(path) => path,
         None => {
-            ratatui::restore();
+            restore_terminal();
             eprintln!("Error: Could not find the home directory.");
             execute!(stdout(), Show).ok();
             std::process::exit(1);
         }
     };
-    config_dir_pathbuf.push(".ssh");
-    let config_dir_path = config_dir_pathbuf.display().to_string();
-    match fs::create_dir_all(&config_dir_path) {
-        Ok(_) => (),
-        Err(text) => {
-            ratatui::restore();
-            eprintln!("{}: {}", config_dir_path, text);
+    if let Some(config_dir_path) = config_pathbuf.parent() {
+        if let Err(text) = fs::create_dir_all(config_dir_path) {
+            restore_terminal();
+            eprintln!("{}: {}", config_dir_path.display(), text);
             execute!(stdout(), Show).ok();
             std::process::exit(1);
         }
-    };
-    config_dir_pathbuf.push("ssh-list_config.toml");
-    config_dir_pathbuf
+    }
+    config_pathbuf
 }
 
 fn read_appconfig() -> AppConfig {
@@ -671,7 +2028,7 @@ fn read_appconfig() -> AppConfig {
 }
 
 pub fn popup_area(area: Rect) -> Rect {
-    let vertical = Layout::vertical([Constraint::Length(21)]).flex(Flex::Center);
+    let vertical = Layout::vertical([Constraint::Length(24)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Length(40)]).flex(Flex::Center);
     let [area] = vertical.areas(area);
     let [area] = horizontal.areas(area);
@@ -679,8 +2036,8 @@ pub fn popup_area(area: Rect) -> Rect {
 }
 
 pub fn config_popup_area(area: Rect) -> Rect {
-    let vertical = Layout::vertical([Constraint::Length(9)]).flex(Flex::Center);
-    let horizontal = Layout::horizontal([Constraint::Length(46)]).flex(Flex::Center);
+    let vertical = Layout::vertical([Constraint::Length(10)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(66)]).flex(Flex::Center);
     let [area] = vertical.areas(area);
     let [area] = horizontal.areas(area);
     area
@@ -703,15 +2060,116 @@ pub fn run_popup_area(area: Rect) -> Rect {
 }
 
 fn check_openssh() -> bool {
-    match Command::new("ssh")
+    Command::new("ssh")
         .arg("-v")
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status()
-    {
-        Ok(_) => true,
-        Err(_) => false,
-    }
+        .is_ok()
+}
+
+pub fn preview_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(10)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(70)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn columns_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(16)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(46)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn confirm_popup_area(area: Rect, lines: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(lines + 4)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(50)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn trash_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(70)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(80)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn import_path_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(3)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(60)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn export_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(10)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(60)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn csv_columns_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(15)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(70)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn import_preview_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(80)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(90)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn profiles_popup_area(area: Rect, rows: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(rows + 3)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(40)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn sync_popup_area(area: Rect, rows: u16) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(rows + 4)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(72)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn import_report_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(80)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(100)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn merge_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Percentage(80)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(90)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
+}
+
+pub fn unlock_popup_area(area: Rect) -> Rect {
+    let vertical = Layout::vertical([Constraint::Length(8)]).flex(Flex::Center);
+    let horizontal = Layout::horizontal([Constraint::Length(50)]).flex(Flex::Center);
+    let [area] = vertical.areas(area);
+    let [area] = horizontal.areas(area);
+    area
 }
 
 pub fn search_area(area: Rect) -> Rect {
@@ -723,9 +2181,213 @@ pub fn search_area(area: Rect) -> Rect {
 }
 
 pub fn options_popup_area(area: Rect) -> Rect {
-    let vertical = Layout::vertical([Constraint::Length(7)]).flex(Flex::Center);
+    let vertical = Layout::vertical([Constraint::Length(11)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Length(46)]).flex(Flex::Center);
     let [area] = vertical.areas(area);
     let [area] = horizontal.areas(area);
     area
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+    use std::sync::{Mutex, Once};
+
+    // Held by tests that change the environment and while an App reads it.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    // App::new reads the settings and the default store, which point into an empty directory for all tests.
+    fn app_with_store(store: &Path) -> App {
+        static SETUP: Once = Once::new();
+        let _env = ENV_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        SETUP.call_once(|| {
+            let dir = tempfile::tempdir().unwrap().keep();
+            env::set_var(paths::CONFIG_ENV, dir.join("ssh-list.json"));
+            env::set_var(paths::SETTINGS_ENV, dir.join("ssh-list_config.toml"));
+            env::set_var(paths::SSH_CONFIG_ENV, dir.join("config"));
+        });
+        let mut app = App::new(profile::DEFAULT_PROFILE.to_string());
+        app.profiles.insert("test".to_string(), store.display().to_string());
+        app.load_profile("test").unwrap();
+        app
+    }
+
+    fn connection(name: &str, hostname: &str) -> SSHConnection {
+        SSHConnection {
+            server_name: name.to_string(),
+            group_name: String::new(),
+            username: "user".to_string(),
+            hostname: hostname.to_string(),
+            port: "22".to_string(),
+            options: String::new(),
+            notes: String::new(),
+            history: vec![],
+            deleted: None,
+            overrides: None,
+            source: None,
+            profile: String::new(),
+            shared: None,
+            conflict: false,
+        }
+    }
+
+    fn write_store(path: &Path, connections: &[SSHConnection]) {
+        store::write(path, serde_json::to_string_pretty(connections).unwrap().as_bytes()).unwrap();
+    }
+
+    fn stored_hosts(path: &Path) -> Vec<(String, String)> {
+        load_store(path, None)
+            .unwrap()
+            .into_iter()
+            .map(|connection| (connection.server_name, connection.hostname))
+            .collect()
+    }
+
+    fn hosts(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(name, hostname)| (name.to_string(), hostname.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn store_path_follows_overrides_environment_and_config_dirs() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+        let saved: Vec<_> = ["HOME", "XDG_CONFIG_HOME", paths::CONFIG_ENV]
+            .into_iter()
+            .map(|name| (name, env::var_os(name)))
+            .collect();
+        let home = tempfile::tempdir().unwrap();
+        env::set_var("HOME", home.path());
+        env::remove_var("XDG_CONFIG_HOME");
+        env::remove_var(paths::CONFIG_ENV);
+
+        // Without an ssh-list config directory the store lives in ~/.ssh, which is created.
+        assert_eq!(get_config_path(), home.path().join(".ssh/ssh-list.json"));
+        assert!(home.path().join(".ssh").is_dir());
+        fs::create_dir_all(home.path().join(".config/ssh-list")).unwrap();
+        assert_eq!(get_config_path(), home.path().join(".config/ssh-list/ssh-list.json"));
+
+        let xdg = home.path().join("xdg");
+        env::set_var("XDG_CONFIG_HOME", &xdg);
+        assert_eq!(get_config_path(), home.path().join(".ssh/ssh-list.json"));
+        fs::create_dir_all(xdg.join("ssh-list")).unwrap();
+        assert_eq!(get_config_path(), xdg.join("ssh-list/ssh-list.json"));
+
+        env::set_var(paths::CONFIG_ENV, "");
+        assert_eq!(get_config_path(), xdg.join("ssh-list/ssh-list.json"));
+        env::set_var(paths::CONFIG_ENV, "~/stores/env.json");
+        assert_eq!(get_config_path(), home.path().join("stores/env.json"));
+        assert!(home.path().join("stores").is_dir());
+
+        // A command line path wins over the variable.
+        let cli = home.path().join("cli/store.json");
+        assert_eq!(resolve_config_file(Some(cli.clone()), "ssh-list.json"), cli);
+        assert!(home.path().join("cli").is_dir());
+
+        for (name, value) in saved {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+
+    #[test]
+    fn unreadable_store_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_store(&dir.path().join("missing.json"), None).unwrap().is_empty());
+        // A directory in place of the store cannot be read.
+        let Err(text) = load_store(dir.path(), None) else {
+            panic!("a directory was read as a store");
+        };
+        assert!(text.starts_with(&format!("Could not read {}", dir.path().display())));
+    }
+
+    #[test]
+    fn wrong_passphrase_leaves_the_store_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+        let locked_path = dir.path().join("locked.json");
+        write_store(&path, &[connection("a", "h1")]);
+        let json = serde_json::to_string_pretty(&[connection("b", "h2")]).unwrap();
+        let encrypted = crypt::encrypt(&json, &SecretString::from("right".to_string())).unwrap();
+        store::write(&locked_path, &encrypted).unwrap();
+        let mut app = app_with_store(&path);
+        app.profiles.insert("locked".to_string(), locked_path.display().to_string());
+        app.unlock_profile = "locked".to_string();
+
+        app.passphrase_input = Input::new("wrong".to_string());
+        app.unlock();
+        assert_eq!(app.unlock_error, "Wrong passphrase");
+        assert!(app.passphrase.is_none());
+        assert_eq!(fs::read(&locked_path).unwrap(), encrypted);
+
+        app.passphrase_input = Input::new("right".to_string());
+        app.unlock();
+        assert_eq!(app.profile, "locked");
+        assert_eq!(app.ssh_connections[0].server_name, "b");
+        assert_eq!(fs::read(&locked_path).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn shared_connections_are_not_copied() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+        write_store(&path, &[connection("a", "h1")]);
+        let mut app = app_with_store(&path);
+        app.ssh_connections.push(SSHConnection {
+            shared: Some("team".to_string()),
+            ..connection("b", "h2")
+        });
+        app.apply_sort();
+        app.table_state.select(Some(1));
+        handler::handle_key_event(&mut app, KeyEvent::from(KeyCode::Char('c')));
+        assert_eq!(app.ssh_connections.len(), 2);
+        assert!(app.app_mode == AppMode::Error);
+        assert_eq!(stored_hosts(&path), hosts(&[("a", "h1")]));
+    }
+
+    #[test]
+    fn external_edits_are_merged_on_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+        write_store(&path, &[connection("a", "h1"), connection("b", "h1")]);
+        let mut app = app_with_store(&path);
+        // Another program changes b and adds c while the app runs.
+        write_store(&path, &[connection("a", "h1"), connection("b", "h22"), connection("c", "h1")]);
+        app.ssh_connections[0].hostname = "h9".to_string();
+        app.update_config();
+        assert_eq!(stored_hosts(&path), hosts(&[("a", "h9"), ("b", "h22"), ("c", "h1")]));
+        assert!(app.pending_error.is_none());
+        assert_eq!(app.ssh_connections.len(), 3);
+    }
+
+    #[test]
+    fn two_instances_keep_each_others_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+        write_store(&path, &[connection("a", "h1")]);
+        let mut first = app_with_store(&path);
+        let mut second = app_with_store(&path);
+        first.ssh_connections.push(connection("b", "h1"));
+        first.update_config();
+        second.ssh_connections.push(connection("c", "h1"));
+        second.update_config();
+        assert_eq!(stored_hosts(&path), hosts(&[("a", "h1"), ("c", "h1"), ("b", "h1")]));
+    }
+
+    #[test]
+    fn conflicting_external_edit_keeps_the_local_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.json");
+        write_store(&path, &[connection("a", "h1")]);
+        let mut app = app_with_store(&path);
+        write_store(&path, &[connection("a", "h22")]);
+        app.ssh_connections[0].hostname = "h333".to_string();
+        app.update_config();
+        assert_eq!(stored_hosts(&path), hosts(&[("a", "h333")]));
+        assert!(app.pending_error.as_ref().is_some_and(|text| text.contains("changed outside ssh-list: a")));
+    }
+}
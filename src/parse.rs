@@ -1,8 +1,6 @@
 use crate::*;
 use glob::glob;
-use std::fs::File;
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader};
 use std::path::Path;
 
 #[derive(PartialEq, Clone)]
@@ -14,51 +12,231 @@ pub struct SSHConfigConnection {
     options: Vec<(String, String)>, 
 }
 
-pub fn import_config(app: &mut App) {
+// Imports the hosts named in Host lines, and the hosts of known_hosts that fall under a Host or Match pattern. Fails
+// only when ssh cannot read the config at all.
+pub fn import_config() -> Result<import::Imported, String> {
     let mut sshconfig: Vec<SSHConfigConnection> = vec![];
-    let default_output = vec!["default_output".to_string()];
-    parse_from_ssh(default_output, &mut sshconfig);
-    let default_output_object = sshconfig[0].clone();
-    sshconfig.remove(0);
+    // ssh's output for a host the config does not name, so that only options the config sets are imported.
+    let default_output_object =
+        query_host("default_output").map_err(|text| format!("ssh cannot read the config: {}", text))?;
 
-    let mut ssh_config_paths: Vec<PathBuf> = vec![];
-    let mut names: Vec<String> = vec![];
-    ssh_config_paths.push(get_sshconfig_path());
-    let config = load_config(&ssh_config_paths[0]);
-    get_includes(&mut ssh_config_paths, config);
+    let config_files = config_paths();
+    let ssh_config_paths = config_files.paths;
+    let mut notes = config_files.errors;
+    let blocks = blocks::read_blocks(&config_files.parts);
+    let mut names = blocks::host_names(&blocks);
+    let mut pattern_hosts = 0;
+    for name in known_host_names() {
+        let known = names.iter().any(|known| known.eq_ignore_ascii_case(&name));
+        if !known && blocks.iter().any(|block| block.expands_to(&name)) {
+            names.push(name);
+            pattern_hosts += 1;
+        }
+    }
+
+    let failures = parse_from_ssh(names, &mut sshconfig);
+    compare_with_defaults(&mut sshconfig, default_output_object);
+    let origins = sshconfig
+        .iter()
+        .map(|connection| {
+            let mut settings = vec![
+                ("user".to_string(), connection.username.clone()),
+                ("hostname".to_string(), connection.hostname.clone()),
+                ("port".to_string(), connection.port.clone()),
+            ];
+            settings.extend(connection.options.iter().cloned());
+            blocks::origins(&blocks, &connection.server_name, &connection.hostname, &connection.username, &settings)
+        })
+        .collect();
+    let now = chrono::Local::now().timestamp();
+    let (mut connections, untranslated) = to_connections(sshconfig);
+    for connection in &mut connections {
+        connection.source = source(&blocks, &connection.server_name, now);
+    }
+    if pattern_hosts > 0 {
+        notes.push(format!("Hosts from known_hosts matching a Host or Match pattern: {}", pattern_hosts));
+    }
+    Ok(import::Imported {
+        connections,
+        notes,
+        origins,
+        failures,
+        untranslated,
+        files: ssh_config_paths.iter().map(|path| paths::abbreviate_home(path)).collect(),
+    })
+}
+
+// Include lines nested deeper than this are not followed, as in ssh.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+// The config files ssh reads, and the Include lines that could not be followed.
+#[derive(Default)]
+struct ConfigFiles {
+    paths: Vec<PathBuf>,
+    // The lines of the files in the order ssh reads them: an included file comes in at its Include line.
+    parts: Vec<ConfigPart>,
+    errors: Vec<String>,
+}
 
-    if check_systemsshconfig_path(PathBuf::from("/etc/ssh/ssh_config")) {
-        ssh_config_paths.push(PathBuf::from("/etc/ssh/ssh_config"));
-        let config = load_config(&ssh_config_paths.last().unwrap());
-        get_includes(&mut ssh_config_paths, config);
+// A run of lines of a config file without Include lines. `depth` is 0 for the user and system configs and grows
+// by one with each Include.
+pub struct ConfigPart {
+    pub path: PathBuf,
+    pub lines: std::ops::Range<usize>,
+    pub depth: usize,
+}
+
+// The user config and the system config, each followed by the files they include. Like `ssh -F`, an overridden
+// user config leaves the system config out.
+fn config_paths() -> ConfigFiles {
+    let mut files = ConfigFiles::default();
+    let user_ssh_dir = env::home_dir().unwrap_or_default().join(".ssh");
+    add_config(&get_sshconfig_path(), &user_ssh_dir, true, 0, &mut vec![], &mut files);
+    let system_config = system_config_path();
+    if paths::ssh_config_override().is_none() && system_config.is_file() {
+        let system_ssh_dir = system_config.parent().map(Path::to_path_buf).unwrap_or_default();
+        add_config(&system_config, &system_ssh_dir, false, 0, &mut vec![], &mut files);
     }
+    files
+}
 
-    if check_systemsshconfig_path(PathBuf::from("C:\\ProgramData\\ssh\\ssh_config")) {
-        ssh_config_paths.push(PathBuf::from("C:\\ProgramData\\ssh\\ssh_config"));
-        let config = load_config(&ssh_config_paths.last().unwrap());
-        get_includes(&mut ssh_config_paths, config);
+fn system_config_path() -> PathBuf {
+    if cfg!(windows) {
+        let program_data = env::var("ProgramData").unwrap_or("C:\\ProgramData".to_string());
+        PathBuf::from(program_data).join("ssh").join("ssh_config")
+    } else {
+        PathBuf::from("/etc/ssh/ssh_config")
     }
+}
 
-    for ssh_config_path in ssh_config_paths {
-        if !check_blank_sshconfig(&ssh_config_path) {
-            let config = load_config(&ssh_config_path);
-            get_names(&mut names, config);
+// Adds a config file, with the files its Include lines name in place of those lines. Relative includes are looked up in
+// `ssh_dir` (~/.ssh for user configs, /etc/ssh for the system config), and only user configs expand ~. `chain` holds
+// the file and the files including it, to stop cycles.
+fn add_config(path: &Path, ssh_dir: &Path, user: bool, depth: usize, chain: &mut Vec<PathBuf>, files: &mut ConfigFiles) {
+    let canonical = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    // A file included twice adds no hosts the second time.
+    if files.paths.iter().any(|known| fs::canonicalize(known).unwrap_or(known.clone()) == canonical) {
+        return;
+    }
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        // A missing user config is not an error: there is nothing to import yet.
+        Err(text) if depth == 0 && text.kind() == std::io::ErrorKind::NotFound => return,
+        Err(text) => {
+            files.errors.push(format!("{}: {}", paths::abbreviate_home(path), text));
+            return;
+        }
+    };
+    files.paths.push(path.to_path_buf());
+    chain.push(canonical);
+    let mut start = 0;
+    for (i, line) in data.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((keyword, value)) = line.split_once(['=', ' ', '\t']) else {
+            continue;
+        };
+        if !keyword.eq_ignore_ascii_case("include") {
+            continue;
+        }
+        files.parts.push(ConfigPart { path: path.to_path_buf(), lines: start..i, depth });
+        start = i + 1;
+        let location = format!("{}:{}", paths::abbreviate_home(path), i + 1);
+        let value = value.trim().trim_start_matches('=').trim();
+        let arguments = shlex::split(value).unwrap_or_else(|| value.split_whitespace().map(str::to_string).collect());
+        for argument in arguments {
+            if depth + 1 > MAX_INCLUDE_DEPTH {
+                files.errors.push(format!("{}: Include nested too deeply: {}", location, argument));
+                continue;
+            }
+            let expanded = match argument.as_str() {
+                "~" if user => env::home_dir().unwrap_or_default(),
+                _ if user => paths::expand_home(&argument),
+                _ => PathBuf::from(&argument),
+            };
+            let pattern = ssh_dir.join(expanded).display().to_string();
+            let matches = match glob(&pattern) {
+                Ok(matches) => matches,
+                Err(text) => {
+                    files.errors.push(format!("{}: Include pattern {} is invalid: {}", location, argument, text.msg));
+                    continue;
+                }
+            };
+            let mut found = false;
+            for entry in matches {
+                match entry {
+                    Ok(included) => {
+                        found = true;
+                        if chain.contains(&fs::canonicalize(&included).unwrap_or(included.clone())) {
+                            let included = paths::abbreviate_home(&included);
+                            files.errors.push(format!("{}: Include cycle back to {}", location, included));
+                        } else {
+                            add_config(&included, ssh_dir, user, depth + 1, chain, files);
+                        }
+                    }
+                    Err(text) => files.errors.push(format!("{}: {}", location, text)),
+                }
+            }
+            if !found {
+                files.errors.push(format!("{}: Include matches no files: {}", location, argument));
+            }
         }
     }
-    
-    parse_from_ssh(names, &mut sshconfig);
+    files.parts.push(ConfigPart { path: path.to_path_buf(), lines: start..data.lines().count(), depth });
+    chain.pop();
+}
+
+pub fn config_blocks() -> Vec<blocks::Block> {
+    blocks::read_blocks(&config_paths().parts)
+}
+
+// The Host block an alias was imported from, or the pattern block it was expanded from.
+fn source(blocks: &[blocks::Block], alias: &str, imported: i64) -> Option<import::ImportSource> {
+    let block = blocks::find_source(blocks, alias)?;
+    Some(import::ImportSource {
+        kind: import::SourceKind::SshConfig,
+        path: std::path::absolute(&block.path).unwrap_or(block.path.clone()).display().to_string(),
+        line: Some(block.line),
+        alias: alias.to_string(),
+        imported,
+        missing: false,
+    })
+}
+
+// Reads a single host of the ssh config again, with its options resolved by `ssh -G` as on import.
+pub fn resolve_host(alias: &str) -> Result<SSHConnection, String> {
+    let blocks = config_blocks();
+    let Some(source) = source(&blocks, alias, chrono::Local::now().timestamp()) else {
+        return Err(format!("The Host block of \"{}\"\nis no longer in the ssh config.", alias));
+    };
+    let default_output_object = query_host("default_output")?;
+    let mut sshconfig = vec![query_host(alias)?];
     compare_with_defaults(&mut sshconfig, default_output_object);
-    add_to_appconfig(sshconfig, app);
-    App::update_config(app);
-    app.table_state.select(Some(app.ssh_connections.len()));
-    app.scroll_state = app.scroll_state.position(app.ssh_connections.len());
+    let mut connection = to_connections(sshconfig).0.remove(0);
+    connection.source = Some(source);
+    Ok(connection)
+}
+
+// Hosts known_hosts has seen on the default port, to match against the patterns of the config.
+fn known_host_names() -> Vec<String> {
+    match known_hosts::read_known_hosts(&known_hosts::get_known_hosts_path()) {
+        Ok(known_hosts) => known_hosts
+            .connections
+            .into_iter()
+            .filter(|connection| connection.port == "22")
+            .map(|connection| connection.hostname)
+            .collect(),
+        Err(_) => vec![],
+    }
 }
 
 pub fn get_sshconfig_path() -> PathBuf {
+    if let Some(path) = paths::ssh_config_override() {
+        return path;
+    }
     let mut config_dir_pathbuf = match env::home_dir() {
         Some(path) => path,
         None => {
-            ratatui::restore();
+            restore_terminal();
             eprintln!("Error: Could not find the home directory.");
             execute!(stdout(), Show).ok();
             std::process::exit(1);
@@ -69,7 +247,7 @@ pub fn get_sshconfig_path() -> PathBuf {
     match fs::create_dir_all(&config_dir_path) {
         Ok(_) => (),
         Err(text) => {
-            ratatui::restore();
+            restore_terminal();
             eprintln!("{}: {}", config_dir_path, text);
             execute!(stdout(), Show).ok();
             std::process::exit(1);
@@ -79,109 +257,73 @@ pub fn get_sshconfig_path() -> PathBuf {
     config_dir_pathbuf
 }
 
-fn check_systemsshconfig_path(path:PathBuf) -> bool {
-    match fs::exists(&path) {
-        Ok(true)  => true,
-        Ok(false)  => false,
-        Err(_) => false
-    }
-}
-
 pub fn check_blank_sshconfig(config_path: &PathBuf) -> bool {
-    let file_data: String = fs::read_to_string(&config_path).unwrap_or_default();
-    if file_data.trim().is_empty() {
-        true
-    } else {
-        false
-    }
+    let file_data: String = fs::read_to_string(config_path).unwrap_or_default();
+    file_data.trim().is_empty()
 }
 
-fn load_config(config_path: &PathBuf) -> BufReader<File> {
-    let file_data = File::open(&config_path).unwrap();
-    let reader: BufReader<File> = BufReader::new(file_data);
-    reader
+// `ssh -G` reads the overridden config file instead of ~/.ssh/config when one is set.
+fn ssh_query(name: &str) -> Command {
+    let mut command = Command::new("ssh");
+    if let Some(path) = paths::ssh_config_override() {
+        command.arg("-F").arg(path);
+    }
+    command.arg("-G").arg(name);
+    command
 }
 
-fn get_names(names: &mut Vec<String>, config: BufReader<File>) {
-    for line_result in config.lines() {
-        match line_result {
-            Ok(line) => {
-                let line = line.split('#').next().unwrap().trim().replace("=", " ");
-                if !line.is_empty() && !line.starts_with('#') && line.to_lowercase().starts_with("host ") {
-                    let line = line.split_whitespace();
-                    for part in line {
-                        if !part.contains("*") && part.to_lowercase() != "host" {
-                            names.push(part.to_string());
-                        }
-                    }
-                }
-            }
-            Err(text) => eprintln!("Error config reading: {}", text),
+// Resolves each name with `ssh -G`; names ssh cannot resolve are returned with the reason.
+fn parse_from_ssh(names: Vec<String>, sshconfig: &mut Vec<SSHConfigConnection>) -> Vec<String> {
+    let mut failures = vec![];
+    for name in &names {
+        match query_host(name) {
+            Ok(connection) => sshconfig.push(connection),
+            Err(text) => failures.push(format!("{}: {}", name, text)),
         }
     }
+    failures
 }
 
-fn parse_from_ssh(names: Vec<String>, sshconfig: &mut Vec<SSHConfigConnection>) {
-    for name in &names {
-        let output_raw = Command::new("ssh").arg("-G").arg(name).output().unwrap();
-        let output = std::str::from_utf8(&output_raw.stdout).unwrap();
-        let error = std::str::from_utf8(&output_raw.stderr).unwrap();
-
-        if !error.contains("Cannot fork") {
-            let mut connection = SSHConfigConnection {
-                server_name: name.to_string(),
-                username: String::new(),
-                hostname: String::new(),
-                port: String::new(),
-                options: vec![],
-            };
-            for line in output.lines() {
-                if let Some((key,value)) = line.trim().split_once(' ') {
-                    let key = key.to_lowercase();
-                    let value = value.to_string();
-                    match key.as_str() {
-                        "host" => connection.server_name = value,
-                        "user" => connection.username = value,
-                        "hostname" => connection.hostname = value,
-                        "port" => connection.port = value,
-                        _ => connection.options.push((key, value))
-                    }
-                }
-            }
-            sshconfig.push(connection);
-        } else {
-            let output = Command::new("ssh").arg("-G").arg(name).arg("uptime").output().unwrap();
-            let output = std::str::from_utf8(&output.stdout).unwrap();
-            let mut connection = SSHConfigConnection {
-                server_name: name.to_string(),
-                username: String::new(),
-                hostname: String::new(),
-                port: String::new(),
-                options: vec![],
-            };
-            for line in output.lines() {
-                if let Some((key,value)) = line.trim().split_once(' ') {
-                    let key = key.to_lowercase();
-                    let value = value.to_string();
-                    match key.as_str() {
-                        "host" => connection.server_name = value,
-                        "user" => connection.username = value,
-                        "hostname" => connection.hostname = value,
-                        "port" => connection.port = value,
-                        _ => connection.options.push((key, value))
-                    }
-                }
+fn query_host(name: &str) -> Result<SSHConfigConnection, String> {
+    let run = |command: &mut Command| command.output().map_err(|text| format!("cannot run ssh: {}", text));
+    let mut output = run(&mut ssh_query(name))?;
+    if String::from_utf8_lossy(&output.stderr).contains("Cannot fork") {
+        output = run(ssh_query(name).arg("uptime"))?;
+    }
+    let text = std::str::from_utf8(&output.stdout).map_err(|_| "ssh printed invalid UTF-8".to_string())?;
+    if !output.status.success() && text.trim().is_empty() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        let reason = error.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("ssh -G failed");
+        return Err(reason.to_string());
+    }
+    let mut connection = SSHConfigConnection {
+        server_name: name.to_string(),
+        username: String::new(),
+        hostname: String::new(),
+        port: String::new(),
+        options: vec![],
+    };
+    for line in text.lines() {
+        if let Some((key,value)) = line.trim().split_once(' ') {
+            let key = key.to_lowercase();
+            let value = value.to_string();
+            match key.as_str() {
+                "host" => connection.server_name = value,
+                "user" => connection.username = value,
+                "hostname" => connection.hostname = value,
+                "port" => connection.port = value,
+                _ => connection.options.push((key, value))
             }
-            sshconfig.push(connection);
         }
     }
+    Ok(connection)
 }
 
 fn compare_with_defaults(sshconfig: &mut Vec<SSHConfigConnection>, default_output_object: SSHConfigConnection) {
     for config in sshconfig {
         let mut new_options: Vec<(String, String)> = vec![];
         for option in &config.options {
-            if !default_output_object.options.contains(&option) {
+            if !default_output_object.options.contains(option) {
                 new_options.push(option.clone());
             }
         }
@@ -189,7 +331,9 @@ fn compare_with_defaults(sshconfig: &mut Vec<SSHConfigConnection>, default_outpu
     }
 }
 
-fn get_options(option: &str, value: &str) -> String {
+// The options field is split like a shell command line, so values that would not survive that are left out and
+// returned as ssh printed them.
+fn get_options(option: &str, value: &str) -> Result<String, String> {
     let options_hashmap = HashMap::from([
         ("addkeystoagent", "AddKeysToAgent"),
         ("addressfamily", "AddressFamily"),
@@ -283,7 +427,13 @@ fn get_options(option: &str, value: &str) -> String {
         ("xauthlocation", "XAuthLocation"),
     ]);
 
-    match option {
+    let name = options_hashmap.get(option).unwrap_or(&option);
+    let command = matches!(option, "localcommand" | "proxycommand" | "remotecommand");
+    let forward = matches!(option, "localforward" | "remoteforward");
+    if (command && value.contains('\'')) || (!command && !forward && value.contains([' ', '\t', '\'', '"'])) {
+        return Err(format!("{} {}", option, value));
+    }
+    Ok(match option {
         "localforward" => {
             if !value.contains("[socks]:0") {
                 let mut line = value.split_whitespace();
@@ -308,71 +458,100 @@ fn get_options(option: &str, value: &str) -> String {
         "proxycommand" => format!("-o ProxyCommand='{}' ", value),
         "proxyjump" => format!("-J {} ", value),
         "remotecommand" => format!("-o RemoteCommand='{}' ", value),
-        _ => format!("-o {}={} ", options_hashmap.get(option).unwrap_or(&option), value)
-    }
+        _ => format!("-o {}={} ", name, value)
+    })
 }
 
-fn add_to_appconfig(sshconfig: Vec<SSHConfigConnection>, app: &mut App) {
+// Also returns the options that could not be translated, as "host: keyword value".
+fn to_connections(sshconfig: Vec<SSHConfigConnection>) -> (Vec<SSHConnection>, Vec<String>) {
+    let mut connections = vec![];
+    let mut untranslated = vec![];
     for connection in sshconfig {
         let mut all_options = String::new();
         for (key, value) in &connection.options {
-            let option = get_options(key, value);
-            all_options.push_str(&option);
+            match get_options(key, value) {
+                Ok(option) => all_options.push_str(&option),
+                Err(setting) => untranslated.push(format!("{}: {}", connection.server_name, setting)),
+            }
         }
-        let import = SSHConnection {
-            server_name: connection.server_name,
-            group_name: String::new(),
-            username: connection.username,
-            hostname: connection.hostname,
-            port: connection.port,
-            options: all_options,
-        };
-        app.ssh_connections.push(import);
+        connections.push(import::connection(
+            connection.server_name,
+            connection.username,
+            connection.hostname,
+            connection.port,
+            all_options,
+        ));
     }
+    (connections, untranslated)
 }
 
-fn get_includes(ssh_config_paths: &mut Vec<PathBuf>, config: BufReader<File>) {
-    for line_result in config.lines() {
-        match line_result {
-            Ok(line) => {
-                let homedir = env::home_dir().unwrap();
-                let line = line
-                    .trim()
-                    .replace("=", " ")
-                    .replace("~", &homedir.display().to_string());
-                if !line.is_empty() && !line.starts_with('#') && line.to_lowercase().starts_with("include ") {
-                    let line = line.split_whitespace();
-                    for part in line {
-                        if part.to_lowercase() != "include" {
-                            if Path::new(part).is_absolute() {
-                                if part.contains("*") {
-                                    for entry in glob(part).expect("Failed to read glob pattern") {
-                                        match entry {
-                                            Ok(path) => ssh_config_paths.push(PathBuf::from(path)),
-                                            Err(e) => println!("{:?}", e),
-                                        }
-                                    }
-                                } else {
-                                    ssh_config_paths.push(PathBuf::from(part))
-                                }
-                            } else if !Path::new(part).is_absolute() {
-                                let part = format!("{}/.ssh/{}", homedir.display(), part);
-                                if part.contains("*") {
-                                    for entry in glob(&part).expect("Failed to read glob pattern") {
-                                        match entry {
-                                            Ok(path) => ssh_config_paths.push(PathBuf::from(path)),
-                                            Err(e) => println!("{:?}", e),
-                                        }
-                                    }
-                                } else {
-                                    ssh_config_paths.push(PathBuf::from(part))
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Err(text) => eprintln!("Error config reading: {}", text),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_are_read_at_their_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config");
+        fs::write(
+            &config,
+            "Host web1\n\
+             \x20 User deploy\n\
+             Include prod.conf\n\
+             \x20 Port 2201\n\
+             Host *.lan\n\
+             \x20 User lan\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("prod.conf"),
+            "Compression yes\n\
+             Host *.prod web2\n\
+             \x20 User admin\n",
+        )
+        .unwrap();
+        let mut files = ConfigFiles::default();
+        add_config(&config, dir.path(), true, 0, &mut vec![], &mut files);
+        assert!(files.errors.is_empty());
+        let parts: Vec<(String, std::ops::Range<usize>, usize)> = files
+            .parts
+            .iter()
+            .map(|part| (part.path.file_name().unwrap().to_string_lossy().to_string(), part.lines.clone(), part.depth))
+            .collect();
+        assert_eq!(parts, [("config".to_string(), 0..2, 0), ("prod.conf".to_string(), 0..3, 1), ("config".to_string(), 3..6, 0)]);
+
+        let blocks = blocks::read_blocks(&files.parts);
+        let lines: Vec<usize> = blocks.iter().map(|block| block.line).collect();
+        assert_eq!(lines, [1, 2, 5]);
+        assert_eq!(blocks::host_names(&blocks), ["web1", "web2"]);
+        // Port comes after the Include, so it belongs to Host web1 again and not to the included Host *.prod.
+        let settings = [("user".to_string(), "admin".to_string()), ("port".to_string(), "2201".to_string())];
+        let origins = blocks::origins(&blocks, "db.prod", "db.prod", "admin", &settings);
+        assert_eq!(origins.len(), 1);
+        assert!(origins[0].starts_with("Host *.prod web2: User ("));
+    }
+
+    #[test]
+    fn reports_options_that_cannot_be_translated() {
+        let option = |key: &str, value: &str| (key.to_string(), value.to_string());
+        let (connections, untranslated) = to_connections(vec![SSHConfigConnection {
+            server_name: "web1".to_string(),
+            username: "deploy".to_string(),
+            hostname: "10.0.0.1".to_string(),
+            port: "22".to_string(),
+            options: vec![
+                option("identityfile", "/home/me/.ssh/web"),
+                option("proxycommand", "ssh -W %h:%p gw"),
+                option("identityfile", "/home/me/.ssh/my key"),
+                option("remotecommand", "echo 'hi'"),
+                option("serveraliveinterval", "30"),
+            ],
+        }]);
+        assert_eq!(
+            connections[0].options,
+            "-i /home/me/.ssh/web -o ProxyCommand='ssh -W %h:%p gw' -o ServerAliveInterval=30 "
+        );
+        assert!(shlex::split(&connections[0].options).is_some());
+        assert_eq!(untranslated, ["web1: identityfile /home/me/.ssh/my key", "web1: remotecommand echo 'hi'"]);
     }
 }
@@ -0,0 +1,79 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const CONFIG_ENV: &str = "SSH_LIST_CONFIG";
+pub const SETTINGS_ENV: &str = "SSH_LIST_SETTINGS";
+pub const SSH_CONFIG_ENV: &str = "SSH_LIST_SSH_CONFIG";
+pub const PROFILE_ENV: &str = "SSH_LIST_PROFILE";
+
+static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+
+// Paths given on the command line; they take precedence over the SSH_LIST_* variables.
+#[derive(Default)]
+pub struct Overrides {
+    pub config: Option<PathBuf>,
+    pub settings: Option<PathBuf>,
+    pub ssh_config: Option<PathBuf>,
+}
+
+pub fn set_overrides(overrides: Overrides) {
+    OVERRIDES.set(overrides).ok();
+}
+
+pub fn store_override() -> Option<PathBuf> {
+    override_path(|overrides| &overrides.config, CONFIG_ENV)
+}
+
+pub fn settings_override() -> Option<PathBuf> {
+    override_path(|overrides| &overrides.settings, SETTINGS_ENV)
+}
+
+pub fn ssh_config_override() -> Option<PathBuf> {
+    override_path(|overrides| &overrides.ssh_config, SSH_CONFIG_ENV)
+}
+
+fn override_path(field: fn(&Overrides) -> &Option<PathBuf>, env_var: &str) -> Option<PathBuf> {
+    choose_override(OVERRIDES.get().and_then(|overrides| field(overrides).clone()), env::var(env_var).ok())
+}
+
+fn choose_override(cli_path: Option<PathBuf>, env_value: Option<String>) -> Option<PathBuf> {
+    cli_path.or_else(|| env_value.filter(|path| !path.is_empty()).map(|path| expand_home(&path)))
+}
+
+// $XDG_CONFIG_HOME/ssh-list (~/.config/ssh-list by default) when that directory exists, otherwise ~/.ssh.
+pub fn config_dir() -> Option<PathBuf> {
+    let home = env::home_dir()?;
+    Some(config_dir_in(&home, env::var_os("XDG_CONFIG_HOME")))
+}
+
+fn config_dir_in(home: &Path, xdg_config_home: Option<OsString>) -> PathBuf {
+    let xdg_config_home = xdg_config_home
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or(home.join(".config"));
+    let xdg_dir = xdg_config_home.join("ssh-list");
+    if xdg_dir.is_dir() {
+        xdg_dir
+    } else {
+        home.join(".ssh")
+    }
+}
+
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// The reverse of expand_home, for paths shown to the user.
+pub fn abbreviate_home(path: &Path) -> String {
+    let home = env::home_dir().unwrap_or_default();
+    match path.strip_prefix(&home) {
+        Ok(rest) if !home.as_os_str().is_empty() => format!("~/{}", rest.display()),
+        _ => path.display().to_string(),
+    }
+}
+
@@ -0,0 +1,191 @@
+use crate::*;
+use serde::Deserializer;
+
+// Connections written for sharing: the fields of the store without history, trash or override state.
+#[derive(Deserialize, Serialize, Default)]
+struct Entry {
+    #[serde(default)]
+    server_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    group_name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    username: String,
+    #[serde(default)]
+    hostname: String,
+    #[serde(default, deserialize_with = "port", skip_serializing_if = "String::is_empty")]
+    port: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    options: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    notes: String,
+    // Only present in copied store files; connections in their trash are not imported.
+    #[serde(default, skip_serializing)]
+    deleted: Option<i64>,
+}
+
+// TOML has no top-level arrays, so every format wraps the list in a table.
+#[derive(Deserialize, Serialize)]
+struct Document {
+    connections: Vec<Entry>,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+// Hand-written files may have the port as a number.
+fn port<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Port {
+        Text(String),
+        Number(u64),
+    }
+    Ok(match Port::deserialize(deserializer)? {
+        Port::Text(text) => text,
+        Port::Number(number) => number.to_string(),
+    })
+}
+
+pub fn write(format: Format, connections: &[&SSHConnection]) -> Result<String, String> {
+    let document = Document {
+        connections: connections
+            .iter()
+            .map(|connection| Entry {
+                server_name: connection.server_name.clone(),
+                group_name: connection.group_name.clone(),
+                username: connection.username.clone(),
+                hostname: connection.hostname.clone(),
+                port: connection.port.clone(),
+                options: connection.options.clone(),
+                notes: connection.notes.clone(),
+                deleted: None,
+            })
+            .collect(),
+    };
+    match format {
+        Format::Json => serde_json::to_string_pretty(&document).map_err(|text| text.to_string()),
+        Format::Toml => toml::to_string(&document).map_err(|text| text.to_string()),
+        Format::Yaml => serde_yaml::to_string(&document).map_err(|text| text.to_string()),
+    }
+}
+
+// Files written by the export, and ssh-list.json stores of other machines.
+pub struct Portable;
+
+impl import::Importer for Portable {
+    fn detect(&self, path: &Path, text: &str) -> bool {
+        match import::extension(path).as_str() {
+            "json" => {
+                let text = text.trim_start();
+                (text.starts_with('{') && text.contains("\"connections\""))
+                    || (text.starts_with('[') && text.contains("\"server_name\""))
+            }
+            "toml" => true,
+            "yml" | "yaml" => text.lines().any(|line| line.trim_end() == "connections:"),
+            _ => false,
+        }
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let text = import::decode(&fs::read(path).map_err(|text| format!("{}: {}", path.display(), text))?);
+        let document = match import::extension(path).as_str() {
+            "json" if text.trim_start().starts_with('[') => {
+                serde_json::from_str(&text).map(|connections| Document { connections }).map_err(|text| text.to_string())
+            }
+            "json" => serde_json::from_str(&text).map_err(|text| text.to_string()),
+            "toml" => toml::from_str(&text).map_err(|text| text.to_string()),
+            _ => serde_yaml::from_str(&text).map_err(|text| text.to_string()),
+        };
+        let document: Document = document.map_err(|text| format!("{}: {}", path.display(), text))?;
+        let mut imported = import::Imported::default();
+        let mut without_hostname = 0;
+        for entry in document.connections {
+            if entry.deleted.is_some() {
+                continue;
+            }
+            if entry.hostname.is_empty() {
+                without_hostname += 1;
+                continue;
+            }
+            let name = if entry.server_name.is_empty() { entry.hostname.clone() } else { entry.server_name };
+            let port = if entry.port.is_empty() { "22".to_string() } else { entry.port };
+            let mut connection = import::connection(name, entry.username, entry.hostname, port, entry.options);
+            connection.group_name = entry.group_name;
+            connection.notes = entry.notes;
+            imported.connections.push(connection);
+        }
+        if without_hostname > 0 {
+            imported.notes.push(format!("Skipped connections without a hostname: {}", without_hostname));
+        }
+        Ok(imported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{Importer, fields};
+
+    fn read(file_name: &str, text: &str) -> import::Imported {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(file_name);
+        fs::write(&path, text).unwrap();
+        assert!(Portable.detect(&path, text));
+        Portable.read(&path).unwrap()
+    }
+
+    #[test]
+    fn reads_json_exports_and_stores() {
+        let imported = read(
+            "export.json",
+            r#"{"connections": [{"server_name": "web", "hostname": "10.0.0.1", "port": 2222, "username": "www"}]}"#,
+        );
+        assert_eq!(fields(&imported.connections[0]), ["web", "", "www", "10.0.0.1", "2222", ""]);
+
+        // A store of another machine, with a connection in its trash.
+        let imported = read(
+            "ssh-list.json",
+            r#"[
+                {"server_name": "db", "group_name": "prod", "hostname": "db.example.com", "port": "22", "history": [1]},
+                {"server_name": "old", "hostname": "old.example.com", "deleted": 1700000000}
+            ]"#,
+        );
+        assert_eq!(imported.connections.len(), 1);
+        assert_eq!(fields(&imported.connections[0]), ["db", "prod", "", "db.example.com", "22", ""]);
+    }
+
+    #[test]
+    fn reads_toml_with_defaults() {
+        let imported = read(
+            "export.toml",
+            "[[connections]]\nhostname = \"10.0.0.2\"\noptions = \"-A\"\n\n[[connections]]\nserver_name = \"nohost\"\n",
+        );
+        assert_eq!(fields(&imported.connections[0]), ["10.0.0.2", "", "", "10.0.0.2", "22", "-A"]);
+        assert_eq!(imported.connections.len(), 1);
+        assert_eq!(imported.notes, ["Skipped connections without a hostname: 1"]);
+    }
+
+    #[test]
+    fn reads_yaml_with_notes() {
+        let imported = read(
+            "export.yaml",
+            "connections:\n- server_name: gw\n  hostname: gw.example.com\n  port: 22\n  notes: |-\n    first\n    second\n",
+        );
+        assert_eq!(fields(&imported.connections[0]), ["gw", "", "", "gw.example.com", "22", ""]);
+        assert_eq!(imported.connections[0].notes, "first\nsecond");
+    }
+
+    #[test]
+    fn exports_read_back() {
+        let mut connection = import::connection("web".to_string(), "www".to_string(), "h".to_string(), "22".to_string(), "-A".to_string());
+        connection.group_name = "prod/eu".to_string();
+        for (format, file_name) in [(Format::Json, "out.json"), (Format::Toml, "out.toml"), (Format::Yaml, "out.yml")] {
+            let imported = read(file_name, &write(format, &[&connection]).unwrap());
+            assert_eq!(fields(&imported.connections[0]), fields(&connection), "{}", file_name);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::get_config_path;
+use crate::paths::expand_home;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::fs;
+
+pub const DEFAULT_PROFILE: &str = "default";
+// Read-only view that merges the connections of every profile.
+pub const MERGED_PROFILE: &str = "all";
+
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!(
+            "Invalid profile name \"{}\": use letters, digits, \"-\" and \"_\"",
+            name
+        ));
+    }
+    Ok(())
+}
+
+// Profiles listed in [profiles] use the configured file; others are stored next to ssh-list.json.
+pub fn store_path(name: &str, profiles: &BTreeMap<String, String>) -> PathBuf {
+    if let Some(path) = profiles.get(name) {
+        return expand_home(path);
+    }
+    let default_path = get_config_path();
+    if name == DEFAULT_PROFILE {
+        default_path
+    } else {
+        default_path.with_file_name(format!("ssh-list-{}.json", name))
+    }
+}
+
+// Whether a file is the store of a profile, also through a symlink, so that exports never write over one.
+pub fn is_store(path: &Path, profiles: &BTreeMap<String, String>) -> bool {
+    let Ok(path) = fs::canonicalize(path) else {
+        return false;
+    };
+    profile_names(profiles)
+        .iter()
+        .any(|name| fs::canonicalize(store_path(name, profiles)).is_ok_and(|store| store == path))
+}
+
+// The default profile, configured profiles and ssh-list-<name>.json files found next to ssh-list.json.
+pub fn profile_names(profiles: &BTreeMap<String, String>) -> Vec<String> {
+    let mut names: Vec<String> = profiles.keys().cloned().collect();
+    if let Some(dir) = get_config_path().parent() {
+        let entries = fs::read_dir(dir).into_iter().flatten().flatten();
+        for entry in entries {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name
+                .strip_prefix("ssh-list-")
+                .and_then(|name| name.strip_suffix(".json"));
+            if let Some(name) = name {
+                if validate_name(name).is_ok() && name != MERGED_PROFILE {
+                    names.push(name.to_string());
+                }
+            }
+        }
+    }
+    names.retain(|name| name != DEFAULT_PROFILE && name != MERGED_PROFILE);
+    names.sort();
+    names.dedup();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    names
+}
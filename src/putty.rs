@@ -0,0 +1,303 @@
+use crate::*;
+
+// Session values by name, as stored in the registry or in a session file.
+type Session = HashMap<String, String>;
+
+// A registry export (.reg) or a directory of session files (~/.putty/sessions, KiTTY's Sessions folder).
+pub struct Sessions;
+
+impl import::Importer for Sessions {
+    fn detect(&self, path: &Path, _text: &str) -> bool {
+        path.is_dir() || import::extension(path) == "reg"
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let mut sessions: Vec<(String, String, Session)> = vec![];
+        if path.is_dir() {
+            read_session_dir(path, "", &mut sessions)?;
+        } else {
+            let data = fs::read(path).map_err(|text| format!("{}: {}", path.display(), text))?;
+            read_reg(&import::decode(&data), &mut sessions);
+        }
+        Ok(to_connections(sessions))
+    }
+}
+
+fn to_connections(sessions: Vec<(String, String, Session)>) -> import::Imported {
+    let mut imported = import::Imported::default();
+    let mut other_protocols = 0;
+    for (name, folder, session) in sessions {
+        let value = |key: &str| session.get(key).map(|value| value.trim()).unwrap_or_default();
+        if name == "Default Settings" || value("HostName").is_empty() {
+            continue;
+        }
+        if !value("Protocol").is_empty() && value("Protocol") != "ssh" {
+            other_protocols += 1;
+            continue;
+        }
+        let (mut username, hostname) = match value("HostName").rsplit_once('@') {
+            Some((username, hostname)) => (username.to_string(), hostname.to_string()),
+            None => (String::new(), value("HostName").to_string()),
+        };
+        if !value("UserName").is_empty() {
+            username = value("UserName").to_string();
+        }
+        let port = if value("PortNumber").is_empty() { "22" } else { value("PortNumber") };
+        let mut options: Vec<String> = vec![];
+        let key_file = value("PublicKeyFile");
+        if key_file.to_ascii_lowercase().ends_with(".ppk") {
+            // OpenSSH cannot read PuTTY's key format.
+            imported.untranslated.push(format!(
+                "{}: PuTTY key {} (convert it with puttygen -O private-openssh, then add it with -i)",
+                name, key_file
+            ));
+        } else if !key_file.is_empty() {
+            options.push(format!("-i {}", import::quote(key_file)));
+        }
+        options.extend(value("PortForwardings").split(',').filter_map(forwarding));
+        match proxy(&session) {
+            Ok(Some(option)) => options.push(option),
+            Ok(None) => {}
+            Err(method) => imported.untranslated.push(format!("{}: {}", name, method)),
+        }
+        let mut connection =
+            import::connection(name, username, hostname, port.to_string(), options.join(" "));
+        connection.group_name = if value("Folder").is_empty() { folder } else { value("Folder").to_string() };
+        imported.connections.push(connection);
+    }
+    if other_protocols > 0 {
+        imported.notes.push(format!("Skipped sessions with other protocols than SSH: {}", other_protocols));
+    }
+    imported
+}
+
+// Keys look like [HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\<name>] (or 9bis.com\KiTTY for KiTTY).
+fn read_reg(data: &str, sessions: &mut Vec<(String, String, Session)>) {
+    let mut current: Option<usize> = None;
+    let mut lines = data.lines();
+    while let Some(line) = lines.next() {
+        let mut line = line.trim().to_string();
+        // Long binary values continue on the next lines.
+        while line.ends_with('\\') {
+            line.pop();
+            line.push_str(lines.next().unwrap_or_default().trim());
+        }
+        if let Some(key) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            current = None;
+            if let Some(start) = key.to_ascii_lowercase().find("\\sessions\\") {
+                let (folder, name) = split_folder(&percent_decode(&key[start + 10..]));
+                sessions.push((name, folder, Session::new()));
+                current = Some(sessions.len() - 1);
+            }
+        } else if let (Some(i), Some((name, value))) = (current, line.split_once("\"=")) {
+            let name = name.trim_start_matches('"').to_string();
+            let value = if let Some(value) = value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+                value.replace("\\\\", "\\").replace("\\\"", "\"")
+            } else if let Some(value) = value.strip_prefix("dword:") {
+                u32::from_str_radix(value, 16).map(|value| value.to_string()).unwrap_or_default()
+            } else {
+                continue;
+            };
+            sessions[i].2.insert(name, value);
+        }
+    }
+}
+
+// PuTTY on Unix writes "Key=Value" lines, KiTTY "Key\Value\" lines with escaped values. Subdirectories are
+// folders of sessions.
+fn read_session_dir(dir: &Path, folder: &str, sessions: &mut Vec<(String, String, Session)>) -> Result<(), String> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|text| format!("{}: {}", dir.display(), text))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+    for path in entries {
+        let file_name = percent_decode(&path.file_name().unwrap_or_default().to_string_lossy());
+        if file_name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            let folder = if folder.is_empty() { file_name } else { format!("{}/{}", folder, file_name) };
+            read_session_dir(&path, &folder, sessions)?;
+            continue;
+        }
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let mut session = Session::new();
+        for line in import::decode(&data).lines() {
+            if let Some((key, value)) = line.strip_suffix('\\').and_then(|line| line.split_once('\\')) {
+                session.insert(key.to_string(), percent_decode(value));
+            } else if let Some((key, value)) = line.split_once('=') {
+                session.insert(key.to_string(), value.to_string());
+            }
+        }
+        let (name_folder, name) = split_folder(&file_name);
+        let folder = match (folder.is_empty(), name_folder.is_empty()) {
+            (true, _) => name_folder,
+            (false, true) => folder.to_string(),
+            (false, false) => format!("{}/{}", folder, name_folder),
+        };
+        sessions.push((name, folder, session));
+    }
+    Ok(())
+}
+
+// Session managers keep folders in the session name, e.g. "Production/web-01".
+fn split_folder(name: &str) -> (String, String) {
+    match name.rsplit_once(['/', '\\']) {
+        Some((folder, name)) => (folder.to_string(), name.to_string()),
+        None => (String::new(), name.to_string()),
+    }
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+// "L8080=localhost:80", "R2222=db:22" and "D1080", optionally prefixed with 4 or 6 for the address family.
+fn forwarding(entry: &str) -> Option<String> {
+    let entry = entry.trim().trim_start_matches(['4', '6']);
+    let (kind, rest) = (entry.chars().next()?, entry.get(1..)?);
+    let (listen, destination) = rest.split_once('=').unwrap_or((rest, ""));
+    match kind {
+        'L' if !destination.is_empty() => Some(format!("-L {}:{}", listen, destination)),
+        'R' if !destination.is_empty() => Some(format!("-R {}:{}", listen, destination)),
+        'D' => Some(format!("-D {}", listen)),
+        _ => None,
+    }
+}
+
+// PuTTY keeps ProxyHost even when no proxy is used, so ProxyMethod decides. Telnet and local command proxies have no
+// ssh equivalent and are returned as errors.
+fn proxy(session: &Session) -> Result<Option<String>, &'static str> {
+    let value = |key: &str| session.get(key).map(|value| value.trim()).unwrap_or_default();
+    let host = value("ProxyHost");
+    let port = value("ProxyPort");
+    let address = if port.is_empty() { host.to_string() } else { format!("{}:{}", host, port) };
+    let netcat = |version: &str| Ok(Some(format!("-o ProxyCommand='nc -X {} -x {} %h %p'", version, address)));
+    match value("ProxyMethod") {
+        _ if host.is_empty() => Ok(None),
+        "" | "0" => Ok(None),
+        "1" => netcat("4"),
+        "2" => netcat("5"),
+        "3" => netcat("connect"),
+        "4" => Err("telnet proxy"),
+        "5" => Err("local proxy command"),
+        "6" if value("ProxyUsername").is_empty() => Ok(Some(format!("-J {}", address))),
+        "6" => Ok(Some(format!("-J {}@{}", value("ProxyUsername"), address))),
+        _ => Err("unknown proxy type"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{Importer, fields};
+
+    // regedit exports are UTF-16 with a byte order mark.
+    const REG_EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Production%2Fweb-01]
+"HostName"="admin@web01.example.com"
+"PortNumber"=dword:00000a28
+"Protocol"="ssh"
+"PublicKeyFile"="/home/me/.ssh/id_ed25519"
+"PortForwardings"="L8080=localhost:80,D1080"
+"ProxyMethod"=dword:00000000
+"ProxyHost"="proxy"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\db]
+"HostName"="db.example.com"
+"UserName"="postgres"
+"Protocol"="ssh"
+"PublicKeyFile"="C:\\Users\\me\\db key.ppk"
+"ProxyMethod"=dword:00000005
+"ProxyHost"="jump"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\router]
+"HostName"="192.168.1.1"
+"Protocol"="telnet"
+"#;
+
+    #[test]
+    fn reads_a_utf16_registry_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("putty.reg");
+        let mut data = vec![0xFF, 0xFE];
+        data.extend(REG_EXPORT.encode_utf16().flat_map(u16::to_le_bytes));
+        fs::write(&path, data).unwrap();
+        assert!(Sessions.detect(&path, ""));
+
+        let imported = Sessions.read(&path).unwrap();
+        assert_eq!(imported.connections.len(), 2);
+        assert_eq!(
+            fields(&imported.connections[0]),
+            [
+                "web-01",
+                "Production",
+                "admin",
+                "web01.example.com",
+                "2600",
+                "-i /home/me/.ssh/id_ed25519 -L 8080:localhost:80 -D 1080"
+            ]
+        );
+        assert_eq!(fields(&imported.connections[1]), ["db", "", "postgres", "db.example.com", "22", ""]);
+        assert_eq!(
+            imported.untranslated,
+            [
+                r"db: PuTTY key C:\Users\me\db key.ppk (convert it with puttygen -O private-openssh, then add it with -i)",
+                "db: local proxy command"
+            ]
+        );
+        assert_eq!(imported.notes, ["Skipped sessions with other protocols than SSH: 1"]);
+    }
+
+    #[test]
+    fn reads_putty_and_kitty_session_folders() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("Staging")).unwrap();
+        fs::write(
+            dir.path().join("web%2001"),
+            "HostName=web.example.com\nPortNumber=2222\nUserName=deploy\nProtocol=ssh\nProxyMethod=6\nProxyHost=bastion\nProxyPort=22\nProxyUsername=jump\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("Staging/api"),
+            "HostName\\api.example.com\\\nProtocol\\ssh\\\nPortForwardings\\R2222=db:22\\\nPublicKeyFile\\/keys/api%20key\\\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join(".hidden"), "HostName=hidden\n").unwrap();
+        assert!(Sessions.detect(dir.path(), ""));
+
+        let imported = Sessions.read(dir.path()).unwrap();
+        let connections: Vec<[&str; 6]> = imported.connections.iter().map(fields).collect();
+        assert_eq!(
+            connections,
+            [
+                ["api", "Staging", "", "api.example.com", "22", "-i '/keys/api key' -R 2222:db:22"],
+                ["web 01", "", "deploy", "web.example.com", "2222", "-J jump@bastion:22"],
+            ]
+        );
+        assert!(imported.untranslated.is_empty());
+    }
+}
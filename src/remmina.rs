@@ -0,0 +1,160 @@
+use crate::*;
+
+// A .remmina file, or a directory of them such as ~/.local/share/remmina.
+pub struct Remmina;
+
+impl import::Importer for Remmina {
+    fn detect(&self, path: &Path, _text: &str) -> bool {
+        if path.is_dir() {
+            return remmina_files(path).is_ok_and(|files| !files.is_empty());
+        }
+        import::extension(path) == "remmina"
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let files = if path.is_dir() { remmina_files(path)? } else { vec![path.to_path_buf()] };
+        let mut imported = import::Imported {
+            files: files.iter().map(|file| paths::abbreviate_home(file)).collect(),
+            ..Default::default()
+        };
+        let mut other_protocols = 0;
+        for file in files {
+            let data = fs::read(&file).map_err(|text| format!("{}: {}", file.display(), text))?;
+            let profile = read_profile(&import::decode(&data));
+            let value = |key: &str| profile.get(key).map(|value| value.trim()).unwrap_or_default();
+            if !value("protocol").eq_ignore_ascii_case("ssh") {
+                other_protocols += 1;
+                continue;
+            }
+            // Older versions prefix the ssh settings with "ssh_".
+            let server = if value("server").is_empty() { value("ssh_server") } else { value("server") };
+            let username = if value("username").is_empty() { value("ssh_username") } else { value("username") };
+            let (hostname, port) = split_server(server);
+            if hostname.is_empty() {
+                continue;
+            }
+            let mut options = vec![];
+            // Authentication 1 is an identity file; the others use the password, the agent or kerberos.
+            if !value("ssh_privatekey").is_empty() && matches!(value("ssh_auth"), "" | "1") {
+                options.push(format!("-i {}", import::quote(value("ssh_privatekey"))));
+            }
+            if !value("ssh_proxycommand").is_empty() {
+                options.push(format!("-o ProxyCommand={}", import::quote(value("ssh_proxycommand"))));
+            }
+            if value("ssh_tunnel_enabled") == "1" && !value("ssh_tunnel_server").is_empty() {
+                let (jump_host, jump_port) = split_server(value("ssh_tunnel_server"));
+                let user = value("ssh_tunnel_username");
+                let user = if user.is_empty() { String::new() } else { format!("{}@", user) };
+                options.push(format!("-J {}{}:{}", user, jump_host, jump_port));
+            }
+            let name = if value("name").is_empty() { hostname.clone() } else { value("name").to_string() };
+            let mut connection = import::connection(name, username.to_string(), hostname, port, options.join(" "));
+            connection.group_name = value("group").to_string();
+            imported.connections.push(connection);
+        }
+        if other_protocols > 0 {
+            imported.notes.push(format!("Skipped profiles with other protocols than SSH: {}", other_protocols));
+        }
+        Ok(imported)
+    }
+}
+
+fn remmina_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|text| format!("{}: {}", dir.display(), text))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && import::extension(path) == "remmina")
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// Profiles are key files with a single [remmina] group.
+fn read_profile(text: &str) -> HashMap<String, String> {
+    let mut profile = HashMap::new();
+    let mut in_group = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(group) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            in_group = group == "remmina";
+        } else if let (true, Some((key, value))) = (in_group, line.split_once('=')) {
+            profile.insert(key.trim().to_string(), value.to_string());
+        }
+    }
+    profile
+}
+
+// "host", "host:2222" or "[2001:db8::1]:2222".
+fn split_server(server: &str) -> (String, String) {
+    let (host, port) = match server.strip_prefix('[').and_then(|server| server.split_once(']')) {
+        Some((host, rest)) => (host, rest.strip_prefix(':').unwrap_or_default()),
+        None => match server.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, port),
+            _ => (server, ""),
+        },
+    };
+    (host.to_string(), if port.is_empty() { "22" } else { port }.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{Importer, fields};
+
+    const ROUTER: &str = "[remmina]\n\
+                          name=Router\n\
+                          group=Net/Core\n\
+                          protocol=SSH\n\
+                          server=[2001:db8::1]:2022\n\
+                          username=admin\n\
+                          ssh_auth=1\n\
+                          ssh_privatekey=/home/me/.ssh/id ed\n";
+
+    #[test]
+    fn reads_an_ssh_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("router.remmina");
+        fs::write(&path, ROUTER).unwrap();
+        assert!(Remmina.detect(&path, ""));
+
+        let imported = Remmina.read(&path).unwrap();
+        assert_eq!(imported.connections.len(), 1);
+        assert_eq!(
+            fields(&imported.connections[0]),
+            ["Router", "Net/Core", "admin", "2001:db8::1", "2022", "-i '/home/me/.ssh/id ed'"]
+        );
+        assert!(imported.notes.is_empty());
+    }
+
+    #[test]
+    fn reads_a_directory_of_profiles() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("1-router.remmina"), ROUTER).unwrap();
+        fs::write(dir.path().join("2-desk.remmina"), "[remmina]\nname=Desk\nprotocol=RDP\nserver=desk:3389\n").unwrap();
+        fs::write(
+            dir.path().join("3-inner.remmina"),
+            "[remmina]\n\
+             protocol=SSH\n\
+             ssh_server=inner.lan\n\
+             ssh_username=bob\n\
+             ssh_auth=2\n\
+             ssh_privatekey=/home/me/.ssh/unused\n\
+             ssh_tunnel_enabled=1\n\
+             ssh_tunnel_server=gw.example.com:2222\n\
+             ssh_tunnel_username=jump\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a profile").unwrap();
+        assert!(Remmina.detect(dir.path(), ""));
+
+        let imported = Remmina.read(dir.path()).unwrap();
+        assert_eq!(imported.files.len(), 3);
+        assert_eq!(imported.connections.len(), 2);
+        assert_eq!(fields(&imported.connections[0])[..5], ["Router", "Net/Core", "admin", "2001:db8::1", "2022"]);
+        assert_eq!(
+            fields(&imported.connections[1]),
+            ["inner.lan", "", "bob", "inner.lan", "22", "-J jump@gw.example.com:2222"]
+        );
+        assert_eq!(imported.notes, ["Skipped profiles with other protocols than SSH: 1"]);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::columns::Column;
+use crate::SSHConnection;
+use std::cmp::Ordering;
+
+const SORT_KEY_LIMIT: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub column: Column,
+    pub descending: bool,
+}
+
+impl SortKey {
+    // "name" sorts ascending, "-name" descending.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (descending, key) = match value.trim().strip_prefix('-') {
+            Some(key) => (true, key),
+            None => (false, value.trim()),
+        };
+        Some(Self {
+            column: Column::from_key(key)?,
+            descending,
+        })
+    }
+
+    pub fn to_config(self) -> String {
+        if self.descending {
+            format!("-{}", self.column.key())
+        } else {
+            self.column.key().to_string()
+        }
+    }
+}
+
+pub fn keys_from_config(config: &[String]) -> Vec<SortKey> {
+    let mut keys: Vec<SortKey> = vec![];
+    for key in config.iter().filter_map(|value| SortKey::parse(value)) {
+        if !keys.iter().any(|k| k.column == key.column) {
+            keys.push(key);
+        }
+    }
+    keys.truncate(SORT_KEY_LIMIT);
+    keys
+}
+
+// Makes the column the primary key; choosing the current primary key again reverses its direction.
+pub fn push_key(keys: &mut Vec<SortKey>, column: Column) {
+    match keys.first_mut() {
+        Some(key) if key.column == column => key.descending = !key.descending,
+        _ => {
+            keys.retain(|key| key.column != column);
+            keys.insert(
+                0,
+                SortKey {
+                    column,
+                    descending: false,
+                },
+            );
+            keys.truncate(SORT_KEY_LIMIT);
+        }
+    }
+}
+
+pub fn compare(a: &SSHConnection, b: &SSHConnection, keys: &[SortKey]) -> Ordering {
+    for key in keys {
+        let ordering = compare_column(a, b, key.column);
+        let ordering = if key.descending { ordering.reverse() } else { ordering };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+fn compare_column(a: &SSHConnection, b: &SSHConnection, column: Column) -> Ordering {
+    match column {
+        Column::Port => a.port.parse::<u16>().unwrap_or_default().cmp(&b.port.parse::<u16>().unwrap_or_default()),
+        Column::LastUsed => a.history.last().cmp(&b.history.last()),
+        _ => natural_cmp(&column.value(a), &column.value(b)),
+    }
+}
+
+// Case-insensitive comparison that orders digit runs by their numeric value, so "web2" < "web10".
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let a = a.to_lowercase();
+    let b = b.to_lowercase();
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a_chars);
+                let y = take_number(&mut b_chars);
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+// Returns the digit run without leading zeros.
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        if !(number.is_empty() && c == '0') {
+            number.push(c);
+        }
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keys: &[SortKey]) -> Vec<String> {
+        keys.iter().map(|key| key.to_config()).collect()
+    }
+
+    #[test]
+    fn orders_numbers_by_value() {
+        assert_eq!(natural_cmp("web2", "web10"), Ordering::Less);
+        assert_eq!(natural_cmp("web10", "web9"), Ordering::Greater);
+        assert_eq!(natural_cmp("Web2", "web2"), Ordering::Equal);
+        assert_eq!(natural_cmp("web02", "web2"), Ordering::Equal);
+        assert_eq!(natural_cmp("db", "db1"), Ordering::Less);
+        assert_eq!(natural_cmp("10.0.0.9", "10.0.0.10"), Ordering::Less);
+        assert_eq!(natural_cmp("a10b", "a10c"), Ordering::Less);
+    }
+
+    #[test]
+    fn parses_directions_and_limits_the_keys() {
+        let key = SortKey::parse("-name").unwrap();
+        assert!(key.column == Column::Name && key.descending);
+        let key = SortKey::parse(" port ").unwrap();
+        assert!(key.column == Column::Port && !key.descending);
+        assert!(SortKey::parse("-nope").is_none());
+
+        let keys = keys_from_config(&["-name", "port", "name", "bogus", "group", "hostname"].map(str::to_string));
+        assert_eq!(config(&keys), ["-name", "port", "group"]);
+    }
+
+    #[test]
+    fn pushed_keys_become_primary() {
+        let mut keys = vec![];
+        push_key(&mut keys, Column::Name);
+        push_key(&mut keys, Column::Port);
+        push_key(&mut keys, Column::Group);
+        assert_eq!(config(&keys), ["group", "port", "name"]);
+        // Choosing the primary key again reverses it, and a secondary key moves to the front ascending.
+        push_key(&mut keys, Column::Group);
+        assert_eq!(config(&keys), ["-group", "port", "name"]);
+        push_key(&mut keys, Column::Name);
+        assert_eq!(config(&keys), ["name", "-group", "port"]);
+        push_key(&mut keys, Column::Hostname);
+        assert_eq!(config(&keys), ["hostname", "name", "-group"]);
+    }
+}
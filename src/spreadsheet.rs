@@ -0,0 +1,255 @@
+use crate::*;
+
+// Connection fields a CSV column can be mapped to, in the column order of exported files.
+pub const FIELDS: [&str; 7] = ["name", "group", "username", "hostname", "port", "options", "notes"];
+
+// Header names recognized for each field, compared without case, spaces, dashes and underscores.
+const ALIASES: [&[&str]; 7] = [
+    &["name", "servername", "alias", "label", "title", "connection"],
+    &["group", "groupname", "folder", "category", "environment"],
+    &["username", "user", "login", "account"],
+    &["hostname", "host", "address", "ip", "ipaddress", "fqdn", "server"],
+    &["port", "sshport"],
+    &["options", "sshoptions", "arguments", "args"],
+    &["notes", "note", "description", "comment", "comments"],
+];
+
+// A CSV file waiting for its columns to be mapped onto connection fields.
+pub struct CsvImport {
+    pub path: PathBuf,
+    // Records with their line numbers, or the reason a line could not be read.
+    rows: Vec<(u64, Result<Vec<String>, String>)>,
+    pub has_header: bool,
+    // Column feeding each of FIELDS.
+    pub mapping: [Option<usize>; 7],
+}
+
+impl CsvImport {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let mut data = fs::read(path).map_err(|text| format!("{}: {}", path.display(), text))?;
+        // Other encodings than UTF-8 and UTF-16 are reported line by line.
+        if data.starts_with(b"\xFF\xFE") || data.starts_with(b"\xFE\xFF") {
+            data = import::decode(&data).into_bytes();
+        }
+        let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&data);
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(guess_delimiter(data))
+            .from_reader(data);
+        let mut rows = vec![];
+        for (i, record) in reader.byte_records().enumerate() {
+            let line = i as u64 + 1;
+            let row = match record {
+                Ok(record) => {
+                    let line = record.position().map_or(line, |position| position.line());
+                    let fields: Result<Vec<String>, _> = record
+                        .iter()
+                        .map(|field| String::from_utf8(field.to_vec()).map(|field| field.trim().to_string()))
+                        .collect();
+                    (line, fields.map_err(|_| "not valid UTF-8 text (save the file as UTF-8)".to_string()))
+                }
+                Err(error) => (error.position().map_or(line, |position| position.line()), Err(error.to_string())),
+            };
+            if matches!(&row.1, Ok(fields) if fields.iter().all(|field| field.is_empty())) {
+                continue;
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(format!("{} is empty.", path.display()));
+        }
+        let mut import = CsvImport {
+            path: path.to_path_buf(),
+            rows,
+            has_header: false,
+            mapping: [None; 7],
+        };
+        import.has_header = import.header().iter().any(|name| field_for(name).is_some());
+        import.guess_mapping();
+        Ok(import)
+    }
+
+    fn header(&self) -> Vec<String> {
+        match self.rows.first() {
+            Some((_, Ok(fields))) => fields.clone(),
+            _ => vec![],
+        }
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.rows.iter().filter_map(|(_, row)| row.as_ref().ok()).map(Vec::len).max().unwrap_or(0)
+    }
+
+    // Header name of a column, or its number in files without a header.
+    pub fn column_name(&self, column: usize) -> String {
+        match self.header().get(column) {
+            Some(name) if self.has_header && !name.is_empty() => name.clone(),
+            _ => format!("column {}", column + 1),
+        }
+    }
+
+    fn data_rows(&self) -> &[(u64, Result<Vec<String>, String>)] {
+        if self.has_header { self.rows.get(1..).unwrap_or_default() } else { &self.rows }
+    }
+
+    // Value of a column in the first data row, shown as an example while mapping.
+    pub fn example(&self, column: usize) -> String {
+        self.data_rows()
+            .iter()
+            .find_map(|(_, row)| row.as_ref().ok())
+            .and_then(|fields| fields.get(column).cloned())
+            .unwrap_or_default()
+    }
+
+    pub fn toggle_header(&mut self) {
+        self.has_header = !self.has_header;
+        self.guess_mapping();
+    }
+
+    // Columns are matched by header name; without a header they are taken in the order of exported files.
+    fn guess_mapping(&mut self) {
+        let columns = self.column_count();
+        self.mapping = [None; 7];
+        if self.has_header {
+            for (column, name) in self.header().iter().enumerate() {
+                if let Some(field) = field_for(name).filter(|&field| self.mapping[field].is_none()) {
+                    self.mapping[field] = Some(column);
+                }
+            }
+        } else {
+            for field in 0..FIELDS.len().min(columns) {
+                self.mapping[field] = Some(field);
+            }
+        }
+    }
+
+    // Steps a field through the columns and "not imported".
+    pub fn cycle(&mut self, field: usize, forward: bool) {
+        let columns = self.column_count();
+        let choices = columns + 1;
+        let current = self.mapping[field].unwrap_or(columns);
+        let next = if forward { (current + 1) % choices } else { (current + choices - 1) % choices };
+        self.mapping[field] = (next < columns).then_some(next);
+    }
+
+    // Rows without a hostname or with an invalid port are reported and left out.
+    pub fn connections(&self) -> import::Imported {
+        let mut imported = import::Imported::default();
+        let mut errors = vec![];
+        for (line, row) in self.data_rows() {
+            let fields = match row {
+                Ok(fields) => fields,
+                Err(text) => {
+                    errors.push(format!("line {}: {}", line, text));
+                    continue;
+                }
+            };
+            let value = |field: usize| {
+                self.mapping[field].and_then(|column| fields.get(column)).cloned().unwrap_or_default()
+            };
+            let hostname = value(3);
+            if hostname.is_empty() {
+                errors.push(format!("line {}: no hostname", line));
+                continue;
+            }
+            let port = if value(4).is_empty() { "22".to_string() } else { value(4) };
+            if port.parse::<u16>().is_err() {
+                errors.push(format!("line {}: invalid port \"{}\"", line, port));
+                continue;
+            }
+            let name = if value(0).is_empty() { hostname.clone() } else { value(0) };
+            let mut connection = import::connection(name, value(2), hostname, port, value(5));
+            connection.group_name = value(1);
+            connection.notes = value(6);
+            imported.connections.push(connection);
+        }
+        imported.failures = errors;
+        imported
+    }
+}
+
+fn field_for(name: &str) -> Option<usize> {
+    let name: String = name
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect();
+    ALIASES.iter().position(|aliases| aliases.contains(&name.as_str()))
+}
+
+// Spreadsheets save CSV with semicolons in locales using the decimal comma, and some export tab-separated files.
+fn guess_delimiter(data: &[u8]) -> u8 {
+    let first_line = data.split(|&byte| byte == b'\n').next().unwrap_or_default();
+    [b'\t', b';', b',']
+        .into_iter()
+        .max_by_key(|&delimiter| first_line.iter().filter(|&&byte| byte == delimiter).count())
+        .unwrap_or(b',')
+}
+
+pub fn write_csv(path: &Path, connections: &[&SSHConnection]) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(FIELDS)?;
+    for connection in connections {
+        writer.write_record([
+            &connection.server_name,
+            &connection.group_name,
+            &connection.username,
+            &connection.hostname,
+            &connection.port,
+            &connection.options,
+            &connection.notes,
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::fields;
+
+    fn read(data: &[u8]) -> CsvImport {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hosts.csv");
+        fs::write(&path, data).unwrap();
+        CsvImport::read(&path).unwrap()
+    }
+
+    #[test]
+    fn quoted_fields_keep_the_delimiter() {
+        let imported = read(b"name,host,notes\n\"web, primary\",10.0.0.1,\"a, b\"\n").connections();
+        assert_eq!(fields(&imported.connections[0]), ["web, primary", "", "", "10.0.0.1", "22", ""]);
+        assert_eq!(imported.connections[0].notes, "a, b");
+    }
+
+    #[test]
+    fn reads_utf16_with_a_byte_order_mark() {
+        let text = "name;host;user\nсервер;db.example.com;админ\n";
+        let mut data = b"\xFF\xFE".to_vec();
+        data.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        let imported = read(&data).connections();
+        assert_eq!(fields(&imported.connections[0]), ["сервер", "", "админ", "db.example.com", "22", ""]);
+    }
+
+    #[test]
+    fn bad_rows_are_reported_with_their_line() {
+        let imported = read(b"name,host,port\nweb1,10.0.0.1,22\nweb2,,22\nweb3,10.0.0.3,ssh\nweb4,10.0.0.4,70000\n")
+            .connections();
+        assert_eq!(imported.connections.len(), 1);
+        assert_eq!(
+            imported.failures,
+            ["line 3: no hostname", "line 4: invalid port \"ssh\"", "line 5: invalid port \"70000\""]
+        );
+    }
+
+    #[test]
+    fn files_without_a_header_use_the_export_order() {
+        let mut csv_import = read(b"web1,prod,root,10.0.0.1,2222\n");
+        assert!(!csv_import.has_header);
+        assert_eq!(fields(&csv_import.connections().connections[0]), ["web1", "prod", "root", "10.0.0.1", "2222", ""]);
+        csv_import.toggle_header();
+        assert!(csv_import.connections().connections.is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Modification time and size, enough to notice that another program rewrote the file.
+pub type FileStamp = Option<(SystemTime, u64)>;
+
+pub fn stamp(path: &Path) -> FileStamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+// Held while the store is read, merged and written; other instances wait for it. The lock file sits next to the
+// store because the store itself is replaced on every write.
+pub fn lock(path: &Path) -> io::Result<File> {
+    let path: PathBuf = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let mut lock_name = path.file_name().unwrap_or_default().to_os_string();
+    lock_name.push(".lock");
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_file_name(lock_name))?;
+    file.lock()?;
+    Ok(file)
+}
+
+// Writes a temporary file and renames it over the store, so readers never see a half-written file.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    // Replace the target of a symlinked store rather than the link.
+    let path: PathBuf = fs::canonicalize(path).unwrap_or(path.to_path_buf());
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, contents)?;
+    if let Ok(metadata) = fs::metadata(&path) {
+        fs::set_permissions(&temp_path, metadata.permissions()).ok();
+    }
+    fs::rename(&temp_path, &path).inspect_err(|_| {
+        fs::remove_file(&temp_path).ok();
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn second_writer_waits_for_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ssh-list.json");
+        let first = lock(&path).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let second_path = path.clone();
+        let second = thread::spawn(move || {
+            let _lock = lock(&second_path).unwrap();
+            sender.send(()).unwrap();
+        });
+        assert!(receiver.recv_timeout(Duration::from_millis(200)).is_err());
+        drop(first);
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        second.join().unwrap();
+    }
+
+    #[test]
+    fn writers_under_the_lock_keep_each_others_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ssh-list.json");
+        write(&path, b"").unwrap();
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let _lock = lock(&path).unwrap();
+                    let mut names = fs::read_to_string(&path).unwrap();
+                    thread::sleep(Duration::from_millis(10));
+                    names.push_str(&format!("writer{}\n", i));
+                    write(&path, names.as_bytes()).unwrap();
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let names = fs::read_to_string(&path).unwrap();
+        assert_eq!(names.lines().count(), 8);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_replaces_a_symlinked_store_through_the_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.json");
+        let link = dir.path().join("ssh-list.json");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        write(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
+}
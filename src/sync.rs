@@ -0,0 +1,722 @@
+use crate::{crypt, store, SSHConnection, HISTORY_LIMIT};
+use age::secrecy::SecretString;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+// More names than this are summarized as a count in commit messages.
+const LISTED_NAMES: usize = 3;
+
+// A git repository containing a connection store. Commands run in the store directory and only touch the store file.
+pub struct GitRepo {
+    dir: PathBuf,
+    file: String,
+    // Commits and merges still need an author when git has no identity configured.
+    identity: Vec<&'static str>,
+    // Needed to merge an encrypted store.
+    pub passphrase: Option<SecretString>,
+}
+
+pub enum PullResult {
+    UpToDate,
+    Updated,
+    Conflict(Merge),
+}
+
+// Result of a three-way merge of the local and remote store, waiting for conflicts to be resolved.
+pub struct Merge {
+    pub entries: Vec<MergeEntry>,
+    pub upstream: String,
+    theirs: String,
+}
+
+pub enum MergeEntry {
+    Resolved(Box<SSHConnection>),
+    // A missing side means the connection was removed there. The local side is kept unless chosen otherwise.
+    Conflict {
+        local: Option<Box<SSHConnection>>,
+        remote: Option<Box<SSHConnection>>,
+        keep_local: bool,
+    },
+}
+
+impl Merge {
+    // Both sides of every conflict and whether the local one is kept.
+    pub fn conflicts(&self) -> impl Iterator<Item = (Option<&SSHConnection>, Option<&SSHConnection>, bool)> {
+        self.entries.iter().filter_map(|entry| match entry {
+            MergeEntry::Conflict {
+                local,
+                remote,
+                keep_local,
+            } => Some((local.as_deref(), remote.as_deref(), *keep_local)),
+            MergeEntry::Resolved(_) => None,
+        })
+    }
+
+    // Chooses the side of the n-th conflict.
+    pub fn choose(&mut self, conflict: usize, local: bool) {
+        let entry = self
+            .entries
+            .iter_mut()
+            .filter(|entry| matches!(entry, MergeEntry::Conflict { .. }))
+            .nth(conflict);
+        if let Some(MergeEntry::Conflict { keep_local, .. }) = entry {
+            *keep_local = local;
+        }
+    }
+
+    pub fn resolve(&self) -> Vec<SSHConnection> {
+        resolve(&self.entries)
+    }
+}
+
+// Takes the chosen side of every conflict.
+pub fn resolve(entries: &[MergeEntry]) -> Vec<SSHConnection> {
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            MergeEntry::Resolved(connection) => Some(*connection.clone()),
+            MergeEntry::Conflict {
+                local,
+                remote,
+                keep_local,
+            } => {
+                if *keep_local {
+                    local.as_deref().cloned()
+                } else {
+                    remote.as_deref().cloned()
+                }
+            }
+        })
+        .collect()
+}
+
+impl GitRepo {
+    pub fn open(store_path: &Path) -> Result<Self, String> {
+        let dir = store_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        let file = store_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut repo = GitRepo {
+            dir,
+            file,
+            identity: vec![],
+            passphrase: None,
+        };
+        if repo.git(&["rev-parse", "--git-dir"]).is_err() {
+            return Err(format!(
+                "{} is not a git repository.\nRun \"git init\" there to use git sync.",
+                repo.dir.display()
+            ));
+        }
+        if repo.git(&["config", "user.email"]).is_err() {
+            repo.identity = vec!["-c", "user.name=ssh-list", "-c", "user.email=ssh-list@localhost"];
+        }
+        Ok(repo)
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String, String> {
+        let output = self.git_bytes(args)?;
+        Ok(String::from_utf8_lossy(&output).trim_end().to_string())
+    }
+
+    fn git_bytes(&self, args: &[&str]) -> Result<Vec<u8>, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.dir)
+            .args(&self.identity)
+            .args(args)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|text| format!("Could not run git: {}", text))?;
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let text = stderr
+            .lines()
+            .map(|line| line.trim_start_matches("error: ").trim_start_matches("fatal: ").trim())
+            .find(|line| !line.is_empty() && !line.starts_with("hint:"))
+            .unwrap_or("git failed")
+            .to_string();
+        Err(text)
+    }
+
+    // Commits the store file alone; returns false when it has no changes.
+    pub fn commit(&self, message: &str) -> Result<bool, String> {
+        if !self.dir.join(&self.file).exists() {
+            return Ok(false);
+        }
+        self.git(&["add", "--", &self.file])?;
+        if self.git(&["diff", "--cached", "--quiet", "--", &self.file]).is_ok() {
+            return Ok(false);
+        }
+        self.git(&["commit", "-q", "-m", message, "--", &self.file])?;
+        Ok(true)
+    }
+
+    // Points "origin" at the configured remote, adding it when missing.
+    pub fn set_remote(&self, url: &str) -> Result<(), String> {
+        match self.git(&["remote", "get-url", "origin"]) {
+            Ok(current) if current == url => Ok(()),
+            Ok(_) => self.git(&["remote", "set-url", "origin", url]).map(|_| ()),
+            Err(_) => self.git(&["remote", "add", "origin", url]).map(|_| ()),
+        }
+    }
+
+    fn branch(&self) -> Result<String, String> {
+        self.git(&["symbolic-ref", "--short", "HEAD"])
+    }
+
+    fn upstream(&self) -> Option<String> {
+        self.git(&["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"]).ok()
+    }
+
+    fn has_origin(&self) -> bool {
+        self.git(&["remote", "get-url", "origin"]).is_ok()
+    }
+
+    pub fn status(&self) -> Vec<String> {
+        let mut lines = vec![];
+        if let Ok(top) = self.git(&["rev-parse", "--show-toplevel"]) {
+            lines.push(format!("Repository: {}", top));
+        }
+        let branch = self.branch().unwrap_or("(detached)".to_string());
+        lines.push(match self.upstream() {
+            Some(upstream) => {
+                let counts = self.git(&["rev-list", "--left-right", "--count", "HEAD...@{u}"]).unwrap_or_default();
+                let mut counts = counts.split_whitespace();
+                let ahead = counts.next().unwrap_or("0");
+                let behind = counts.next().unwrap_or("0");
+                format!("Branch: {} → {} ({} ahead, {} behind)", branch, upstream, ahead, behind)
+            }
+            None if self.has_origin() => format!("Branch: {} (not pushed to origin yet)", branch),
+            None => format!("Branch: {} (no remote)", branch),
+        });
+        match self.git(&["log", "-1", "--format=%s (%cr)", "--", &self.file]) {
+            Ok(last) if !last.is_empty() => lines.push(format!("Last commit: {}", last)),
+            _ => lines.push("Last commit: none".to_string()),
+        }
+        lines
+    }
+
+    pub fn push(&self) -> Result<String, String> {
+        self.commit("save connections")?;
+        match self.upstream() {
+            Some(upstream) => {
+                self.git(&["push", "-q"])?;
+                Ok(format!("Pushed to {}", upstream))
+            }
+            None if self.has_origin() => {
+                self.git(&["push", "-q", "-u", "origin", "HEAD"])?;
+                Ok(format!("Pushed to origin/{}", self.branch()?))
+            }
+            None => Err("No remote configured. Set git_remote in the settings.".to_string()),
+        }
+    }
+
+    // Fetches the upstream branch and merges it connection by connection; textual merges of the JSON are never used.
+    pub fn pull(&self) -> Result<PullResult, String> {
+        self.commit("save connections")?;
+        let upstream = match self.upstream() {
+            Some(upstream) => upstream,
+            None if self.has_origin() => {
+                let branch = self.branch()?;
+                self.git(&["fetch", "-q", "origin"])?;
+                let upstream = format!("origin/{}", branch);
+                if self.git(&["rev-parse", "-q", "--verify", &upstream]).is_err() {
+                    return Err(format!("Nothing to pull: {} does not exist yet.", upstream));
+                }
+                self.git(&["branch", "-q", "--set-upstream-to", &upstream])?;
+                upstream
+            }
+            None => return Err("No remote configured. Set git_remote in the settings.".to_string()),
+        };
+        let remote = upstream.split_once('/').map_or("origin", |(remote, _)| remote);
+        self.git(&["fetch", "-q", remote])?;
+        let theirs = self.git(&["rev-parse", "@{u}"])?;
+        let Ok(head) = self.git(&["rev-parse", "-q", "--verify", "HEAD"]) else {
+            // Nothing committed locally yet.
+            self.git(&["merge", "-q", "--ff-only", &theirs])?;
+            return Ok(PullResult::Updated);
+        };
+        if self.git(&["merge-base", "--is-ancestor", &theirs, &head]).is_ok() {
+            return Ok(PullResult::UpToDate);
+        }
+        if self.git(&["merge-base", "--is-ancestor", &head, &theirs]).is_ok() {
+            self.git(&["merge", "-q", "--ff-only", &theirs])?;
+            return Ok(PullResult::Updated);
+        }
+        let base = self.git(&["merge-base", &head, &theirs])?;
+        let merge = Merge {
+            entries: merge(&self.show(&base)?, &self.show(&head)?, &self.show(&theirs)?),
+            upstream,
+            theirs,
+        };
+        if merge.conflicts().next().is_some() {
+            return Ok(PullResult::Conflict(merge));
+        }
+        self.finish_merge(&merge)?;
+        Ok(PullResult::Updated)
+    }
+
+    // Records the merge commit with the resolved store; other files merge as git would.
+    pub fn finish_merge(&self, merge: &Merge) -> Result<(), String> {
+        let merged = self.git(&["merge", "-q", "--no-commit", "--no-ff", &merge.theirs]);
+        if self.git(&["rev-parse", "-q", "--verify", "MERGE_HEAD"]).is_err() {
+            return merged.map(|_| ());
+        }
+        let connections = merge.resolve();
+        let json = serde_json::to_string_pretty(&connections).unwrap();
+        let encrypted = self.show_bytes("HEAD").is_ok_and(|data| crypt::is_encrypted(&data));
+        let contents = match &self.passphrase {
+            Some(passphrase) if encrypted => crypt::encrypt(&json, passphrase),
+            _ => Ok(json.into_bytes()),
+        };
+        let written = contents
+            .and_then(|contents| {
+                // Other instances may be saving the same store.
+                let path = self.dir.join(&self.file);
+                let _lock = store::lock(&path).map_err(|text| text.to_string())?;
+                store::write(&path, &contents).map_err(|text| text.to_string())
+            })
+            .and_then(|_| self.git(&["add", "--", &self.file]))
+            .and_then(|_| {
+                let message = format!("merge {}", merge.upstream);
+                self.git(&["commit", "-q", "-m", &message])
+            });
+        if let Err(text) = written {
+            self.git(&["merge", "--abort"]).ok();
+            return Err(text);
+        }
+        Ok(())
+    }
+
+    // The store file as committed in a revision; a missing file is empty.
+    fn show_bytes(&self, rev: &str) -> Result<Vec<u8>, String> {
+        let object = format!("{}:./{}", rev, self.file);
+        if self.git(&["cat-file", "-e", &object]).is_err() {
+            return Ok(vec![]);
+        }
+        self.git_bytes(&["show", &object])
+    }
+
+    fn show(&self, rev: &str) -> Result<Vec<SSHConnection>, String> {
+        let data = self.show_bytes(rev)?;
+        let json = if crypt::is_encrypted(&data) {
+            crypt::decrypt(&data, self.passphrase.as_ref())?
+        } else {
+            String::from_utf8_lossy(&data).to_string()
+        };
+        if json.trim().is_empty() {
+            return Ok(vec![]);
+        }
+        serde_json::from_str(&json).map_err(|text| format!("The store in {} is invalid: {}", rev, text))
+    }
+}
+
+// Connections are matched by name; repeated names are told apart by their position among the duplicates.
+fn keyed(connections: &[SSHConnection]) -> Vec<((String, usize), &SSHConnection)> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    connections
+        .iter()
+        .map(|connection| {
+            let count = seen.entry(&connection.server_name).or_default();
+            *count += 1;
+            ((connection.server_name.clone(), *count), connection)
+        })
+        .collect()
+}
+
+// Fields compared by merges and commit messages; history is merged separately.
+fn fields(connection: &SSHConnection) -> [(&'static str, String); 8] {
+    [
+        ("name", connection.server_name.clone()),
+        ("group", connection.group_name.clone()),
+        ("username", connection.username.clone()),
+        ("hostname", connection.hostname.clone()),
+        ("port", connection.port.clone()),
+        ("options", connection.options.clone()),
+        ("notes", connection.notes.clone()),
+        ("trash", if connection.deleted.is_some() { "yes" } else { "no" }.to_string()),
+    ]
+}
+
+// Field name and both values for every field that differs, besides history.
+pub fn field_changes(old: &SSHConnection, new: &SSHConnection) -> Vec<(&'static str, String, String)> {
+    let mut changes: Vec<(&'static str, String, String)> = fields(old)
+        .into_iter()
+        .zip(fields(new))
+        .filter(|((_, a), (_, b))| a != b)
+        .map(|((name, a), (_, b))| (name, a, b))
+        .collect();
+    if old.overrides != new.overrides {
+        changes.push((
+            "overrides",
+            old.overrides.clone().unwrap_or_default(),
+            new.overrides.clone().unwrap_or_default(),
+        ));
+    }
+    changes
+}
+
+fn same(a: &SSHConnection, b: &SSHConnection) -> bool {
+    field_changes(a, b).is_empty() && a.deleted == b.deleted
+}
+
+fn with_history(connection: &SSHConnection, a: &SSHConnection, b: &SSHConnection) -> SSHConnection {
+    let mut history: Vec<i64> = a.history.iter().chain(&b.history).copied().collect();
+    history.sort();
+    history.dedup();
+    if history.len() > HISTORY_LIMIT {
+        history.drain(..history.len() - HISTORY_LIMIT);
+    }
+    SSHConnection {
+        history,
+        ..connection.clone()
+    }
+}
+
+fn merge_entry(
+    base: Option<&SSHConnection>,
+    local: Option<&SSHConnection>,
+    remote: Option<&SSHConnection>,
+) -> Option<MergeEntry> {
+    let unchanged = |side: &SSHConnection| base.is_some_and(|base| same(base, side));
+    match (local, remote) {
+        (Some(local), Some(remote)) => {
+            if same(local, remote) || unchanged(remote) {
+                Some(MergeEntry::Resolved(Box::new(with_history(local, local, remote))))
+            } else if unchanged(local) {
+                Some(MergeEntry::Resolved(Box::new(with_history(remote, local, remote))))
+            } else {
+                Some(MergeEntry::Conflict {
+                    local: Some(Box::new(local.clone())),
+                    remote: Some(Box::new(remote.clone())),
+                    keep_local: true,
+                })
+            }
+        }
+        (Some(side), None) | (None, Some(side)) => {
+            if base.is_none() {
+                Some(MergeEntry::Resolved(Box::new(side.clone())))
+            } else if unchanged(side) {
+                None
+            } else {
+                Some(MergeEntry::Conflict {
+                    local: local.cloned().map(Box::new),
+                    remote: remote.cloned().map(Box::new),
+                    keep_local: true,
+                })
+            }
+        }
+        (None, None) => None,
+    }
+}
+
+// Keeps the local order and appends connections that only exist remotely.
+pub fn merge(base: &[SSHConnection], local: &[SSHConnection], remote: &[SSHConnection]) -> Vec<MergeEntry> {
+    let base: HashMap<_, _> = keyed(base).into_iter().collect();
+    let local = keyed(local);
+    let remote = keyed(remote);
+    let local_keys: HashSet<_> = local.iter().map(|(key, _)| key.clone()).collect();
+    let remote_map: HashMap<_, _> = remote.iter().cloned().collect();
+    let mut entries = vec![];
+    for (key, connection) in &local {
+        entries.extend(merge_entry(base.get(key).copied(), Some(connection), remote_map.get(key).copied()));
+    }
+    for (key, connection) in remote.iter().filter(|(key, _)| !local_keys.contains(key)) {
+        entries.extend(merge_entry(base.get(key).copied(), None, Some(connection)));
+    }
+    entries
+}
+
+fn name_list(verb: &str, names: &[String]) -> String {
+    if names.len() > LISTED_NAMES {
+        format!("{} {} connections", verb, names.len())
+    } else {
+        format!("{} {}", verb, names.join(", "))
+    }
+}
+
+// Commit message describing how the saved store changed, e.g. "add web-03" or "edit db-01 port".
+pub fn describe_changes(old: &[SSHConnection], new: &[SSHConnection]) -> String {
+    let old_keyed = keyed(old);
+    let new_keyed = keyed(new);
+    let old_map: HashMap<_, _> = old_keyed.iter().cloned().collect();
+    let new_map: HashMap<_, _> = new_keyed.iter().cloned().collect();
+    let added: Vec<&SSHConnection> = new_keyed
+        .iter()
+        .filter(|(key, _)| !old_map.contains_key(key))
+        .map(|(_, connection)| *connection)
+        .collect();
+    let removed: Vec<&SSHConnection> = old_keyed
+        .iter()
+        .filter(|(key, _)| !new_map.contains_key(key))
+        .map(|(_, connection)| *connection)
+        .collect();
+
+    let mut parts = vec![];
+    let mut edits = vec![];
+    let mut names: HashMap<&str, Vec<String>> = HashMap::new();
+    if let ([old], [new]) = (removed.as_slice(), added.as_slice()) {
+        parts.push(format!("rename {} to {}", old.server_name, new.server_name));
+    } else {
+        for connection in &removed {
+            let verb = if connection.deleted.is_some() { "purge" } else { "remove" };
+            names.entry(verb).or_default().push(connection.server_name.clone());
+        }
+        for connection in &added {
+            names.entry("add").or_default().push(connection.server_name.clone());
+        }
+    }
+    for (key, new) in &new_keyed {
+        let Some(old) = old_map.get(key) else {
+            continue;
+        };
+        match (old.deleted, new.deleted) {
+            (None, Some(_)) => names.entry("delete").or_default().push(new.server_name.clone()),
+            (Some(_), None) => names.entry("restore").or_default().push(new.server_name.clone()),
+            _ => {}
+        }
+        let changed: Vec<&str> = field_changes(old, new)
+            .into_iter()
+            .map(|(field, _, _)| field)
+            .filter(|field| *field != "trash")
+            .collect();
+        if !changed.is_empty() {
+            edits.push(format!("{} {}", new.server_name, changed.join(", ")));
+        } else if old.history != new.history && old.deleted == new.deleted {
+            names.entry("connect to").or_default().push(new.server_name.clone());
+        }
+    }
+    for verb in ["add", "delete", "restore", "purge", "remove"] {
+        if let Some(list) = names.get(verb) {
+            parts.push(name_list(verb, list));
+        }
+    }
+    match edits.len() {
+        0 => {}
+        count if count > LISTED_NAMES => parts.push(format!("edit {} connections", count)),
+        _ => parts.push(format!("edit {}", edits.join("; "))),
+    }
+    if let Some(list) = names.get("connect to") {
+        parts.push(name_list("connect to", list));
+    }
+    if parts.is_empty() {
+        let old_order: Vec<_> = old_keyed.iter().map(|(key, _)| key).collect();
+        let new_order: Vec<_> = new_keyed.iter().map(|(key, _)| key).collect();
+        if old_order != new_order {
+            return "reorder connections".to_string();
+        }
+        return "update connections".to_string();
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn connection(name: &str, hostname: &str) -> SSHConnection {
+        SSHConnection {
+            server_name: name.to_string(),
+            group_name: String::new(),
+            username: "user".to_string(),
+            hostname: hostname.to_string(),
+            port: "22".to_string(),
+            options: String::new(),
+            notes: String::new(),
+            history: vec![],
+            deleted: None,
+            overrides: None,
+            source: None,
+            profile: String::new(),
+            shared: None,
+            conflict: false,
+        }
+    }
+
+    fn hosts(connections: &[SSHConnection]) -> Vec<(&str, &str)> {
+        connections
+            .iter()
+            .map(|connection| (connection.server_name.as_str(), connection.hostname.as_str()))
+            .collect()
+    }
+
+    fn conflict_count(entries: &[MergeEntry]) -> usize {
+        entries.iter().filter(|entry| matches!(entry, MergeEntry::Conflict { .. })).count()
+    }
+
+    #[test]
+    fn one_sided_changes_merge_without_conflicts() {
+        let base = [connection("a", "h1"), connection("b", "h1"), connection("c", "h1")];
+        let local = [connection("a", "h2"), connection("b", "h1"), connection("c", "h1"), connection("d", "h1")];
+        let remote = [connection("a", "h1"), connection("b", "h3"), connection("e", "h1")];
+        let entries = merge(&base, &local, &remote);
+        assert_eq!(conflict_count(&entries), 0);
+        assert_eq!(
+            hosts(&resolve(&entries)),
+            [("a", "h2"), ("b", "h3"), ("d", "h1"), ("e", "h1")]
+        );
+    }
+
+    #[test]
+    fn histories_of_both_sides_are_combined() {
+        let base = [connection("a", "h1")];
+        let mut local = connection("a", "h1");
+        local.history = vec![1, 3];
+        let mut remote = connection("a", "h1");
+        remote.history = vec![2, 3];
+        let entries = merge(&base, &[local], &[remote]);
+        assert_eq!(resolve(&entries)[0].history, [1, 2, 3]);
+    }
+
+    #[test]
+    fn changes_on_both_sides_conflict_and_keep_local_by_default() {
+        let base = [connection("a", "h1")];
+        let entries = merge(&base, &[connection("a", "h2")], &[connection("a", "h3")]);
+        assert_eq!(conflict_count(&entries), 1);
+        assert_eq!(hosts(&resolve(&entries)), [("a", "h2")]);
+    }
+
+    #[test]
+    fn edit_against_removal_is_a_conflict() {
+        let base = [connection("a", "h1"), connection("b", "h1")];
+        let mut pending = Merge {
+            entries: merge(&base, &[connection("a", "h2"), connection("b", "h1")], &[]),
+            upstream: String::new(),
+            theirs: String::new(),
+        };
+        // b was only removed remotely, so it is gone without a conflict.
+        assert_eq!(hosts(&pending.resolve()), [("a", "h2")]);
+        pending.choose(0, false);
+        assert!(pending.resolve().is_empty());
+    }
+
+    #[test]
+    fn each_conflict_takes_its_own_side() {
+        let base = [connection("a", "h1"), connection("b", "h1"), connection("c", "h1")];
+        let local = [connection("a", "h2"), connection("b", "h2"), connection("c", "h2")];
+        let remote = [connection("a", "h3"), connection("b", "h3"), connection("c", "h3")];
+        let mut pending = Merge {
+            entries: merge(&base, &local, &remote),
+            upstream: String::new(),
+            theirs: String::new(),
+        };
+        pending.choose(1, false);
+        let sides: Vec<bool> = pending.conflicts().map(|(_, _, keep_local)| keep_local).collect();
+        assert_eq!(sides, [true, false, true]);
+        assert_eq!(hosts(&pending.resolve()), [("a", "h2"), ("b", "h3"), ("c", "h2")]);
+    }
+
+    #[test]
+    fn repeated_names_are_matched_by_position() {
+        let base = [connection("a", "h1"), connection("a", "h1")];
+        let local = [connection("a", "h1"), connection("a", "h2")];
+        let remote = [connection("a", "h3"), connection("a", "h1")];
+        let entries = merge(&base, &local, &remote);
+        assert_eq!(conflict_count(&entries), 0);
+        assert_eq!(hosts(&resolve(&entries)), [("a", "h3"), ("a", "h2")]);
+    }
+
+    #[test]
+    fn describes_added_renamed_and_edited_connections() {
+        let old = [connection("a", "h1")];
+        assert_eq!(describe_changes(&old, &[connection("a", "h1"), connection("b", "h1")]), "add b");
+        assert_eq!(describe_changes(&old, &[connection("b", "h1")]), "rename a to b");
+        let mut edited = connection("a", "h2");
+        edited.port = "2222".to_string();
+        assert_eq!(describe_changes(&old, &[edited]), "edit a hostname, port");
+    }
+
+    #[test]
+    fn describes_trash_purge_and_connections() {
+        let mut deleted = connection("a", "h1");
+        deleted.deleted = Some(1);
+        assert_eq!(describe_changes(&[connection("a", "h1")], &[deleted.clone()]), "delete a");
+        assert_eq!(describe_changes(&[deleted.clone()], &[connection("a", "h1")]), "restore a");
+        assert_eq!(describe_changes(&[deleted], &[]), "purge a");
+        let mut connected = connection("a", "h1");
+        connected.history = vec![1];
+        assert_eq!(describe_changes(&[connection("a", "h1")], &[connected]), "connect to a");
+    }
+
+    #[test]
+    fn summarizes_long_lists_and_reorders() {
+        let new: Vec<SSHConnection> = ["a", "b", "c", "d"].iter().map(|name| connection(name, "h1")).collect();
+        assert_eq!(describe_changes(&[], &new), "add 4 connections");
+        let old = [connection("a", "h1"), connection("b", "h1")];
+        let new = [connection("b", "h1"), connection("a", "h1")];
+        assert_eq!(describe_changes(&old, &new), "reorder connections");
+    }
+
+    fn run_git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn write_store(path: &Path, connections: &[SSHConnection]) {
+        fs::write(path, serde_json::to_string_pretty(connections).unwrap()).unwrap();
+    }
+
+    fn read_store(path: &Path) -> Vec<SSHConnection> {
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn pulls_and_pushes_through_a_bare_remote() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let first = dir.path().join("first");
+        fs::create_dir_all(&remote).unwrap();
+        fs::create_dir_all(&first).unwrap();
+        run_git(&remote, &["init", "-q", "--bare", "-b", "main"]);
+        run_git(&first, &["init", "-q", "-b", "main"]);
+
+        let first_store = first.join("ssh-list.json");
+        write_store(&first_store, &[connection("a", "h1")]);
+        let first_repo = GitRepo::open(&first_store).unwrap();
+        first_repo.set_remote(&remote.to_string_lossy()).unwrap();
+        assert!(first_repo.commit("add a").unwrap());
+        assert_eq!(first_repo.push().unwrap(), "Pushed to origin/main");
+
+        run_git(dir.path(), &["clone", "-q", &remote.to_string_lossy(), "second"]);
+        let second_store = dir.path().join("second/ssh-list.json");
+        let second_repo = GitRepo::open(&second_store).unwrap();
+        assert!(matches!(second_repo.pull().unwrap(), PullResult::UpToDate));
+
+        write_store(&first_store, &[connection("a", "h2"), connection("b", "h1")]);
+        first_repo.commit("edit a hostname, add b").unwrap();
+        first_repo.push().unwrap();
+        assert!(matches!(second_repo.pull().unwrap(), PullResult::Updated));
+        assert_eq!(hosts(&read_store(&second_store)), [("a", "h2"), ("b", "h1")]);
+
+        // a changes on both sides, b only on the second one.
+        write_store(&first_store, &[connection("a", "h3"), connection("b", "h1")]);
+        first_repo.commit("edit a hostname").unwrap();
+        first_repo.push().unwrap();
+        write_store(&second_store, &[connection("a", "h4"), connection("b", "h5")]);
+        let PullResult::Conflict(mut pending) = second_repo.pull().unwrap() else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(pending.conflicts().count(), 1);
+        pending.choose(0, false);
+        second_repo.finish_merge(&pending).unwrap();
+        assert_eq!(hosts(&read_store(&second_store)), [("a", "h3"), ("b", "h5")]);
+
+        second_repo.push().unwrap();
+        assert!(matches!(first_repo.pull().unwrap(), PullResult::Updated));
+        assert_eq!(hosts(&read_store(&first_store)), [("a", "h3"), ("b", "h5")]);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::*;
+use serde_json::Value;
+
+// Termius JSON exports: a list of hosts, or an object with "hosts" and the "groups" they refer to by id.
+pub struct TermiusJson;
+
+impl import::Importer for TermiusJson {
+    fn detect(&self, path: &Path, _text: &str) -> bool {
+        import::extension(path) == "json"
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let text = import::decode(&fs::read(path).map_err(|text| format!("{}: {}", path.display(), text))?);
+        let value: Value = serde_json::from_str(&text).map_err(|text| format!("{}: {}", path.display(), text))?;
+        let (hosts, groups) = match &value {
+            Value::Array(hosts) => (hosts.as_slice(), &[][..]),
+            Value::Object(map) => (list(map, &["hosts", "host_set"]), list(map, &["groups", "group_set"])),
+            _ => (&[][..], &[][..]),
+        };
+        if hosts.is_empty() {
+            return Err(format!("{} has no Termius hosts.", path.display()));
+        }
+        let mut hosts_with_keys = 0;
+        let mut other_protocols = 0;
+        let mut rows = vec![];
+        for host in hosts {
+            let ssh_config = host.get("ssh_config").filter(|config| config.is_object());
+            let identity = ssh_config.and_then(|config| config.get("identity")).or(host.get("identity"));
+            let protocol = field(host, &["protocol"]);
+            if !protocol.is_empty() && !protocol.eq_ignore_ascii_case("ssh") {
+                other_protocols += 1;
+                continue;
+            }
+            if identity.is_some_and(|identity| !matches!(identity.get("ssh_key"), None | Some(Value::Null))) {
+                hosts_with_keys += 1;
+            }
+            let setting = |keys: &[&str]| {
+                [Some(host), ssh_config, identity]
+                    .into_iter()
+                    .flatten()
+                    .map(|value| field(value, keys))
+                    .find(|value| !value.is_empty())
+                    .unwrap_or_default()
+            };
+            rows.push(Row {
+                label: field(host, &["label", "name"]),
+                group: group_path(host.get("group"), groups),
+                tags: tags(host.get("tags")),
+                hostname: field(host, &["address", "hostname", "host"]),
+                port: setting(&["port"]),
+                username: setting(&["username", "user"]),
+                notes: field(host, &["notes", "description"]),
+            });
+        }
+        Ok(to_connections(rows, other_protocols, hosts_with_keys))
+    }
+}
+
+// CSV files written by Termius, recognized by their "Hostname/IP" column.
+pub struct TermiusCsv;
+
+impl import::Importer for TermiusCsv {
+    fn detect(&self, path: &Path, text: &str) -> bool {
+        import::extension(path) == "csv"
+            && text.lines().next().is_some_and(|header| header.to_lowercase().contains("hostname/ip"))
+    }
+
+    fn read(&self, path: &Path) -> Result<import::Imported, String> {
+        let text = import::decode(&fs::read(path).map_err(|text| format!("{}: {}", path.display(), text))?);
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+        let header: Vec<String> = reader
+            .headers()
+            .map_err(|text| format!("{}: {}", path.display(), text))?
+            .iter()
+            .map(|name| name.trim().to_lowercase())
+            .collect();
+        let column = |names: &[&str]| header.iter().position(|name| names.contains(&name.as_str()));
+        let columns = [
+            column(&["label"]),
+            column(&["groups", "group"]),
+            column(&["tags"]),
+            column(&["hostname/ip", "hostname", "address"]),
+            column(&["protocol"]),
+            column(&["port"]),
+            column(&["username"]),
+            column(&["ssh_key", "ssh key"]),
+            column(&["notes"]),
+        ];
+        let mut hosts_with_keys = 0;
+        let mut other_protocols = 0;
+        let mut rows = vec![];
+        for record in reader.records() {
+            let record = record.map_err(|text| format!("{}: {}", path.display(), text))?;
+            let [label, group, tags, hostname, protocol, port, username, key, notes] = columns
+                .map(|column| column.and_then(|column| record.get(column)).unwrap_or_default().trim().to_string());
+            if !protocol.is_empty() && !protocol.eq_ignore_ascii_case("ssh") {
+                other_protocols += 1;
+                continue;
+            }
+            if !key.is_empty() {
+                hosts_with_keys += 1;
+            }
+            rows.push(Row {
+                label,
+                group,
+                tags: tags.split([',', ';', ' ']).filter(|tag| !tag.is_empty()).map(str::to_string).collect(),
+                hostname,
+                port,
+                username,
+                notes,
+            });
+        }
+        Ok(to_connections(rows, other_protocols, hosts_with_keys))
+    }
+}
+
+// A host as both export formats describe it.
+struct Row {
+    label: String,
+    group: String,
+    tags: Vec<String>,
+    hostname: String,
+    port: String,
+    username: String,
+    notes: String,
+}
+
+// Termius keeps keys in its own vault, so hosts using one are imported without it and counted.
+fn to_connections(rows: Vec<Row>, other_protocols: usize, hosts_with_keys: usize) -> import::Imported {
+    let mut imported = import::Imported::default();
+    for row in rows {
+        if row.hostname.is_empty() {
+            continue;
+        }
+        let name = if row.label.is_empty() { row.hostname.clone() } else { row.label };
+        let port = if row.port.is_empty() { "22".to_string() } else { row.port };
+        let mut connection = import::connection(name, row.username, row.hostname, port, String::new());
+        connection.group_name = row.group;
+        // Tags are #words in the notes.
+        let tags: Vec<String> = row.tags.iter().map(|tag| format!("#{}", tag.trim_start_matches('#'))).collect();
+        connection.notes = [row.notes, tags.join(" ")]
+            .into_iter()
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        imported.connections.push(connection);
+    }
+    if other_protocols > 0 {
+        imported.notes.push(format!("Skipped hosts with other protocols than SSH: {}", other_protocols));
+    }
+    if hosts_with_keys > 0 {
+        imported.notes.push(format!("Hosts using keys stored in Termius, imported without them: {}", hosts_with_keys));
+    }
+    imported
+}
+
+fn list<'a>(map: &'a serde_json::Map<String, Value>, keys: &[&str]) -> &'a [Value] {
+    keys.iter().find_map(|key| map.get(*key)?.as_array()).map_or(&[], Vec::as_slice)
+}
+
+fn field(value: &Value, keys: &[&str]) -> String {
+    keys.iter()
+        .find_map(|key| match value.get(*key)? {
+            Value::String(text) => Some(text.trim().to_string()),
+            Value::Number(number) => Some(number.to_string()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+fn tags(value: Option<&Value>) -> Vec<String> {
+    let Some(Value::Array(tags)) = value else {
+        return vec![];
+    };
+    tags.iter()
+        .filter_map(|tag| match tag {
+            Value::String(tag) => Some(tag.clone()),
+            tag => Some(field(tag, &["label", "name"])).filter(|tag| !tag.is_empty()),
+        })
+        .collect()
+}
+
+// A group is a path ("Prod/DB"), an object with a label and a parent, or the id of an entry in the group list.
+fn group_path(value: Option<&Value>, groups: &[Value]) -> String {
+    let mut names: Vec<String> = vec![];
+    let mut current = value;
+    while let Some(value) = current.filter(|_| names.len() < import::MAX_GROUP_DEPTH) {
+        let group = if value.is_object() {
+            Some(value)
+        } else {
+            groups.iter().find(|group| group.get("id") == Some(value))
+        };
+        match (group, value) {
+            (Some(group), _) => {
+                names.insert(0, field(group, &["label", "name"]));
+                current = group.get("parent_group");
+            }
+            (None, Value::String(path)) => {
+                names.insert(0, path.trim().to_string());
+                break;
+            }
+            (None, _) => break,
+        }
+    }
+    names.retain(|name| !name.is_empty());
+    names.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{Importer, fields};
+
+    #[test]
+    fn reads_a_json_export_with_nested_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("termius.json");
+        fs::write(
+            &path,
+            r#"{
+                "group_set": [
+                    {"id": 1, "label": "Prod"},
+                    {"id": 2, "label": "DB", "parent_group": 1},
+                    {"id": 3, "label": "Replicas", "parent_group": 2}
+                ],
+                "host_set": [
+                    {
+                        "label": "pg-main",
+                        "address": "10.1.0.5",
+                        "group": 2,
+                        "ssh_config": {"port": 2200, "identity": {"username": "postgres", "ssh_key": {"label": "pg"}}},
+                        "tags": [{"label": "db"}, "critical"]
+                    },
+                    {"label": "pg-replica", "address": "10.1.0.6", "group": 3, "username": "postgres"},
+                    {"label": "web", "address": "web.example.com", "group": {"label": "Web"}, "username": "www"},
+                    {"address": "10.9.9.9"},
+                    {"label": "switch", "address": "sw1", "protocol": "telnet"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(TermiusJson.detect(&path, ""));
+
+        let imported = TermiusJson.read(&path).unwrap();
+        assert_eq!(imported.connections.len(), 4);
+        assert_eq!(fields(&imported.connections[0]), ["pg-main", "Prod/DB", "postgres", "10.1.0.5", "2200", ""]);
+        assert_eq!(imported.connections[0].notes, "#db #critical");
+        assert_eq!(
+            fields(&imported.connections[1]),
+            ["pg-replica", "Prod/DB/Replicas", "postgres", "10.1.0.6", "22", ""]
+        );
+        assert_eq!(fields(&imported.connections[2]), ["web", "Web", "www", "web.example.com", "22", ""]);
+        assert_eq!(fields(&imported.connections[3]), ["10.9.9.9", "", "", "10.9.9.9", "22", ""]);
+        assert_eq!(
+            imported.notes,
+            [
+                "Skipped hosts with other protocols than SSH: 1",
+                "Hosts using keys stored in Termius, imported without them: 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_a_csv_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("termius.csv");
+        let text = "Groups,Label,Tags,Hostname/IP,Protocol,Port,Username,Password,SSH_KEY\n\
+                    Ops/Edge,edge1,\"lb,edge\",198.51.100.7,ssh,2222,root,,edge-key\n\
+                    ,bare,,bare.example.com,,,,,\n\
+                    ,mosh1,,m.example.com,mosh,22,u,,\n";
+        fs::write(&path, text).unwrap();
+        assert!(TermiusCsv.detect(&path, text));
+        assert!(!TermiusCsv.detect(&path, "Name,Host\n"));
+
+        let imported = TermiusCsv.read(&path).unwrap();
+        assert_eq!(imported.connections.len(), 2);
+        assert_eq!(fields(&imported.connections[0]), ["edge1", "Ops/Edge", "root", "198.51.100.7", "2222", ""]);
+        assert_eq!(imported.connections[0].notes, "#lb #edge");
+        assert_eq!(fields(&imported.connections[1]), ["bare", "", "", "bare.example.com", "22", ""]);
+        assert_eq!(
+            imported.notes,
+            [
+                "Skipped hosts with other protocols than SSH: 1",
+                "Hosts using keys stored in Termius, imported without them: 1"
+            ]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+pub const ACCENT_COLORS: [&str; 15] = [
+    "yellow",
+    "lightyellow",
+    "white",
+    "darkgray",
+    "gray",
+    "red",
+    "lightred",
+    "green",
+    "lightgreen",
+    "blue",
+    "lightblue",
+    "magenta",
+    "lightmagenta",
+    "cyan",
+    "lightcyan",
+];
+
+pub const BUILTIN_THEMES: [&str; 2] = ["dark", "light"];
+
+#[derive(Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct ThemeConfig {
+    base: Option<String>,
+    background: Option<String>,
+    text: Option<String>,
+    row: Option<String>,
+    alt_row: Option<String>,
+    header_fg: Option<String>,
+    header_bg: Option<String>,
+    selection: Option<String>,
+    border: Option<String>,
+    popup_fg: Option<String>,
+    popup_bg: Option<String>,
+    popup_border: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub background: Color,
+    pub text: Color,
+    pub row: Color,
+    pub alt_row: Color,
+    pub header_fg: Color,
+    pub header_bg: Color,
+    // Selection and borders follow the accent color unless the theme sets them.
+    pub selection: Option<Color>,
+    pub border: Option<Color>,
+    pub popup_fg: Color,
+    pub popup_bg: Color,
+    pub popup_border: Color,
+    // Rows with a name conflict, and error messages.
+    pub error: Color,
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: Color::Black,
+            text: Color::White,
+            row: Color::Black,
+            alt_row: Color::Indexed(235),
+            header_fg: Color::Gray,
+            header_bg: Color::Indexed(235),
+            selection: None,
+            border: None,
+            popup_fg: Color::White,
+            popup_bg: Color::Reset,
+            popup_border: Color::Reset,
+            error: Color::Red,
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Color::Rgb(255, 255, 255),
+            text: Color::Rgb(32, 32, 32),
+            row: Color::Rgb(255, 255, 255),
+            alt_row: Color::Rgb(238, 238, 238),
+            header_fg: Color::Rgb(64, 64, 64),
+            header_bg: Color::Rgb(221, 221, 221),
+            selection: None,
+            border: None,
+            popup_fg: Color::Rgb(32, 32, 32),
+            popup_bg: Color::Rgb(245, 245, 245),
+            popup_border: Color::Rgb(96, 96, 96),
+            error: Color::Rgb(192, 0, 0),
+        }
+    }
+
+    fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    pub fn resolve(name: &str, themes: &BTreeMap<String, ThemeConfig>) -> Result<Self, String> {
+        let Some(config) = themes.get(name) else {
+            return Self::builtin(name).ok_or(format!("Unknown theme: {}", name));
+        };
+        let base = config.base.as_deref().unwrap_or("dark");
+        let mut theme = Self::builtin(base).ok_or(format!("Unknown base theme: {}", base))?;
+        let colors = [
+            (&config.background, &mut theme.background),
+            (&config.text, &mut theme.text),
+            (&config.row, &mut theme.row),
+            (&config.alt_row, &mut theme.alt_row),
+            (&config.header_fg, &mut theme.header_fg),
+            (&config.header_bg, &mut theme.header_bg),
+            (&config.popup_fg, &mut theme.popup_fg),
+            (&config.popup_bg, &mut theme.popup_bg),
+            (&config.popup_border, &mut theme.popup_border),
+            (&config.error, &mut theme.error),
+        ];
+        for (value, color) in colors {
+            if let Some(value) = value {
+                *color = parse_color(value).ok_or(format!("Invalid color \"{}\" in theme {}", value, name))?;
+            }
+        }
+        for (value, color) in [(&config.selection, &mut theme.selection), (&config.border, &mut theme.border)] {
+            if let Some(value) = value {
+                *color = Some(parse_color(value).ok_or(format!("Invalid color \"{}\" in theme {}", value, name))?);
+            }
+        }
+        Ok(theme)
+    }
+}
+
+// Accepts color names, 256-color indexes, "#rrggbb" and "rgb(r, g, b)".
+pub fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+    if let Some(rgb) = value.strip_prefix("rgb(").and_then(|rgb| rgb.strip_suffix(')')) {
+        let parts: Option<Vec<u8>> = rgb.split(',').map(|part| part.trim().parse().ok()).collect();
+        return match parts.as_deref() {
+            Some(&[r, g, b]) => Some(Color::Rgb(r, g, b)),
+            _ => None,
+        };
+    }
+    Color::from_str(value).ok()
+}
+
+pub fn theme_names(themes: &BTreeMap<String, ThemeConfig>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|name| name.to_string()).collect();
+    for name in themes.keys() {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("rgb(1, 2, 3)"), Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(parse_color("#0a0b0c"), Some(Color::Rgb(10, 11, 12)));
+        assert_eq!(parse_color("lightblue"), Some(Color::LightBlue));
+        assert_eq!(parse_color("235"), Some(Color::Indexed(235)));
+        for invalid in ["rgb(1,x,2,3)", "rgb(1,2)", "rgb(1,2,3,4)", "rgb(1,2,256)", "rgb()", "nocolor"] {
+            assert_eq!(parse_color(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn themes_override_their_base() {
+        let config = ThemeConfig {
+            base: Some("light".to_string()),
+            error: Some("#dc322f".to_string()),
+            ..Default::default()
+        };
+        let themes = BTreeMap::from([("solarized".to_string(), config)]);
+        let theme = Theme::resolve("solarized", &themes).unwrap();
+        assert_eq!(theme.error, Color::Rgb(220, 50, 47));
+        assert_eq!(theme.text, Theme::light().text);
+        assert_eq!(Theme::resolve("dark", &themes).unwrap().error, Color::Red);
+
+        let bad = ThemeConfig { error: Some("rgb(1,x,2,3)".to_string()), ..Default::default() };
+        let themes = BTreeMap::from([("bad".to_string(), bad)]);
+        assert_eq!(Theme::resolve("bad", &themes).err().as_deref(), Some("Invalid color \"rgb(1,x,2,3)\" in theme bad"));
+    }
+}
@@ -1,19 +1,29 @@
+use crate::columns::Column;
+use crate::keybindings::Action;
 use crate::*;
 use ratatui::{
     layout::{Alignment, Constraint, Layout, Margin, Rect},
-    style::{Color, Modifier, Style, Stylize},
-    text::Text,
+    style::{Modifier, Style, Stylize},
+    text::{Line, Span, Text},
     widgets::{
-        Block, BorderType, Cell, Clear, HighlightSpacing, Paragraph, Row, Scrollbar, ScrollbarOrientation, Table,
+        Block, BorderType, Cell, Clear, HighlightSpacing, List, ListItem, Padding, Paragraph, Row, Scrollbar, ScrollbarOrientation,
+        Table, Wrap,
     },
     Frame,
 };
-use std::str::FromStr;
+
+fn popup_block<'a>(app: &App, title: &'a str) -> Block<'a> {
+    Block::bordered()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .style(Style::new().fg(app.theme.popup_fg).bg(app.theme.popup_bg))
+        .border_style(Style::new().fg(app.theme.popup_border))
+}
 
 pub fn render_input(app: &App, frame: &mut Frame, area: Rect, title: &str, selected_input: &Input, focused: Focus) {
     let width = area.width.max(3) - 3;
     let scroll = selected_input.visual_scroll(width as usize);
-    let input = Paragraph::new(selected_input.value())
+    let input = Paragraph::new(selected_input.value().replace('\n', "↵"))
         .scroll((0, scroll as u16))
         .block(Block::bordered().title(title));
     frame.render_widget(input, area);
@@ -27,11 +37,14 @@ pub fn render_input(app: &App, frame: &mut Frame, area: Rect, title: &str, selec
 pub fn render_popup(app: &mut App, frame: &mut Frame, area: Rect) {
     let title_text = match app.app_mode {
         AppMode::New => " Add new connection ",
+        AppMode::Edit if app.highlighted_connection().is_some_and(|connection| connection.shared.is_some()) => {
+            " Edit (saved as personal override) "
+        }
         AppMode::Edit => " Edit connection ",
         _ => "",
     };
 
-    let popup_block = Block::bordered().title(title_text).title_alignment(Alignment::Center);
+    let popup_block = popup_block(app, title_text);
     let area = popup_area(area);
     let inner = popup_block.inner(area);
     frame.render_widget(Clear, area);
@@ -45,8 +58,18 @@ pub fn render_popup(app: &mut App, frame: &mut Frame, area: Rect) {
         Constraint::Max(3),
         Constraint::Max(3),
         Constraint::Max(3),
+        Constraint::Max(3),
     ]);
     let rects_popup = vertical_popup.split(inner);
+    app.field_areas = vec![
+        (Focus::ServerNameField, rects_popup[1]),
+        (Focus::GroupNameField, rects_popup[2]),
+        (Focus::UsernameField, rects_popup[3]),
+        (Focus::HostnameField, rects_popup[4]),
+        (Focus::PortField, rects_popup[5]),
+        (Focus::OptionsField, rects_popup[6]),
+        (Focus::NotesField, rects_popup[7]),
+    ];
     render_input(
         app,
         frame,
@@ -95,36 +118,200 @@ pub fn render_popup(app: &mut App, frame: &mut Frame, area: Rect) {
         &app.field_inputs.options_input,
         Focus::OptionsField,
     );
+    render_input(
+        app,
+        frame,
+        rects_popup[7],
+        " Notes ([Alt+Enter] new line) ",
+        &app.field_inputs.notes_input,
+        Focus::NotesField,
+    );
+}
+
+fn key_hint(app: &App, action: Action, text: &str) -> String {
+    format!("[{}] {}", app.keybindings.label(action), text)
+}
+
+fn align_columns(rows: Vec<Vec<String>>) -> String {
+    let columns = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(i, cell)| format!("{:<width$}", cell, width = widths[i]))
+                .collect::<Vec<String>>()
+                .join(" | ")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
 }
 
 pub fn render_footer(app: &mut App, frame: &mut Frame, area: Rect) {
     let footer_text = match app.app_mode {
         AppMode::Normal => {
-            if app.ssh_connections.is_empty() {
-                "[A] add | [I] import | [Esc] quit"
+            if app.view_index.is_empty() {
+                [
+                    key_hint(app, Action::Add, "add"),
+                    key_hint(app, Action::Import, "import"),
+                    key_hint(app, Action::Trash, "trash"),
+                    key_hint(app, Action::Profiles, "profile"),
+                    key_hint(app, Action::Quit, "quit"),
+                ]
+                .join(" | ")
             } else {
-                "[Enter] connect | [R] run  | [/] search | [I] import | [O] options | [Esc] quit  \n    [A] add     | [E] edit | [C] copy   | [M] move   | [S] sort    | [Del] delete"
+                let mut second_row = vec![
+                    key_hint(app, Action::Add, "add"),
+                    key_hint(app, Action::Edit, "edit"),
+                    key_hint(app, Action::Copy, "copy"),
+                    key_hint(app, Action::Move, "move"),
+                    key_hint(app, Action::Sort, "sort"),
+                    key_hint(app, Action::Preview, "preview"),
+                    key_hint(app, Action::Mark, "mark"),
+                    key_hint(app, Action::Delete, "delete"),
+                ];
+                if app.git_sync {
+                    second_row.push(key_hint(app, Action::Sync, "sync"));
+                }
+                align_columns(vec![
+                    vec![
+                        key_hint(app, Action::Connect, "connect"),
+                        key_hint(app, Action::Run, "run"),
+                        key_hint(app, Action::Search, "search"),
+                        key_hint(app, Action::Import, "import"),
+                        key_hint(app, Action::Export, "export"),
+                        key_hint(app, Action::Options, "options"),
+                        key_hint(app, Action::Details, "details"),
+                        key_hint(app, Action::Profiles, "profile"),
+                        key_hint(app, Action::Trash, "trash"),
+                        key_hint(app, Action::Quit, "quit"),
+                    ],
+                    second_row,
+                ])
             }
         }
-        AppMode::New => "[Enter] save | [Esc] cancel",
-        AppMode::Edit => "[Enter] save | [Esc] cancel",
-        AppMode::Move => "[↓] move down | [↑] move up | [Esc] back",
-        AppMode::Import => "[I] import | [Esc] back",
-        AppMode::Error => "[Esc] back",
-        AppMode::RunCommand => "[Enter] run command | [Esc] back",
-        AppMode::Search => "[Enter] connect | [Ctrl+R] run | [Ctrl+E] edit | [Del] delete | [Esc] back",
-        AppMode::Options => "[↑][↓] height | [←][→] color | [Esc] back",
-        AppMode::Sort => "[n] name | [g] group | [u] username | [h] hostname | [p] port | [Esc] back",
+        AppMode::New => "[Enter] save | [Esc] cancel".to_string(),
+        AppMode::Edit => "[Enter] save | [Esc] cancel".to_string(),
+        AppMode::Move => [
+            key_hint(app, Action::MoveDown, "move down"),
+            key_hint(app, Action::MoveUp, "move up"),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Import => [
+            key_hint(app, Action::ImportSshConfig, "SSH config"),
+            key_hint(app, Action::ImportKnownHosts, "known_hosts"),
+            key_hint(app, Action::ImportFile, "file"),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::ImportPath => "[Enter] read file | [Esc] back".to_string(),
+        AppMode::CsvColumns => [
+            "[↑][↓] select | [←][→] column".to_string(),
+            key_hint(app, Action::CsvHeader, "header row"),
+            "[Enter] continue | [Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Export => [
+            "[Tab] format | [↑][↓] connections".to_string(),
+            key_hint(app, Action::ExportPersonal, "personal"),
+            "[Enter] export | [Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::ImportPreview => [
+            key_hint(app, Action::Mark, "select"),
+            key_hint(app, Action::ImportSelectAll, "all"),
+            key_hint(app, Action::ImportReplace, "merge/replace"),
+            "[Enter] import".to_string(),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::ImportReport => "[↑][↓] scroll | [Enter] close".to_string(),
+        AppMode::Error => "[Esc] back".to_string(),
+        AppMode::RunCommand => "[Enter] run command | [Esc] back".to_string(),
+        AppMode::Search => [
+            key_hint(app, Action::Connect, "connect"),
+            key_hint(app, Action::SearchRun, "run"),
+            key_hint(app, Action::SearchEdit, "edit"),
+            key_hint(app, Action::SearchPreview, "preview"),
+            key_hint(app, Action::SearchExport, "export"),
+            key_hint(app, Action::Delete, "delete"),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Options => [
+            "[↑][↓] height | [←][→] color".to_string(),
+            key_hint(app, Action::OptionsTheme, "theme"),
+            key_hint(app, Action::OptionsColumns, "columns"),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Columns if app.columns.get(app.column_cursor).is_some_and(|layout| layout.max_width.is_none()) => {
+            "[↑][↓] select | [Space] show/hide | [Shift+↑↓] move | [←] set max (fills the rest) | [-][+] min | [Esc] back"
+                .to_string()
+        }
+        AppMode::Columns => {
+            "[↑][↓] select | [Space] show/hide | [Shift+↑↓] move | [←][→] max | [-][+] min | [Esc] back".to_string()
+        }
+        AppMode::Preview => [key_hint(app, Action::CopyCommand, "copy to clipboard"), "[Esc] back".to_string()].join(" | "),
+        AppMode::Confirm => {
+            [key_hint(app, Action::ConfirmYes, "confirm"), key_hint(app, Action::ConfirmNo, "cancel")].join(" | ")
+        }
+        AppMode::Profiles => "[↑][↓] select | [Enter] switch | [Esc] back".to_string(),
+        AppMode::Trash => [
+            key_hint(app, Action::TrashRestore, "restore"),
+            key_hint(app, Action::TrashPurge, "purge"),
+            key_hint(app, Action::TrashPurgeAll, "purge all"),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Unlock if app.unlocking_at_startup() => "[Enter] unlock | [Esc] quit".to_string(),
+        AppMode::Unlock => "[Enter] unlock | [Esc] cancel".to_string(),
+        AppMode::Sync => [
+            key_hint(app, Action::SyncPull, "pull"),
+            key_hint(app, Action::SyncPush, "push"),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Merge => [
+            key_hint(app, Action::MergeLocal, "keep local"),
+            key_hint(app, Action::MergeRemote, "take remote"),
+            "[↑][↓] select".to_string(),
+            "[Enter] merge".to_string(),
+            "[Esc] cancel".to_string(),
+        ]
+        .join(" | "),
+        AppMode::Sort => [
+            key_hint(app, Action::SortName, "name"),
+            key_hint(app, Action::SortGroup, "group"),
+            key_hint(app, Action::SortUsername, "username"),
+            key_hint(app, Action::SortHostname, "hostname"),
+            key_hint(app, Action::SortPort, "port"),
+            key_hint(app, Action::SortManual, "manual order"),
+            "press again to reverse".to_string(),
+            "[Esc] back".to_string(),
+        ]
+        .join(" | "),
     };
-    let app_color = Color::from_str(&app.color).unwrap_or(Color::Yellow);
+    let border_color = app.theme.border.unwrap_or(app.accent_color());
+    let mut block = Block::bordered()
+        .border_type(BorderType::Double)
+        .border_style(Style::new().fg(border_color));
+    if app.profile != profile::DEFAULT_PROFILE {
+        block = block.title(format!(" Profile: {} ", profile_label(&app.profile)));
+    }
     let info_footer = Paragraph::new(footer_text)
-        .style(Style::new().fg(Color::White).bg(Color::Black))
+        .style(Style::new().fg(app.theme.text).bg(app.theme.background))
         .centered()
-        .block(
-            Block::bordered()
-                .border_type(BorderType::Double)
-                .border_style(Style::new().fg(app_color)),
-        );
+        .block(block);
     frame.render_widget(info_footer, area);
 }
 
@@ -139,124 +326,113 @@ pub fn render_scrollbar(app: &mut App, frame: &mut Frame, area: Rect) {
     );
 }
 
-fn get_constraint(app: &App) -> Vec<Constraint> {
-    let server_name_len = app
-        .ssh_connections
-        .iter()
-        .map(|i| i.server_name.len() + 1)
-        .max()
-        .unwrap_or(0);
-    let group_name_len = app
-        .ssh_connections
+// The merged view always shows which profile a connection comes from, and shared inventories their source.
+pub fn visible_columns(app: &App) -> Vec<&ColumnLayout> {
+    let has_shared = !app.inventories.is_empty();
+    app.columns
         .iter()
-        .map(|i| i.group_name.len() + 1)
-        .max()
-        .unwrap_or(0);
-    let username_len = app
-        .ssh_connections
-        .iter()
-        .map(|i| i.username.len() + 1)
-        .max()
-        .unwrap_or(0);
-    let hostname_len = app
-        .ssh_connections
-        .iter()
-        .map(|i| i.hostname.len() + 1)
-        .max()
-        .unwrap_or(0);
-
-    vec![
-        Constraint::Length(server_name_len.clamp(10, 50) as u16),
-        Constraint::Length(group_name_len.clamp(10, 50) as u16),
-        Constraint::Length(username_len.clamp(10, 50) as u16),
-        Constraint::Length(hostname_len.clamp(10, 50) as u16),
-        Constraint::Length(7), //port
-        Constraint::Min(1),    //options
-    ]
+        .filter(|layout| {
+            layout.visible
+                || (layout.column == Column::Profile && app.is_merged_view())
+                || (layout.column == Column::Source && has_shared)
+        })
+        .collect()
+}
+
+fn get_constraint(app: &App) -> Vec<Constraint> {
+    visible_columns(app)
+        .into_iter()
+        .map(|layout| {
+            let content_len = app
+                .view_index
+                .iter()
+                .map(|&i| layout.column.value(&app.ssh_connections[i]).chars().count() + 1)
+                .max()
+                .unwrap_or(0);
+            layout.constraint(content_len)
+        })
+        .collect()
 }
 
 pub fn render_table(app: &mut App, frame: &mut Frame, area: Rect) {
-    let app_color = Color::from_str(&app.color).unwrap_or(Color::Yellow);
-    let header_style = Style::default().fg(Color::Gray).bg(Color::Indexed(235));
-    let selected_row_style = Style::default().add_modifier(Modifier::REVERSED).fg(app_color);
-    let header = [" Name", " Group", " Username", " Hostname", " Port", " Options"]
+    let selection_color = app.theme.selection.unwrap_or(app.accent_color());
+    let header_style = Style::default().fg(app.theme.header_fg).bg(app.theme.header_bg);
+    let selected_row_style = Style::default().add_modifier(Modifier::REVERSED).fg(selection_color);
+    let constraints = get_constraint(app);
+    app.table_area = area;
+    app.column_areas = Layout::horizontal(constraints.clone()).spacing(1).split(area).to_vec();
+    let visible: Vec<(Column, usize)> = visible_columns(app)
         .into_iter()
-        .map(Cell::from)
+        .zip(&app.column_areas)
+        .map(|(layout, area)| (layout.column, area.width.saturating_sub(1) as usize))
+        .collect();
+    let header = visible
+        .iter()
+        .map(|(column, width)| {
+            let title = match app.sort_keys.iter().position(|key| key.column == *column) {
+                // The primary sort key gets a filled arrow, secondary keys an outlined one.
+                Some(position) => {
+                    let key = app.sort_keys[position];
+                    let arrow = match (position, key.descending) {
+                        (0, false) => "▲",
+                        (0, true) => "▼",
+                        (_, false) => "△",
+                        (_, true) => "▽",
+                    };
+                    format!("{} {}", column.title(), arrow)
+                }
+                None => column.title().to_string(),
+            };
+            Cell::from(format!(" {}", columns::truncate(&title, *width)))
+        })
         .collect::<Row>()
         .style(header_style)
         .height(1);
+    let indexes: Vec<usize> = if app.shows_all_rows() {
+        app.view_index.clone()
+    } else {
+        app.search_index.clone()
+    };
     let mut rows = Vec::new();
-    if app.app_mode == AppMode::Normal
-        || app.app_mode == AppMode::New
-        || app.app_mode == AppMode::Move
-        || app.app_mode == AppMode::Options
-        || app.app_mode == AppMode::Import
-        || app.app_mode == AppMode::Sort
-    {
-        for (i, data) in app.ssh_connections.iter().enumerate() {
-            let color = match i % 2 {
-                0 => Color::Black,
-                _ => Color::Indexed(235),
-            };
-            let item = data.ref_array();
+    for (i, index) in indexes.into_iter().enumerate() {
+        let data = &app.ssh_connections[index];
+        let color = match i % 2 {
+            0 => app.theme.row,
+            _ => app.theme.alt_row,
+        };
+        let cells = visible.iter().map(|(column, width)| {
+            let content = columns::truncate(&column.value(data), *width);
             if app.row_height == 3 {
-                let row = item
-                    .into_iter()
-                    .map(|content| Cell::from(Text::from(format!("\n {content}\n"))))
-                    .collect::<Row>()
-                    .style(Style::new().fg(Color::White).bg(color))
-                    .height(3);
-                rows.push(row);
-            }
-            if app.row_height == 1 {
-                let row = item
-                    .into_iter()
-                    .map(|content| Cell::from(Text::from(format!(" {content}"))))
-                    .collect::<Row>()
-                    .style(Style::new().fg(Color::White).bg(color))
-                    .height(1);
-                rows.push(row);
+                Cell::from(Text::from(format!("\n {content}\n")))
+            } else {
+                Cell::from(Text::from(format!(" {content}")))
             }
+        });
+        let mut style = if app.marked.contains(&index) {
+            Style::new().fg(app.accent_color()).bg(color).add_modifier(Modifier::BOLD)
+        } else {
+            Style::new().fg(app.theme.text).bg(color)
+        };
+        if data.shared.is_some() {
+            style = style.add_modifier(Modifier::ITALIC);
         }
-    } else {
-        for (i, &index) in app.search_index.iter().enumerate() {
-            let data = &app.ssh_connections[index];
-            let color = match i % 2 {
-                0 => Color::Black,
-                _ => Color::Indexed(235),
-            };
-            let item = data.ref_array();
-            if app.row_height == 3 {
-                let row = item
-                    .into_iter()
-                    .map(|content| Cell::from(Text::from(format!("\n {content}\n"))))
-                    .collect::<Row>()
-                    .style(Style::new().fg(Color::White).bg(color))
-                    .height(3);
-                rows.push(row);
-            }
-            if app.row_height == 1 {
-                let row = item
-                    .into_iter()
-                    .map(|content| Cell::from(Text::from(format!(" {content}"))))
-                    .collect::<Row>()
-                    .style(Style::new().fg(Color::White).bg(color))
-                    .height(1);
-                rows.push(row);
-            }
+        if data.conflict {
+            style = style.fg(app.theme.error);
         }
+        let row = cells.collect::<Row>().style(style).height(app.row_height);
+        rows.push(row);
     }
-    let t = Table::new(rows, get_constraint(&app))
+    let t = Table::new(rows, constraints)
         .header(header)
         .row_highlight_style(selected_row_style)
-        .bg(Color::Black)
+        .bg(app.theme.background)
         .highlight_spacing(HighlightSpacing::Always);
     frame.render_stateful_widget(t, area, &mut app.table_state);
 }
 
-pub fn render_config_popup(frame: &mut Frame, area: Rect) {
-    let title_text = " SSH Config Import ";
-    let popup_block = Block::bordered().title(title_text).title_alignment(Alignment::Center);
+pub fn render_config_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let title_text = " Import ";
+    let popup_block = popup_block(app, title_text).padding(Padding::horizontal(1));
     let area = config_popup_area(area);
     let inner = popup_block.inner(area);
     frame.render_widget(Clear, area);
@@ -264,27 +440,254 @@ pub fn render_config_popup(frame: &mut Frame, area: Rect) {
 
     let vertical_popup = &Layout::vertical([
         Constraint::Length(1),
-        Constraint::Length(2), // text1
+        Constraint::Length(1), // text1
         Constraint::Length(1),
-        Constraint::Length(2), // text2
+        Constraint::Length(3), // sources
+        Constraint::Length(1),
+        Constraint::Length(1), // text2
     ]);
     let rects_popup = vertical_popup.split(inner);
 
-    let text1 = format!(
-        "Press [I] to import connections from:\n{}",
-        parse::get_sshconfig_path().display()
-    );
-    let info_footer = Paragraph::new(text1).style(Style::new().fg(Color::White)).centered();
+    let text1 = "Press a key to import connections from:";
+    let info_footer = Paragraph::new(text1).style(Style::new().fg(app.theme.popup_fg)).centered();
     frame.render_widget(info_footer, rects_popup[1]);
 
-    let text2 = "The username, hostname, port, and \nnon-default options will be imported";
-    let info_footer = Paragraph::new(text2).style(Style::new().fg(Color::White)).centered();
-    frame.render_widget(info_footer, rects_popup[3]);
+    let path_width = (inner.width as usize).saturating_sub(18);
+    let sources = [
+        (app.keybindings.label(Action::ImportSshConfig), "SSH config", parse::get_sshconfig_path().display().to_string()),
+        (
+            app.keybindings.label(Action::ImportKnownHosts),
+            "known_hosts",
+            known_hosts::get_known_hosts_path().display().to_string(),
+        ),
+        (app.keybindings.label(Action::ImportFile), "file", "CSV, Ansible, PuTTY, Remmina, Termius, Asbru".to_string()),
+    ]
+    .into_iter()
+    .map(|(key, label, path)| {
+        Line::from(vec![
+            Span::styled(format!("[{}] ", key), Style::new().fg(app.accent_color())),
+            Span::raw(format!("{:<13}", label)),
+            Span::raw(columns::truncate(&path, path_width)),
+        ])
+    })
+    .collect::<Vec<Line>>();
+    let sources = Paragraph::new(sources).style(Style::new().fg(app.theme.popup_fg));
+    frame.render_widget(sources, rects_popup[3]);
+
+    let text2 = "Connections are listed for review first";
+    let info_footer = Paragraph::new(text2).style(Style::new().fg(app.theme.popup_fg)).centered();
+    frame.render_widget(info_footer, rects_popup[5]);
 }
 
-pub fn render_error_popup(frame: &mut Frame, area: Rect, error_text: String) {
+pub fn render_import_path_popup(app: &mut App, frame: &mut Frame, area: Rect) {
+    let popup_block = Block::new();
+    frame.render_widget(Clear, import_path_popup_area(area));
+    frame.render_widget(popup_block, import_path_popup_area(area));
+
+    render_input(
+        app,
+        frame,
+        import_path_popup_area(area),
+        " File or folder to import ",
+        &app.import_path_input,
+        Focus::ImportPathField,
+    );
+}
+
+pub fn render_csv_columns_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let Some(csv_import) = &app.csv_import else {
+        return;
+    };
+    let title_text = " CSV Columns ";
+    let popup_block = popup_block(app, title_text).padding(Padding::horizontal(1));
+    let area = csv_columns_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let mut lines = vec![
+        Line::raw(columns::truncate(&csv_import.path.display().to_string(), inner.width as usize)),
+        Line::raw(format!(
+            "First row is a header: {}",
+            if csv_import.has_header { "yes" } else { "no" }
+        )),
+        Line::default(),
+        Line::styled(
+            format!("{:<10} {:<24} {}", "Field", "Column", "Example"),
+            Style::new().add_modifier(Modifier::BOLD),
+        ),
+    ];
+    for (i, field) in spreadsheet::FIELDS.iter().enumerate() {
+        let (column, example) = match csv_import.mapping[i] {
+            Some(column) => (csv_import.column_name(column), csv_import.example(column)),
+            None => ("not imported".to_string(), String::new()),
+        };
+        let text = format!(
+            "{:<10} {:<24} {}",
+            field,
+            columns::truncate(&format!("◂ {} ▸", column), 24),
+            columns::truncate(&example, (inner.width as usize).saturating_sub(36))
+        );
+        if i == app.csv_cursor {
+            lines.push(Line::styled(text, Style::new().fg(app.accent_color()).add_modifier(Modifier::REVERSED)));
+        } else {
+            lines.push(Line::raw(text));
+        }
+    }
+    lines.push(Line::default());
+    lines.push(Line::raw("Rows without a hostname or with an invalid port are skipped."));
+    frame.render_widget(Paragraph::new(lines).style(Style::new().fg(app.theme.popup_fg)), inner);
+}
+
+pub fn render_export_popup(app: &mut App, frame: &mut Frame, area: Rect) {
+    let title_text = " Export ";
+    let popup_block = popup_block(app, title_text).padding(Padding::horizontal(1));
+    let area = export_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let [_, format_area, scope_area, personal_area, input_area, status_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+    let (scope, indexes) = app.export_scopes.get(app.export_scope).cloned().unwrap_or_default();
+    let personal = if app.export_personal { "included" } else { "left out (usernames, key files)" };
+    for (label, value, area) in [
+        ("Format:   ", app.export_format.label().to_string(), format_area),
+        ("Export:   ", format!("{} ({})", scope, indexes.len()), scope_area),
+        ("Personal: ", personal.to_string(), personal_area),
+    ] {
+        let line = Line::from(vec![
+            Span::raw(label),
+            Span::styled(format!("◂ {} ▸", value), Style::new().fg(app.accent_color())),
+        ]);
+        frame.render_widget(Paragraph::new(line).style(Style::new().fg(app.theme.popup_fg)), area);
+    }
+    render_input(app, frame, input_area, "", &app.export_input, Focus::ExportPathField);
+    let status = match &app.export_status {
+        Some(Ok(text)) => Paragraph::new(text.as_str()).style(Style::new().fg(Color::Green)),
+        Some(Err(text)) => Paragraph::new(text.as_str()).style(Style::new().fg(app.theme.error)),
+        None => Paragraph::default(),
+    };
+    frame.render_widget(status, status_area);
+}
+
+pub fn render_import_preview_popup(app: &mut App, frame: &mut Frame, area: Rect) {
+    let Some(preview) = &app.import_preview else {
+        return;
+    };
+    let title_text = " Import Preview ";
+    let popup_block = popup_block(app, title_text).padding(Padding::horizontal(1));
+    let area = import_preview_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let mut summary = vec![
+        Line::raw(columns::truncate(&preview.source, inner.width as usize)),
+        Line::raw(match preview.replaced {
+            None => format!(
+                "{} found, {} selected, {} already in the list",
+                preview.candidates.len(),
+                preview.selected(),
+                preview.candidates.iter().filter(|candidate| candidate.duplicate).count()
+            ),
+            Some(replaced) => format!(
+                "{} found, {} selected, replacing the list: {} connections move to the trash",
+                preview.candidates.len(),
+                preview.selected(),
+                replaced
+            ),
+        }),
+    ];
+    summary.extend(preview.notes.iter().map(|note| Line::raw(note.as_str())));
+    let mut left_out = vec![];
+    if !preview.failures.is_empty() {
+        left_out.push(format!("Failed: {}", preview.failures.len()));
+    }
+    if !preview.untranslated.is_empty() {
+        left_out.push(format!("With options not imported: {}", preview.untranslated.len()));
+    }
+    if !left_out.is_empty() {
+        summary.push(Line::raw(format!("{} (listed in the report after the import)", left_out.join(", "))));
+    }
+    // Pattern blocks that set options of the highlighted connection, when the source is the ssh config.
+    let origin_height = preview.candidates.iter().map(|candidate| candidate.origin.len()).max().unwrap_or(0).min(4);
+    let vertical_popup = &Layout::vertical([
+        Constraint::Length(summary.len() as u16),
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(if origin_height > 0 { origin_height as u16 + 2 } else { 0 }),
+    ]);
+    let rects_popup = vertical_popup.split(inner);
+    frame.render_widget(Paragraph::new(summary).style(Style::new().fg(app.theme.popup_fg)), rects_popup[0]);
+    if origin_height > 0 {
+        let origin = app
+            .import_state
+            .selected()
+            .and_then(|row| preview.candidates.get(row))
+            .map(|candidate| candidate.origin.as_slice())
+            .unwrap_or_default();
+        let mut lines = vec![Line::default(), Line::styled("Options from patterns:", Style::new().add_modifier(Modifier::BOLD))];
+        if origin.is_empty() {
+            lines.push(Line::raw("none"));
+        }
+        lines.extend(origin.iter().map(|line| Line::raw(columns::truncate(line, inner.width as usize))));
+        frame.render_widget(Paragraph::new(lines).style(Style::new().fg(app.theme.popup_fg)), rects_popup[3]);
+    }
+
+    if preview.candidates.is_empty() {
+        let text = Paragraph::new("No connections found").style(Style::new().fg(app.theme.popup_fg)).centered();
+        frame.render_widget(text, rects_popup[2]);
+        return;
+    }
+    let header = ["", " Name", " Group", " Username", " Hostname", " Port", " Status"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::default().fg(app.theme.header_fg).bg(app.theme.header_bg));
+    let rows = preview.candidates.iter().map(|candidate| {
+        let connection = &candidate.connection;
+        let status = if candidate.duplicate { "duplicate" } else { "new" };
+        [
+            if candidate.selected { "[x]" } else { "[ ]" },
+            &connection.server_name,
+            &connection.group_name,
+            &connection.username,
+            &connection.hostname,
+            &connection.port,
+            status,
+        ]
+        .into_iter()
+        .map(|content| Cell::from(format!(" {}", content)))
+        .collect::<Row>()
+    });
+    let constraints = [
+        Constraint::Length(4),
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Length(6),
+        Constraint::Length(10),
+    ];
+    let selection_color = app.theme.selection.unwrap_or(app.accent_color());
+    let t = Table::new(rows, constraints)
+        .header(header)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED).fg(selection_color))
+        .style(Style::new().fg(app.theme.popup_fg));
+    frame.render_stateful_widget(t, rects_popup[2], &mut app.import_state);
+}
+
+pub fn render_error_popup(app: &App, frame: &mut Frame, area: Rect, error_text: String) {
     let title_text = " Error ";
-    let popup_block = Block::bordered().title(title_text).title_alignment(Alignment::Center);
+    let popup_block = popup_block(app, title_text);
     let area = error_popup_area(area);
     let inner = popup_block.inner(area);
     frame.render_widget(Clear, area);
@@ -293,8 +696,8 @@ pub fn render_error_popup(frame: &mut Frame, area: Rect, error_text: String) {
     let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Length(2)]);
     let rects_popup = vertical_popup.split(inner);
 
-    let text1 = format!("{}", error_text);
-    let info_footer = Paragraph::new(text1).style(Style::new().fg(Color::White)).centered();
+    let text1 = error_text.to_string();
+    let info_footer = Paragraph::new(text1).style(Style::new().fg(app.theme.popup_fg)).centered();
     frame.render_widget(info_footer, rects_popup[1]);
 }
 
@@ -313,6 +716,38 @@ pub fn render_run_popup(app: &mut App, frame: &mut Frame, area: Rect) {
     );
 }
 
+// The passphrase is masked; the cursor always stays at its end.
+pub fn render_unlock_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let title_text = if app.unlock_profile == profile::DEFAULT_PROFILE {
+        " Unlock ".to_string()
+    } else {
+        format!(" Unlock {} ", profile_label(&app.unlock_profile))
+    };
+    let popup_block = popup_block(app, &title_text).padding(Padding::horizontal(1));
+    let area = unlock_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let [_, text_area, input_area, error_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+    let text = Paragraph::new("Passphrase of the encrypted store:").style(Style::new().fg(app.theme.popup_fg));
+    frame.render_widget(text, text_area);
+    let masked = "•".repeat(app.passphrase_input.value().chars().count());
+    let width = input_area.width.saturating_sub(2) as usize;
+    let visible: String = masked.chars().skip(masked.chars().count().saturating_sub(width.saturating_sub(1))).collect();
+    let cursor = visible.chars().count() as u16;
+    frame.render_widget(Paragraph::new(visible).block(Block::bordered()), input_area);
+    frame.set_cursor_position((input_area.x + 1 + cursor, input_area.y + 1));
+    let error = Paragraph::new(app.unlock_error.clone()).style(Style::new().fg(app.theme.error));
+    frame.render_widget(error, error_area);
+}
+
 pub fn render_search(app: &mut App, frame: &mut Frame, area: Rect) {
     let popup_block = Block::new();
     frame.render_widget(popup_block, search_area(area));
@@ -326,18 +761,409 @@ pub fn render_search(app: &mut App, frame: &mut Frame, area: Rect) {
     );
 }
 
-pub fn render_options_popup(frame: &mut Frame, area: Rect) {
+pub fn render_options_popup(app: &App, frame: &mut Frame, area: Rect) {
     let title_text = " Options ";
-    let popup_block = Block::bordered().title(title_text).title_alignment(Alignment::Center);
+    let popup_block = popup_block(app, title_text);
     let area = options_popup_area(area);
     let inner = popup_block.inner(area);
     frame.render_widget(Clear, area);
     frame.render_widget(popup_block, area);
 
-    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Length(3)]);
+    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Length(7)]);
     let rects_popup = vertical_popup.split(inner);
 
-    let text = format!("Press [↑] or [↓] to change row height\n\nPress [←] or [→] to change color     ");
-    let info_footer = Paragraph::new(text).style(Style::new().fg(Color::White)).centered();
+    let theme_text =
+        format!("Press [{}] to change theme ({})", app.keybindings.label(Action::OptionsTheme), app.theme_name);
+    let text = format!(
+        "Press [↑] or [↓] to change row height\n\nPress [←] or [→] to change color     \n\n{:<37}\n\n{:<37}",
+        theme_text,
+        format!("Press [{}] to configure columns", app.keybindings.label(Action::OptionsColumns))
+    );
+    let info_footer = Paragraph::new(text).style(Style::new().fg(app.theme.popup_fg)).centered();
     frame.render_widget(info_footer, rects_popup[1]);
-}
\ No newline at end of file
+}
+
+pub fn render_columns_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let title_text = " Columns ";
+    let popup_block = popup_block(app, title_text).padding(Padding::horizontal(1));
+    let area = columns_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
+    let rects_popup = vertical_popup.split(inner);
+
+    let mut lines = vec![];
+    for (i, layout) in app.columns.iter().enumerate() {
+        let checkbox = if layout.visible { "[x]" } else { "[ ]" };
+        let max_width = layout.max_width.map_or("fill".to_string(), |max_width| max_width.to_string());
+        let text = format!(
+            "{} {:<12} min {:<4} max {:<4}",
+            checkbox,
+            layout.column.title(),
+            layout.min_width,
+            max_width
+        );
+        if i == app.column_cursor {
+            lines.push(Line::styled(text, Style::new().fg(app.accent_color()).add_modifier(Modifier::REVERSED)));
+        } else {
+            lines.push(Line::raw(text));
+        }
+    }
+    let list = Paragraph::new(lines).style(Style::new().fg(app.theme.popup_fg)).centered();
+    frame.render_widget(list, rects_popup[1]);
+}
+
+pub fn render_confirm_popup(app: &App, frame: &mut Frame, area: Rect) {
+    const LISTED: usize = 5;
+    let count = app.confirm_indexes.len();
+    let (title_text, question) = match app.confirm_action {
+        ConfirmAction::Delete if count == 1 => (" Delete ", "Move this connection to the trash?".to_string()),
+        ConfirmAction::Delete => (" Delete ", format!("Move {} connections to the trash?", count)),
+        ConfirmAction::Purge if count == 1 => (" Purge ", "Permanently delete this connection?".to_string()),
+        ConfirmAction::Purge => (" Purge ", format!("Permanently delete {} connections?", count)),
+    };
+    let mut lines = vec![Line::raw(question), Line::default()];
+    for &i in app.confirm_indexes.iter().take(LISTED) {
+        let connection = &app.ssh_connections[i];
+        let name = if connection.group_name.is_empty() {
+            connection.server_name.clone()
+        } else {
+            format!("{} ({})", connection.server_name, connection.group_name)
+        };
+        lines.push(Line::styled(name, Style::new().fg(app.accent_color())));
+    }
+    if count > LISTED {
+        lines.push(Line::raw(format!("and {} more", count - LISTED)));
+    }
+    let popup_block = popup_block(app, title_text);
+    let area = confirm_popup_area(area, lines.len() as u16);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
+    let rects_popup = vertical_popup.split(inner);
+    let text = Paragraph::new(lines).style(Style::new().fg(app.theme.popup_fg)).centered();
+    frame.render_widget(text, rects_popup[1]);
+}
+
+fn profile_label(name: &str) -> String {
+    if name == profile::MERGED_PROFILE {
+        "all profiles (read-only)".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+pub fn render_profiles_popup(app: &mut App, frame: &mut Frame, area: Rect) {
+    let title_text = " Profiles ";
+    let popup_block = popup_block(app, title_text);
+    let entries = app.profile_entries();
+    let area = profiles_popup_area(area, entries.len() as u16);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|name| {
+            let marker = if *name == app.profile { "● " } else { "  " };
+            ListItem::new(format!(" {}{}", marker, profile_label(name)))
+        })
+        .collect();
+    let selection_color = app.theme.selection.unwrap_or(app.accent_color());
+    let list = List::new(items)
+        .style(Style::new().fg(app.theme.popup_fg))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED).fg(selection_color));
+    let [_, list_area] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+    frame.render_stateful_widget(list, list_area, &mut app.profiles_state);
+}
+
+pub fn render_trash_popup(app: &mut App, frame: &mut Frame, area: Rect) {
+    let title_text = " Trash ";
+    let popup_block = popup_block(app, title_text);
+    let area = trash_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let trash = app.trash_indexes();
+    if trash.is_empty() {
+        let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Length(1)]);
+        let rects_popup = vertical_popup.split(inner);
+        let text = Paragraph::new("The trash is empty").style(Style::new().fg(app.theme.popup_fg)).centered();
+        frame.render_widget(text, rects_popup[1]);
+        return;
+    }
+    let header = [" Name", " Group", " Hostname", " Deleted"]
+        .into_iter()
+        .map(Cell::from)
+        .collect::<Row>()
+        .style(Style::default().fg(app.theme.header_fg).bg(app.theme.header_bg));
+    let rows = trash.iter().map(|&i| {
+        let connection = &app.ssh_connections[i];
+        let deleted = connection
+            .deleted
+            .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
+            .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        [
+            &connection.server_name,
+            &connection.group_name,
+            &connection.hostname,
+            &deleted,
+        ]
+        .into_iter()
+        .map(|content| Cell::from(format!(" {}", content)))
+        .collect::<Row>()
+    });
+    let constraints = [
+        Constraint::Fill(2),
+        Constraint::Fill(1),
+        Constraint::Fill(2),
+        Constraint::Length(18),
+    ];
+    let selection_color = app.theme.selection.unwrap_or(app.accent_color());
+    let t = Table::new(rows, constraints)
+        .header(header)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED).fg(selection_color))
+        .style(Style::new().fg(app.theme.popup_fg));
+    frame.render_stateful_widget(t, inner, &mut app.trash_state);
+}
+
+pub fn render_detail_panel(app: &App, frame: &mut Frame, area: Rect) {
+    let border_color = app.theme.border.unwrap_or(app.accent_color());
+    let label_style = Style::new().fg(app.accent_color());
+    let mut lines: Vec<Line> = vec![];
+    if let Some(connection) = app.highlighted_connection() {
+        let fields = [
+            ("Name", &connection.server_name),
+            ("Group", &connection.group_name),
+            ("Username", &connection.username),
+            ("Hostname", &connection.hostname),
+            ("Port", &connection.port),
+            ("Options", &connection.options),
+        ];
+        for (label, value) in fields {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{label}: "), label_style),
+                Span::raw(value.as_str()),
+            ]));
+        }
+        lines.push(Line::from(vec![
+            Span::styled("Source: ", label_style),
+            Span::raw(columns::source(connection)),
+        ]));
+        if let Some(source) = &connection.source {
+            let path = paths::abbreviate_home(Path::new(&source.path));
+            let location = match source.line {
+                Some(line) => format!("{}:{}", path, line),
+                None => path,
+            };
+            let time = chrono::DateTime::from_timestamp(source.imported, 0)
+                .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_default();
+            lines.push(Line::from(vec![Span::styled("Imported from: ", label_style), Span::raw(location)]));
+            lines.push(Line::from(vec![
+                Span::styled("Imported as: ", label_style),
+                Span::raw(format!("{} ({})", source.alias, time)),
+            ]));
+        }
+        let status = columns::status(connection);
+        if !status.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled("Status: ", label_style),
+                Span::styled(status, Style::new().fg(app.theme.error)),
+            ]));
+        }
+        lines.push(Line::default());
+        lines.push(Line::styled("Command:", label_style));
+        lines.push(Line::raw(command::SshCommand::new(connection).to_shell_string()));
+        lines.push(Line::default());
+        lines.push(Line::styled("Notes:", label_style));
+        for note in connection.notes.lines() {
+            lines.push(Line::raw(note));
+        }
+        lines.push(Line::default());
+        if connection.shared.is_some() {
+            lines.push(Line::styled("History: not kept for shared connections", label_style));
+        } else {
+            lines.push(Line::styled(
+                format!("History (last {} connections):", connection.history.len()),
+                label_style,
+            ));
+            for timestamp in connection.history.iter().rev() {
+                if let Some(time) = chrono::DateTime::from_timestamp(*timestamp, 0) {
+                    let time = time.with_timezone(&chrono::Local);
+                    lines.push(Line::raw(time.format("%Y-%m-%d %H:%M").to_string()));
+                }
+            }
+        }
+    }
+    let panel = Paragraph::new(lines)
+        .style(Style::new().fg(app.theme.text).bg(app.theme.background))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::bordered()
+                .title(" Details ")
+                .border_style(Style::new().fg(border_color)),
+        );
+    frame.render_widget(panel, area);
+}
+
+pub fn render_preview_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let title_text = " SSH command ";
+    let popup_block = popup_block(app, title_text);
+    let area = preview_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Min(3), Constraint::Length(1)]);
+    let rects_popup = vertical_popup.split(inner);
+
+    if let Some(i) = app.get_row_index() {
+        let command_line = command::SshCommand::new(&app.ssh_connections[i]).to_shell_string();
+        let command_text = Paragraph::new(command_line)
+            .style(Style::new().fg(app.theme.popup_fg))
+            .wrap(Wrap { trim: false })
+            .block(Block::new().padding(Padding::horizontal(1)));
+        frame.render_widget(command_text, rects_popup[1]);
+    }
+
+    let status_text = if app.preview_copied {
+        "Copied to clipboard".to_string()
+    } else {
+        format!("Press [{}] to copy (OSC 52)", app.keybindings.label(Action::CopyCommand))
+    };
+    let status = Paragraph::new(status_text).style(Style::new().fg(app.theme.popup_fg)).centered();
+    frame.render_widget(status, rects_popup[2]);
+}
+
+pub fn render_sync_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let title_text = " Git sync ";
+    let mut lines: Vec<Line> = app.sync_status.iter().map(|line| Line::raw(line.clone())).collect();
+    if !app.sync_message.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled(app.sync_message.clone(), Style::new().fg(app.accent_color())));
+    }
+    let popup_block = popup_block(app, title_text).padding(Padding::horizontal(1));
+    let area = sync_popup_area(area, lines.len() as u16);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
+    let rects_popup = vertical_popup.split(inner);
+    let text = Paragraph::new(lines)
+        .style(Style::new().fg(app.theme.popup_fg))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(text, rects_popup[1]);
+}
+
+// Counts of the import, then what failed, what was left out and the files read.
+pub fn render_import_report_popup(app: &App, frame: &mut Frame, area: Rect) {
+    let Some(report) = &app.import_report else {
+        return;
+    };
+    let popup_block = popup_block(app, " Import Report ").padding(Padding::horizontal(1));
+    let area = import_report_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let mut lines = vec![];
+    for (title, section) in report.sections() {
+        lines.push(Line::styled(title, Style::new().fg(app.accent_color()).add_modifier(Modifier::BOLD)));
+        lines.extend(
+            section
+                .iter()
+                .map(|line| Line::raw(columns::truncate(&format!("  {}", line), inner.width as usize))),
+        );
+        lines.push(Line::default());
+    }
+    let text = Paragraph::new(lines)
+        .style(Style::new().fg(app.theme.popup_fg))
+        .scroll((app.import_report_scroll, 0));
+    frame.render_widget(text, inner);
+}
+
+// Lists every conflicting connection with the differing fields side by side and the side that will be kept.
+pub fn render_merge_popup(app: &App, frame: &mut Frame, area: Rect) {
+    const VALUE_WIDTH: usize = 36;
+    let Some(merge) = &app.merge else {
+        return;
+    };
+    let title_text = format!(" Merge {} ", merge.upstream);
+    let count = merge.conflicts().count();
+    let mut lines = vec![
+        Line::raw(if count == 1 {
+            "1 connection was changed both here and remotely.".to_string()
+        } else {
+            format!("{} connections were changed both here and remotely.", count)
+        }),
+        Line::raw("All other changes are merged automatically."),
+        Line::default(),
+        Line::styled(
+            format!("  {:<10}{:<width$}  {}", "", "local", "remote", width = VALUE_WIDTH),
+            Style::new().add_modifier(Modifier::BOLD),
+        ),
+    ];
+    let mut selected_lines = (0, 0);
+    for (i, (local, remote, keep_local)) in merge.conflicts().enumerate() {
+        let start = lines.len();
+        let name = local.or(remote).map(|connection| connection.server_name.clone());
+        let marker = if i == app.merge_selected { "› " } else { "  " };
+        let side = if keep_local { "keep local" } else { "take remote" };
+        let mut name_style = Style::new().fg(app.accent_color()).add_modifier(Modifier::BOLD);
+        if i == app.merge_selected {
+            name_style = name_style.add_modifier(Modifier::REVERSED);
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}{}", marker, name.unwrap_or_default()), name_style),
+            Span::raw(format!("  ({})", side)),
+        ]));
+        let changes = match (local, remote) {
+            (Some(local), Some(remote)) => sync::field_changes(local, remote),
+            (Some(_), None) => vec![("", "changed".to_string(), "removed".to_string())],
+            _ => vec![("", "removed".to_string(), "changed".to_string())],
+        };
+        // The side that is not kept is dimmed.
+        let (local_style, remote_style) = if keep_local {
+            (Style::new(), Style::new().add_modifier(Modifier::DIM))
+        } else {
+            (Style::new().add_modifier(Modifier::DIM), Style::new())
+        };
+        for (field, local_value, remote_value) in changes {
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {:<10}", field)),
+                Span::styled(
+                    format!("{:<width$}", columns::truncate(&local_value, VALUE_WIDTH), width = VALUE_WIDTH),
+                    local_style,
+                ),
+                Span::raw("  "),
+                Span::styled(columns::truncate(&remote_value, VALUE_WIDTH), remote_style),
+            ]));
+        }
+        if i == app.merge_selected {
+            selected_lines = (start, lines.len());
+        }
+    }
+    let popup_block = popup_block(app, &title_text).padding(Padding::horizontal(1));
+    let area = merge_popup_area(area);
+    let inner = popup_block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup_block, area);
+
+    let vertical_popup = &Layout::vertical([Constraint::Length(1), Constraint::Min(0)]);
+    let rects_popup = vertical_popup.split(inner);
+    // Scrolls just enough to show the whole selected conflict.
+    let height = rects_popup[1].height as usize;
+    let scroll = selected_lines.1.saturating_sub(height).min(selected_lines.0);
+    let text = Paragraph::new(lines)
+        .style(Style::new().fg(app.theme.popup_fg))
+        .scroll((scroll as u16, 0));
+    frame.render_widget(text, rects_popup[1]);
+}